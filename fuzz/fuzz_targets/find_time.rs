@@ -0,0 +1,27 @@
+#![no_main]
+use chrono::{Duration, FixedOffset, TimeZone};
+use libfuzzer_sys::fuzz_target;
+
+//the scheduler doesn't need anything else from the bot, so pull it in the same way bonk-editor does the bonker
+#[path = "../../src/scheduler.rs"]
+mod scheduler;
+
+//libfuzzer's panic hook aborts on any panic, even one find_time catches from date_time_parser, so they all get reported
+fuzz_target!(|data: &[u8]| {
+    //the first few bytes pick now and the timezone, the rest is what someone typed
+    if data.len() < 6 {
+        return
+    }
+    let seconds = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as i64 * 2;
+    let offset = (i16::from_le_bytes([data[4], data[5]]) % (14 * 60)) as i32 * 60;
+    let text = match std::str::from_utf8(&data[6..]) {
+        Ok(text) => text,
+        Err(_) => return,
+    };
+    let now = FixedOffset::east(offset).timestamp(seconds, 0);
+    if let Some(found) = scheduler::find_time(text, &now) {
+        let found = now.timezone().from_local_datetime(&found).unwrap();
+        assert!(found > now, "{:?} gave {} at {}", text, found, now);
+        assert!(found <= now + Duration::days(scheduler::FURTHEST_DAYS), "{:?} gave {} at {}", text, found, now);
+    }
+});
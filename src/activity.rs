@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::error::Error;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use serenity::model::gateway::Activity;
+use tracing::error;
+
+//longest activity discord will show
+const MAX_LENGTH: usize = 128;
+
+//what can go in {braces} in an activity, filled in when it's picked
+pub const VARIABLES: &[(&str, &str)] = &[
+    ("guild_count", "how many servers the bot is in"),
+    ("next_reminder", "how long until the next reminder goes off"),
+    ("top_bonker", "whoever has handed out the most bonks"),
+    ("bonk_count", "how many bonks have ever been handed out"),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    #[default]
+    Playing,
+    Listening,
+    //serenity 0.10 has no watching, so that can't be offered until it's upgraded
+    Competing,
+    //needs a twitch or youtube url
+    Streaming,
+}
+
+//one entry of activities.json, a plain string is playing it with the defaults
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "EntryFile", into = "EntryFile")]
+pub struct Entry {
+    pub text: String,
+    pub kind: Kind,
+    //relative chance of being picked
+    pub weight: u32,
+    pub url: Option<String>,
+    //only picked from the first hour until the second (local time), wrapping past midnight if the second is smaller
+    pub hours: Option<[u32; 2]>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum EntryFile {
+    Text(String),
+    Full {
+        text: String,
+        #[serde(default)]
+        kind: Kind,
+        #[serde(default = "default_weight")]
+        weight: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hours: Option<[u32; 2]>,
+    },
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl From<EntryFile> for Entry {
+    fn from(file: EntryFile) -> Self {
+        match file {
+            EntryFile::Text(text) => Entry::new(text),
+            EntryFile::Full {text, kind, weight, url, hours} => Entry {text, kind, weight, url, hours},
+        }
+    }
+}
+
+//entries with nothing but text are written back as plain strings, so the file stays easy to edit by hand
+impl From<Entry> for EntryFile {
+    fn from(entry: Entry) -> Self {
+        if entry == Entry::new(entry.text.clone()) {
+            EntryFile::Text(entry.text)
+        }
+        else {
+            EntryFile::Full {text: entry.text, kind: entry.kind, weight: entry.weight, url: entry.url, hours: entry.hours}
+        }
+    }
+}
+
+impl Entry {
+    pub fn new<S: Into<String>>(text: S) -> Entry {
+        Entry {text: text.into(), kind: Kind::Playing, weight: 1, url: None, hours: None}
+    }
+
+    pub fn active_at(&self, hour: u32) -> bool {
+        match self.hours {
+            None => true,
+            Some([from, until]) if from <= until => hour >= from && hour < until,
+            Some([from, until]) => hour >= from || hour < until,
+        }
+    }
+
+    //"[playing|listening|competing] <text>" or "streaming <url> <text>", playing if there's no kind
+    pub fn parse(args: &[String]) -> Result<Entry, String> {
+        let kind = args.first().and_then(|arg| serde_json::from_value::<Kind>(serde_json::Value::String(arg.to_lowercase())).ok());
+        let rest = if kind.is_some() {&args[1..]} else {args};
+        let (url, rest) = match kind {
+            Some(Kind::Streaming) => match rest.split_first() {
+                Some((url, rest)) if url.starts_with("https://") => (Some(url.clone()), rest),
+                _ => return Err("streaming needs a link first, then the text".to_string()),
+            },
+            _ => (None, rest),
+        };
+        let text = rest.join(" ");
+        if text.trim().is_empty() {
+            return Err("what should it say?".to_string())
+        }
+        Ok(Entry {kind: kind.unwrap_or_default(), url, ..Entry::new(text)})
+    }
+
+    //how it looks in a list, e.g. "listening to `{bonk_count} bonks` (weight 2, 22:00-6:00)"
+    pub fn describe(&self) -> String {
+        let verb = match self.kind {
+            Kind::Playing => "playing",
+            Kind::Listening => "listening to",
+            Kind::Competing => "competing in",
+            Kind::Streaming => "streaming",
+        };
+        let mut extras = Vec::new();
+        if self.weight != 1 {
+            extras.push(format!("weight {}", self.weight));
+        }
+        if let Some([from, until]) = self.hours {
+            extras.push(format!("{}:00-{}:00", from, until));
+        }
+        if extras.is_empty() {
+            format!("{} `{}`", verb, self.text)
+        }
+        else {
+            format!("{} `{}` ({})", verb, self.text, extras.join(", "))
+        }
+    }
+
+    //the variables it uses, in order
+    pub fn variables(&self) -> Vec<&str> {
+        self.text.split('{').skip(1).filter_map(|part| part.split_once('}').map(|(name, _)| name)).collect()
+    }
+
+    pub fn to_activity(&self, text: &str) -> Activity {
+        match self.kind {
+            Kind::Playing => Activity::playing(text),
+            Kind::Listening => Activity::listening(text),
+            Kind::Competing => Activity::competing(text),
+            Kind::Streaming => Activity::streaming(text, self.url.as_deref().unwrap_or_default()),
+        }
+    }
+}
+
+//a weighted pick out of the entries that are on at this hour
+pub fn pick(entries: &[Entry], hour: u32) -> Option<&Entry> {
+    let active: Vec<&Entry> = entries.iter().filter(|entry| entry.weight > 0 && entry.active_at(hour)).collect();
+    active.choose_weighted(&mut rand::thread_rng(), |entry| entry.weight).ok().copied()
+}
+
+//swaps {name} for its value, anything unknown is left as it is
+pub fn fill(text: &str, values: &HashMap<&str, String>) -> String {
+    let mut filled = text.to_string();
+    for (name, value) in values {
+        filled = filled.replace(&format!("{{{}}}", name), value);
+    }
+    filled
+}
+
+pub fn save_entries(dir: &str, entries: &[Entry]) -> Result<(), Box<dyn Error>> {
+    let json_content = serde_json::to_string_pretty(entries)?;
+    std::fs::write(format!("{}/activities.json", dir), json_content)?;
+    Ok(())
+}
+
+//the status set with !activity set or pin, kept in the data folder so it survives a restart
+pub fn load_pinned(dir: &str) -> Option<Entry> {
+    match std::fs::read_to_string(format!("{}/pinned_activity.json", dir)) {
+        Ok(string) => match serde_json::from_str(&string) {
+            Ok(data) => data,
+            Err(e) => {
+                error!(error = %e, "could not parse pinned_activity.json");
+                None
+            },
+        },
+        Err(_) => None,
+    }
+}
+
+pub fn save_pinned(dir: &str, pinned: Option<&Entry>) -> Result<(), Box<dyn Error>> {
+    let json_content = serde_json::to_string(&pinned)?;
+    std::fs::write(format!("{}/pinned_activity.json", dir), json_content)?;
+    Ok(())
+}
+
+//roughly how long something is away, the two biggest units
+pub fn describe_wait(wait: chrono::Duration) -> String {
+    let minutes = wait.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    }
+    else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    }
+    else {
+        format!("{}m", minutes)
+    }
+}
+
+//problems with the entries, for the asset checks
+pub fn check(entries: &[Entry]) -> Vec<String> {
+    let mut errors = Vec::new();
+    if entries.is_empty() {
+        errors.push("activities.json has no activities".to_string());
+    }
+    else if !(0..24).any(|hour| pick(entries, hour).is_some()) {
+        errors.push("no activity can ever be picked (they all have weight 0)".to_string());
+    }
+    for entry in entries {
+        if entry.text.trim().is_empty() {
+            errors.push("activities.json has a blank activity".to_string());
+        }
+        else if entry.text.chars().count() > MAX_LENGTH {
+            errors.push(format!("activity '{}' is longer than {} characters", entry.text, MAX_LENGTH));
+        }
+        for variable in entry.variables() {
+            if !VARIABLES.iter().any(|(name, _)| *name == variable) {
+                errors.push(format!("activity '{}' uses {{{}}}, which isn't a variable", entry.text, variable));
+            }
+        }
+        if entry.kind == Kind::Streaming && entry.url.is_none() {
+            errors.push(format!("streaming activity '{}' needs a url", entry.text));
+        }
+        if entry.hours.is_some_and(|[from, until]| from > 23 || until > 23 || from == until) {
+            errors.push(format!("activity '{}' needs two different hours from 0-23", entry.text));
+        }
+    }
+    errors
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::time::SystemTime;
+use image::{DynamicImage, GenericImageView};
+use crate::activity::{self, Entry};
+use crate::bonker::{self, ImageData, Mask};
+
+//everything read out of the assets folder, checked and decoded once so commands don't touch the disk
+//a reload builds a whole new one and swaps it in, so nothing ever sees half of an update
+#[derive(Default)]
+pub struct Assets {
+    pub activities: Vec<Entry>,
+    pub bonks: Vec<ImageData>,
+    //file name -> decoded image, for templates, masks and the label
+    images: HashMap<String, DynamicImage>,
+}
+
+impl Assets {
+    pub fn image(&self, name: &str) -> Result<&DynamicImage, String> {
+        self.images.get(name).ok_or(format!("{} isn't loaded", name))
+    }
+
+    pub fn render_bonk(&self, avatar: &DynamicImage, meta: &ImageData) -> Result<DynamicImage, String> {
+        let mask = match &meta.mask {
+            Mask::Image(path) => Some(self.image(path)?),
+            _ => None,
+        };
+        bonker::composite_bonk(avatar, self.image(&meta.name)?, self.image("bonklabel.png")?, mask, meta)
+    }
+
+    //every file these came from, new templates show up through a change to bonk_locations.json
+    pub fn files(&self) -> Vec<String> {
+        let mut files = vec!["activities.json".to_string(), "bonk_locations.json".to_string()];
+        files.extend(self.images.keys().cloned());
+        files
+    }
+
+    //thumbnails of every template, in the same order as bonks
+    pub fn gallery(&self, thumb_size: u32, columns: u32) -> Result<DynamicImage, String> {
+        let thumbnails = self.bonks.iter()
+            .map(|meta| self.image(&meta.name).map(|image| image.thumbnail(thumb_size, thumb_size)))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(bonker::contact_sheet(&thumbnails, thumb_size, columns))
+    }
+}
+
+//reads and checks everything, any problem means none of it is used
+pub fn load_assets(dir: &str) -> Result<Assets, Vec<String>> {
+    let mut errors = Vec::new();
+    let activities = match read_json::<Vec<Entry>>(dir, "activities.json") {
+        Ok(activities) => activities,
+        Err(e) => {
+            errors.push(e);
+            Vec::new()
+        },
+    };
+    let bonks = match read_json::<Vec<ImageData>>(dir, "bonk_locations.json") {
+        Ok(bonks) => bonks,
+        Err(e) => {
+            errors.push(e);
+            Vec::new()
+        },
+    };
+    let mut images = HashMap::new();
+    let mut wanted = vec!["bonklabel.png".to_string()];
+    for meta in &bonks {
+        wanted.push(meta.name.clone());
+        if let Mask::Image(path) = &meta.mask {
+            wanted.push(path.clone());
+        }
+    }
+    for name in wanted {
+        if images.contains_key(&name) {
+            continue
+        }
+        match image::open(format!("{}/{}", dir, name)) {
+            Ok(image) => {
+                images.insert(name, image);
+            },
+            Err(e) => errors.push(format!("could not open {}: {}", name, e)),
+        }
+    }
+    errors.extend(activity::check(&activities));
+    errors.extend(check_bonks(&bonks, &images));
+    if errors.is_empty() {
+        Ok(Assets {activities, bonks, images})
+    }
+    else {
+        Err(errors)
+    }
+}
+
+//the whole file or an error, for things that shouldn't be half loaded
+pub fn read_json<T: serde::de::DeserializeOwned>(dir: &str, file: &str) -> Result<T, String> {
+    match std::fs::read_to_string(format!("{}/{}", dir, file)) {
+        Ok(string) => serde_json::from_str(&string).map_err(|e| format!("could not parse {}: {}", file, e)),
+        Err(e) => Err(format!("could not read {}: {}", file, e)),
+    }
+}
+
+//read_json for the files the bot writes itself, which aren't there until there's something in them
+pub fn read_saved_json<T: serde::de::DeserializeOwned + Default>(dir: &str, file: &str) -> Result<T, String> {
+    match std::fs::read_to_string(format!("{}/{}", dir, file)) {
+        Ok(string) => serde_json::from_str(&string).map_err(|e| format!("could not parse {}: {}", file, e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("could not read {}: {}", file, e)),
+    }
+}
+
+//templates whose image didn't load are already reported, so they're only checked for the things that don't need it
+pub fn check_bonks(bonks: &[ImageData], images: &HashMap<String, DynamicImage>) -> Vec<String> {
+    let mut errors = Vec::new();
+    if !bonks.iter().any(|meta| meta.weight > 0) {
+        errors.push("no bonk template can be picked at random (they all have weight 0)".to_string());
+    }
+    for (i, meta) in bonks.iter().enumerate() {
+        if bonks[..i].iter().any(|other| other.short_name().eq_ignore_ascii_case(meta.short_name())) {
+            errors.push(format!("there's more than one bonk template named {}", meta.short_name()));
+        }
+        if meta.bonkee_width == 0 || meta.bonkee_height == 0 || meta.bonk_label_width == 0 || meta.bonk_label_height == 0 {
+            errors.push(format!("{} has a zero sized avatar or label", meta.name));
+        }
+        if !meta.rotation.is_finite() || meta.perspective.is_some_and(|corners| corners.iter().flatten().any(|n| !n.is_finite())) {
+            errors.push(format!("{} has a rotation or perspective that isn't a number", meta.name));
+        }
+        //labels are sometimes pushed off the image on purpose to hide them, so only the avatar has to be on it
+        if let (Some(image), None) = (images.get(&meta.name), meta.perspective) {
+            let (width, height) = image.dimensions();
+            if meta.bonkee_x >= width || meta.bonkee_y >= height {
+                errors.push(format!("{}'s avatar starts outside the {}x{} image", meta.name, width, height));
+            }
+        }
+    }
+    errors
+}
+
+//newest change to any of the files, for noticing edits. the bot writes its own stats into the same folder, so only what assets are made of is looked at
+pub fn last_modified(dir: &str, files: &[String]) -> Option<SystemTime> {
+    files.iter()
+        .filter_map(|file| std::fs::metadata(format!("{}/{}", dir, file)).ok()?.modified().ok())
+        .max()
+}
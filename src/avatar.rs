@@ -0,0 +1,99 @@
+use std::io::Cursor;
+use serenity::{
+    model::user::User,
+    utils::parse_emoji,
+};
+use crate::command_error::CommandError;
+use crate::invocation::Invocation;
+use crate::surface::ChatSurface;
+
+//more than this and the tiles get too small to recognize anyone
+pub const MAX_BONKEES: usize = 9;
+//avatars get shrunk to fit the template anyway, this is plenty
+pub const MAX_AVATAR_SIDE: u32 = 4096;
+const MAX_AVATAR_ALLOC: u64 = 256 * 1024 * 1024;
+
+//something to put under the bonk, user is set when it's someone's avatar so the bonk can be recorded
+#[derive(Debug, Clone)]
+pub struct Bonkee {
+    pub user: Option<u64>,
+    pub image_url: String,
+}
+
+//true for arguments that name a bonkee rather than a template
+pub fn is_bonkee_arg(arg: &str) -> bool {
+    arg.starts_with("<@") || is_image_url(arg) || parse_emoji(arg).is_some()
+}
+
+//links can be wrapped in <> to stop discord from embedding them
+fn is_image_url(arg: &str) -> bool {
+    let arg = arg.trim_start_matches('<');
+    arg.starts_with("https://") || arg.starts_with("http://")
+}
+
+//everything the message points at, in order: attached images, custom emoji, image links, then mentioned users
+//if none of those are there it falls back to the author of the message being replied to, then the author
+pub async fn find_bonkees(chat: &dyn ChatSurface, invocation: &Invocation) -> Vec<Bonkee> {
+    let mut bonkees = Vec::new();
+    for url in &invocation.attachments {
+        bonkees.push(Bonkee {user: None, image_url: url.clone()});
+    }
+    for arg in &invocation.args {
+        if let Some(emoji) = parse_emoji(arg) {
+            //animated emoji are also served as png, which gets us the first frame
+            bonkees.push(Bonkee {user: None, image_url: format!("https://cdn.discordapp.com/emojis/{}.png", emoji.id)});
+        }
+        else if is_image_url(arg) {
+            bonkees.push(Bonkee {user: None, image_url: arg.trim_matches(|c| c == '<' || c == '>').to_string()});
+        }
+    }
+    for user in &invocation.users {
+        bonkees.push(user_bonkee(chat, invocation.guild, user).await);
+    }
+    if bonkees.is_empty() {
+        let user = invocation.replied_to.as_ref().unwrap_or(&invocation.author);
+        bonkees.push(user_bonkee(chat, invocation.guild, user).await);
+    }
+    bonkees.truncate(MAX_BONKEES);
+    bonkees
+}
+
+//prefers the avatar the user set for this server, if they have one
+async fn user_bonkee(chat: &dyn ChatSurface, guild: Option<u64>, user: &User) -> Bonkee {
+    let guild_avatar = match guild {
+        Some(guild_id) => chat.member_avatar(guild_id, *user.id.as_u64()).await,
+        None => None,
+    };
+    let image_url = guild_avatar.unwrap_or_else(|| match user.static_avatar_url() {
+        Some(url) => str::replace(&url, ".webp", ".png"),
+        None => user.default_avatar_url(),
+    });
+    Bonkee {user: Some(*user.id.as_u64()), image_url}
+}
+
+//the download failing is worth another try, something that isn't an image isn't
+pub async fn fetch_avatar(chat: &dyn ChatSurface, image_url: &str) -> Result<image::DynamicImage, CommandError> {
+    let fetched = chat.fetch(image_url).await?;
+    if !fetched.content_type.as_deref().is_some_and(|content_type| content_type.starts_with("image/")) {
+        return Err(CommandError::User(format!("{} isn't an image", image_url)))
+    }
+    decode_avatar(&fetched.body)
+}
+
+//a small file can still claim to be enormous, so the size is checked before anything gets allocated for it
+pub fn decode_avatar(body: &[u8]) -> Result<image::DynamicImage, CommandError> {
+    let mut reader = match image::io::Reader::new(Cursor::new(body)).with_guessed_format() {
+        Ok(reader) => reader,
+        Err(e) => return Err(CommandError::User(format!("could not parse avatar image: {}", e))),
+    };
+    let mut limits = image::io::Limits::default();
+    limits.max_image_width = Some(MAX_AVATAR_SIDE);
+    limits.max_image_height = Some(MAX_AVATAR_SIDE);
+    limits.max_alloc = Some(MAX_AVATAR_ALLOC);
+    reader.limits(limits);
+    match reader.decode() {
+        Ok(image) => Ok(image),
+        Err(image::ImageError::Limits(_)) => Err(CommandError::User(format!("that image is too big, the most is {}x{}", MAX_AVATAR_SIDE, MAX_AVATAR_SIDE))),
+        Err(e) => Err(CommandError::User(format!("could not parse avatar image: {}", e))),
+    }
+}
@@ -0,0 +1,281 @@
+//helper for writing entries in assets/bonk_locations.json without guessing
+//run it from the repo root, the same as the bot
+use std::env;
+use std::process;
+use image::{Rgba, RgbaImage};
+
+#[allow(dead_code)]
+#[path = "../activity.rs"]
+mod activity;
+#[allow(dead_code)]
+#[path = "../assets.rs"]
+mod assets;
+#[allow(dead_code)]
+#[path = "../bonker.rs"]
+mod bonker;
+
+use bonker::{ImageData, Mask};
+
+const USAGE: &str = "usage:
+    bonk-editor preview <template> [--avatar <image>] [--out <file>] [--<field> <value>]...
+        renders one template with the avatar slot and label outlined and their centers marked.
+        <template> is either a name from assets/bonk_locations.json or the path to a new image.
+        any field of the entry can be overridden, e.g. --bonkee_x 120 --bonkee_top true
+        --mask takes square, circle, rounded:<radius> or image:<file in assets/>
+        --perspective takes 8 comma separated numbers, the x,y of each corner clockwise from the top left
+        the resulting entry is printed so it can be pasted into bonk_locations.json
+    bonk-editor sheet [--avatar <image>] [--out <file>] [--size <pixels>] [--columns <n>]
+        renders every template in assets/bonk_locations.json into one image";
+
+const BONKEE_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+const LABEL_COLOR: Rgba<u8> = Rgba([0, 128, 255, 255]);
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(|arg| arg.as_str()) {
+        Some("preview") => preview(&args[1..]),
+        Some("sheet") => sheet(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn preview(args: &[String]) -> Result<(), String> {
+    let (template, options) = match args.split_first() {
+        Some((template, options)) if !template.starts_with("--") => (template, parse_options(options)?),
+        _ => return Err(USAGE.to_string()),
+    };
+    //start from the existing entry if there is one, so only the values being tweaked need to be passed in
+    let existing = assets::read_json::<Vec<ImageData>>("./assets", "bonk_locations.json")?.into_iter().find(|meta| meta.matches_name(template));
+    let (bonk_image, mut meta) = match existing {
+        Some(meta) => (open_image(&format!("./assets/{}", meta.name))?, meta),
+        None => {
+            let bonk_image = open_image(template)?;
+            let name = std::path::Path::new(template).file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| template.to_string());
+            let meta = default_meta(name, &bonk_image);
+            (bonk_image, meta)
+        },
+    };
+    let mut avatar_path = None;
+    let mut out = "preview.png".to_string();
+    for (key, value) in options {
+        match key.as_str() {
+            "avatar" => avatar_path = Some(value),
+            "out" => out = value,
+            _ => set_field(&mut meta, &key, &value)?,
+        }
+    }
+    let avatar = load_avatar(avatar_path.as_deref())?;
+    let bonk_label = open_image("./assets/bonklabel.png")?;
+    let mask_image = match &meta.mask {
+        Mask::Image(path) => Some(open_image(&format!("./assets/{}", path))?),
+        _ => None,
+    };
+    let mut rendered = bonker::composite_bonk(&avatar, &bonk_image, &bonk_label, mask_image.as_ref(), &meta)?.to_rgba8();
+    let corners = bonker::bonkee_corners(&meta);
+    outline(&mut rendered, &corners, BONKEE_COLOR);
+    let [center_x, center_y] = quad_center(&corners);
+    crosshair(&mut rendered, center_x, center_y, (meta.bonkee_width.min(meta.bonkee_height) / 4).max(4), BONKEE_COLOR);
+    let label_left = meta.bonk_label_x as f32 - (meta.bonk_label_width / 2) as f32;
+    let label_top = meta.bonk_label_y as f32 - (meta.bonk_label_height / 2) as f32;
+    let label_right = label_left + meta.bonk_label_width as f32 - 1.0;
+    let label_bottom = label_top + meta.bonk_label_height as f32 - 1.0;
+    outline(&mut rendered, &[[label_left, label_top], [label_right, label_top], [label_right, label_bottom], [label_left, label_bottom]], LABEL_COLOR);
+    crosshair(&mut rendered, meta.bonk_label_x as f32, meta.bonk_label_y as f32, (meta.bonk_label_width.min(meta.bonk_label_height) / 4).max(4), LABEL_COLOR);
+    if let Err(e) = rendered.save_with_format(&out, image::ImageFormat::Png) {
+        return Err(format!("could not save {}: {}", out, e))
+    }
+    println!("saved preview to {}", out);
+    match serde_json::to_string_pretty(&meta) {
+        Ok(json) => println!("{}", json),
+        Err(e) => return Err(format!("could not serialize entry: {}", e)),
+    }
+    Ok(())
+}
+
+fn sheet(args: &[String]) -> Result<(), String> {
+    let mut avatar_path = None;
+    let mut out = "contact_sheet.png".to_string();
+    let mut size = 400;
+    let mut columns = 4;
+    for (key, value) in parse_options(args)? {
+        match key.as_str() {
+            "avatar" => avatar_path = Some(value),
+            "out" => out = value,
+            "size" => size = parse_value(&key, &value)?,
+            "columns" => columns = parse_value(&key, &value)?,
+            _ => return Err(format!("unknown option --{}\n{}", key, USAGE)),
+        }
+    }
+    let avatar = load_avatar(avatar_path.as_deref())?;
+    //drawn the way the bot draws them, so anything it wouldn't load shows up here first
+    let loaded = assets::load_assets("./assets").map_err(|errors| errors.join("\n"))?;
+    let mut rendered = Vec::new();
+    for (i, meta) in loaded.bonks.iter().enumerate() {
+        let bonked = loaded.render_bonk(&avatar, meta)?;
+        rendered.push(bonked.thumbnail(size, size));
+        println!("{}. {}", i + 1, meta.name);
+    }
+    let sheet = bonker::contact_sheet(&rendered, size, columns);
+    if let Err(e) = sheet.save_with_format(&out, image::ImageFormat::Png) {
+        return Err(format!("could not save {}: {}", out, e))
+    }
+    println!("saved contact sheet to {}", out);
+    Ok(())
+}
+
+//turns '--key value' pairs into a list
+fn parse_options(args: &[String]) -> Result<Vec<(String, String)>, String> {
+    let mut options = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let key = match arg.strip_prefix("--") {
+            Some(key) => key.replace('-', "_"),
+            None => return Err(format!("unexpected argument '{}'\n{}", arg, USAGE)),
+        };
+        match args.next() {
+            Some(value) => options.push((key, value.clone())),
+            None => return Err(format!("--{} needs a value", key)),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_value<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value.parse::<T>().map_err(|_| format!("invalid value for --{}: '{}'", key, value))
+}
+
+fn set_field(meta: &mut ImageData, key: &str, value: &str) -> Result<(), String> {
+    match key {
+        "bonkee_x" => meta.bonkee_x = parse_value(key, value)?,
+        "bonkee_y" => meta.bonkee_y = parse_value(key, value)?,
+        "bonkee_width" => meta.bonkee_width = parse_value(key, value)?,
+        "bonkee_height" => meta.bonkee_height = parse_value(key, value)?,
+        "bonk_label_x" => meta.bonk_label_x = parse_value(key, value)?,
+        "bonk_label_y" => meta.bonk_label_y = parse_value(key, value)?,
+        "bonk_label_width" => meta.bonk_label_width = parse_value(key, value)?,
+        "bonk_label_height" => meta.bonk_label_height = parse_value(key, value)?,
+        "bonkee_top" => meta.bonkee_top = parse_value(key, value)?,
+        "weight" => meta.weight = parse_value(key, value)?,
+        "tags" => meta.tags = value.split(',').map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()).collect(),
+        "filter" => meta.filter = match serde_json::from_value(serde_json::Value::String(value.to_lowercase())) {
+            Ok(filter) => filter,
+            Err(_) => return Err(format!("invalid value for --filter: '{}' (nearest, triangle, catmullrom, gaussian or lanczos3)", value)),
+        },
+        "mask" => meta.mask = parse_mask(value)?,
+        "rotation" => meta.rotation = parse_value(key, value)?,
+        "perspective" => {
+            let numbers = value.split(',').map(|number| parse_value::<f32>(key, number.trim())).collect::<Result<Vec<f32>, String>>()?;
+            if numbers.len() != 8 {
+                return Err(format!("--perspective needs 8 numbers, got {}", numbers.len()))
+            }
+            meta.perspective = Some([[numbers[0], numbers[1]], [numbers[2], numbers[3]], [numbers[4], numbers[5]], [numbers[6], numbers[7]]]);
+        },
+        _ => return Err(format!("unknown option --{}\n{}", key, USAGE)),
+    }
+    Ok(())
+}
+
+fn parse_mask(value: &str) -> Result<Mask, String> {
+    match value.split_once(':') {
+        Some(("rounded", radius)) => Ok(Mask::Rounded(parse_value("mask", radius)?)),
+        Some(("image", path)) => Ok(Mask::Image(path.to_string())),
+        None if value == "square" => Ok(Mask::Square),
+        None if value == "circle" => Ok(Mask::Circle),
+        _ => Err(format!("invalid value for --mask: '{}'", value)),
+    }
+}
+
+//a brand new template starts with the avatar in the middle and the label just above it
+fn default_meta(name: String, bonk_image: &image::DynamicImage) -> ImageData {
+    let size = (bonk_image.width().min(bonk_image.height()) / 4).max(1);
+    let center_x = bonk_image.width() / 2;
+    let center_y = bonk_image.height() / 2;
+    ImageData {
+        name,
+        bonkee_x: center_x,
+        bonkee_y: center_y,
+        bonkee_width: size,
+        bonkee_height: size,
+        bonk_label_x: center_x,
+        bonk_label_y: center_y.saturating_sub(size / 2).max(size / 4),
+        bonk_label_width: size,
+        bonk_label_height: (size / 2).max(1),
+        bonkee_top: true,
+        weight: 1,
+        tags: Vec::new(),
+        filter: Default::default(),
+        mask: Default::default(),
+        rotation: 0.0,
+        perspective: None,
+    }
+}
+
+fn open_image(path: &str) -> Result<image::DynamicImage, String> {
+    image::open(path).map_err(|e| format!("could not open {}: {}", path, e))
+}
+
+//without an avatar, use the same checkerboard the golden tests use
+fn load_avatar(path: Option<&str>) -> Result<image::DynamicImage, String> {
+    match path {
+        Some(path) => open_image(path),
+        None => Ok(sample_avatar()),
+    }
+}
+
+//one marked corner so stretching and flipping are easy to spot
+fn sample_avatar() -> image::DynamicImage {
+    let avatar = RgbaImage::from_fn(128, 128, |x, y| {
+        if x < 32 && y < 32 {
+            Rgba([255, 200, 0, 255])
+        }
+        else if (x / 16 + y / 16) % 2 == 0 {
+            Rgba([255, 0, 255, 255])
+        }
+        else {
+            Rgba([40, 40, 40, 255])
+        }
+    });
+    image::DynamicImage::ImageRgba8(avatar)
+}
+
+fn quad_center(corners: &[[f32; 2]; 4]) -> [f32; 2] {
+    let x = corners.iter().map(|c| c[0]).sum::<f32>() / 4.0;
+    let y = corners.iter().map(|c| c[1]).sum::<f32>() / 4.0;
+    [x, y]
+}
+
+//draws the edges of a quad given its corners in order
+fn outline(image: &mut RgbaImage, corners: &[[f32; 2]; 4], color: Rgba<u8>) {
+    for i in 0..4 {
+        line(image, corners[i], corners[(i + 1) % 4], color);
+    }
+}
+
+fn crosshair(image: &mut RgbaImage, x: f32, y: f32, arm: u32, color: Rgba<u8>) {
+    let arm = arm as f32;
+    line(image, [x - arm, y], [x + arm, y], color);
+    line(image, [x, y - arm], [x, y + arm], color);
+}
+
+fn line(image: &mut RgbaImage, from: [f32; 2], to: [f32; 2], color: Rgba<u8>) {
+    let steps = (to[0] - from[0]).abs().max((to[1] - from[1]).abs()).ceil().max(1.0) as i64;
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32;
+        let x = from[0] + (to[0] - from[0]) * t;
+        let y = from[1] + (to[1] - from[1]) * t;
+        put_pixel(image, x.round() as i64, y.round() as i64, color);
+    }
+}
+
+//anything out of bounds is skipped, labels are often pushed off the image on purpose
+fn put_pixel(image: &mut RgbaImage, x: i64, y: i64, color: Rgba<u8>) {
+    if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < image.height() {
+        image.put_pixel(x as u32, y as u32, color);
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::str::FromStr;
+use chrono::NaiveDateTime;
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use crate::assets;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BonkRecord {
+    pub bonker: u64,
+    pub bonkee: u64,
+    pub template: String,
+    //None for bonks in DMs
+    pub guild: Option<u64>,
+    pub time: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    Week,
+    Month,
+    All,
+}
+
+impl FromStr for Period {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.to_lowercase().as_str() {
+            "week" => Ok(Period::Week),
+            "month" => Ok(Period::Month),
+            "all" => Ok(Period::All),
+            _ => Err(format!("'{}' is not one of week, month or all", string)),
+        }
+    }
+}
+
+impl Period {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            Period::Week => "this week",
+            Period::Month => "this month",
+            Period::All => "of all time",
+        }
+    }
+
+    fn contains(&self, time: NaiveDateTime, now: NaiveDateTime) -> bool {
+        match self {
+            Period::Week => time > now - chrono::Duration::days(7),
+            Period::Month => time > now - chrono::Duration::days(30),
+            Period::All => true,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct UserStats {
+    pub given: usize,
+    pub received: usize,
+    pub favorite_template: Option<(String, usize)>,
+    pub favorite_target: Option<(u64, usize)>,
+    pub nemesis: Option<(u64, usize)>,
+}
+
+pub fn load_bonk_records(dir: &str) -> Result<Vec<BonkRecord>, String> {
+    assets::read_saved_json(dir, "bonk_stats.json")
+}
+
+pub fn save_bonk_records(dir: &str, records: &[BonkRecord]) -> Result<(), Box<dyn Error>> {
+    let json_content = serde_json::to_string(records)?;
+    std::fs::write(format!("{}/bonk_stats.json", dir), json_content)?;
+    Ok(())
+}
+
+//bonkers in this guild ranked by how many bonks they handed out during the period
+pub fn leaderboard(records: &[BonkRecord], guild: Option<u64>, period: Period, now: NaiveDateTime) -> Vec<(u64, usize)> {
+    let counts = count_by(records.iter()
+        .filter(|record| record.guild == guild && period.contains(record.time, now))
+        .map(|record| record.bonker));
+    ranked(counts)
+}
+
+//across every guild, for the bot's status
+pub fn top_bonker(records: &[BonkRecord]) -> Option<(u64, usize)> {
+    ranked(count_by(records.iter().map(|record| record.bonker))).into_iter().next()
+}
+
+pub fn top_templates(records: &[BonkRecord], guild: Option<u64>) -> Vec<(String, usize)> {
+    let counts = count_by(records.iter()
+        .filter(|record| record.guild == guild)
+        .map(|record| record.template.clone()));
+    ranked(counts)
+}
+
+pub fn user_stats(records: &[BonkRecord], guild: Option<u64>, user: u64) -> UserStats {
+    let records: Vec<&BonkRecord> = records.iter().filter(|record| record.guild == guild).collect();
+    let given: Vec<&&BonkRecord> = records.iter().filter(|record| record.bonker == user).collect();
+    let received: Vec<&&BonkRecord> = records.iter().filter(|record| record.bonkee == user).collect();
+    UserStats {
+        given: given.len(),
+        received: received.len(),
+        favorite_template: ranked(count_by(given.iter().map(|record| record.template.clone()))).into_iter().next(),
+        favorite_target: ranked(count_by(given.iter().map(|record| record.bonkee))).into_iter().next(),
+        nemesis: ranked(count_by(received.iter().map(|record| record.bonker))).into_iter().next(),
+    }
+}
+
+fn count_by<K: std::hash::Hash + Eq>(keys: impl Iterator<Item = K>) -> HashMap<K, usize> {
+    let mut counts = HashMap::new();
+    for key in keys {
+        *counts.entry(key).or_insert(0) += 1;
+    }
+    counts
+}
+
+//highest count first, ties broken by key so the order is stable
+fn ranked<K: Ord>(counts: HashMap<K, usize>) -> Vec<(K, usize)> {
+    let mut ranked: Vec<(K, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+//one horizontal bar per value, in the same order as the list it goes along with
+pub fn render_bar_chart(values: &[usize]) -> image::DynamicImage {
+    const WIDTH: u32 = 400;
+    const BAR_HEIGHT: u32 = 24;
+    const GAP: u32 = 8;
+    let colors = [
+        Rgba([255, 196, 0, 255]),
+        Rgba([192, 192, 192, 255]),
+        Rgba([205, 127, 50, 255]),
+        Rgba([88, 101, 242, 255]),
+    ];
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    let height = (values.len() as u32 * (BAR_HEIGHT + GAP) + GAP).max(1);
+    let mut chart = RgbaImage::from_pixel(WIDTH, height, Rgba([47, 49, 54, 255]));
+    for (i, value) in values.iter().enumerate() {
+        let bar_width = ((WIDTH - GAP * 2) as u64 * *value as u64 / max as u64).max(1) as u32;
+        let top = GAP + i as u32 * (BAR_HEIGHT + GAP);
+        let color = colors[i.min(colors.len() - 1)];
+        for y in top..top + BAR_HEIGHT {
+            for x in GAP..GAP + bar_width {
+                chart.put_pixel(x, y, color);
+            }
+        }
+    }
+    image::DynamicImage::ImageRgba8(chart)
+}
@@ -1,52 +1,348 @@
-use image::imageops;
-use serde::{Deserialize, Serialize};
-use rand::seq::SliceRandom;
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct ImageData {
-    name: String,
-    bonkee_x: u32,
-    bonkee_y: u32,
-    bonkee_width: u32,
-    bonkee_height: u32,
-    bonk_label_x: u32,
-    bonk_label_y: u32,
-    bonk_label_width: u32,
-    bonk_label_height: u32,
-    bonkee_top: bool
-}
-pub fn overlay_bonk(avatar: image::DynamicImage, meta: &ImageData) -> Result<image::DynamicImage, String> {
-    let bonk_image = match image::open(format!("./assets/{}", meta.name)) {
-        Ok(image) => image,
-        Err(e) => return Err(format!("could not open bonk image: {}", e)),
-    };
-    let resized_avatar = imageops::resize(&avatar, meta.bonkee_width, meta.bonkee_height, imageops::FilterType::Nearest);
-    let bonk_label = match image::open("./assets/bonklabel.png") {
-        Ok(image) => image,
-        Err(e) => return Err(format!("could not open bonk image: {}", e)),
-    };
-    let resized_label = imageops::resize(&bonk_label, meta.bonk_label_width, meta.bonk_label_height, imageops::FilterType::Nearest);
-    let mut bonk_image_copy = bonk_image.clone();
-    //to get actual coordinates, subtract half of width from x and half of height from y
-    imageops::overlay(&mut bonk_image_copy, &resized_avatar, meta.bonkee_x - meta.bonkee_width/2, meta.bonkee_y - meta.bonkee_height/2);
-    if !meta.bonkee_top {
-        imageops::overlay(&mut bonk_image_copy, &bonk_image, 0, 0);
-    }
-    imageops::overlay(&mut bonk_image_copy, &resized_label, meta.bonk_label_x - meta.bonk_label_width/2, meta.bonk_label_y - meta.bonk_label_height/2);
-    Ok(bonk_image_copy)
-}
-
-pub fn choose_bonk() -> Result<ImageData, String> {
-    let meta_data: Vec<ImageData> = match std::fs::read_to_string("./assets/bonk_locations.json") {
-        Ok(string) => match serde_json::from_str(&string) {
-            Ok(data) => data,
-            Err(e) => return Err(format!("could not parse JSON: {}", e)),
-        },
-        Err(e) => return Err(format!("could not read 'bonk_locations.json': {}", e)),
-    };
-    let meta = match meta_data.choose(&mut rand::thread_rng()) {
-        Some(item) => item,
-        None => return Err(format!("meta data has no elements to choose from")),
-    };
-    Ok(meta.clone())
-}
\ No newline at end of file
+use image::{imageops, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use rand::seq::SliceRandom;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImageData {
+    pub name: String,
+    pub bonkee_x: u32,
+    pub bonkee_y: u32,
+    pub bonkee_width: u32,
+    pub bonkee_height: u32,
+    pub bonk_label_x: u32,
+    pub bonk_label_y: u32,
+    pub bonk_label_width: u32,
+    pub bonk_label_height: u32,
+    pub bonkee_top: bool,
+    //relative chance of this template being picked when choosing randomly
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    //lets users pick from a group of templates with '#tag'
+    #[serde(default)]
+    pub tags: Vec<String>,
+    //how the avatar and label get scaled
+    #[serde(default)]
+    pub filter: Filter,
+    //shape the avatar is cut into before it is pasted
+    #[serde(default)]
+    pub mask: Mask,
+    //clockwise, in degrees, around (bonkee_x, bonkee_y)
+    #[serde(default)]
+    pub rotation: f32,
+    //corners of the avatar slot as [x, y] pairs (top left, top right, bottom right, bottom left)
+    //if set this replaces bonkee_x/y/width/height and rotation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub perspective: Option<[[f32; 2]; 4]>,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Filter {
+    Nearest,
+    Triangle,
+    #[default]
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+impl From<Filter> for imageops::FilterType {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::Nearest => imageops::FilterType::Nearest,
+            Filter::Triangle => imageops::FilterType::Triangle,
+            Filter::CatmullRom => imageops::FilterType::CatmullRom,
+            Filter::Gaussian => imageops::FilterType::Gaussian,
+            Filter::Lanczos3 => imageops::FilterType::Lanczos3,
+        }
+    }
+}
+
+//"square", "circle", {"rounded": <corner radius>} or {"image": <mask file in assets/>}
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Mask {
+    #[default]
+    Square,
+    Circle,
+    Rounded(u32),
+    //white/opaque parts of the image keep the avatar, black/transparent parts cut it out
+    Image(String),
+}
+
+impl ImageData {
+    //'bonk1.png' can be asked for as 'bonk1' or 'bonk1.png'
+    pub fn short_name(&self) -> &str {
+        self.name.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(&self.name)
+    }
+
+    pub fn matches_name(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.name.to_lowercase() == name || self.short_name().to_lowercase() == name
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+}
+
+//does the actual layering, the images come from the loaded assets
+pub fn composite_bonk(avatar: &image::DynamicImage, bonk_image: &image::DynamicImage, bonk_label: &image::DynamicImage, mask_image: Option<&image::DynamicImage>, meta: &ImageData) -> Result<image::DynamicImage, String> {
+    let filter = imageops::FilterType::from(meta.filter);
+    let resized_label = imageops::resize(bonk_label, meta.bonk_label_width, meta.bonk_label_height, filter);
+    let mut bonk_image_copy = bonk_image.clone();
+    let corners = bonkee_corners(meta);
+    if meta.perspective.is_none() && meta.rotation == 0.0 {
+        //plain rectangle, no need to warp anything
+        let mut resized_avatar = imageops::resize(avatar, meta.bonkee_width, meta.bonkee_height, filter);
+        apply_mask(&mut resized_avatar, &meta.mask, mask_image, filter);
+        imageops::overlay(&mut bonk_image_copy, &resized_avatar, corners[0][0].round() as i64, corners[0][1].round() as i64);
+    }
+    else {
+        //scale the avatar to roughly its final size first so the filter does the downsampling, the warp only interpolates
+        let (width, height) = quad_size(&corners);
+        let mut resized_avatar = imageops::resize(avatar, width, height, filter);
+        apply_mask(&mut resized_avatar, &meta.mask, mask_image, filter);
+        let warped = warp_to_quad(&resized_avatar, &corners, bonk_image.width(), bonk_image.height())?;
+        imageops::overlay(&mut bonk_image_copy, &warped, 0, 0);
+    }
+    if !meta.bonkee_top {
+        imageops::overlay(&mut bonk_image_copy, bonk_image, 0, 0);
+    }
+    //to get actual coordinates, subtract half of width from x and half of height from y
+    imageops::overlay(&mut bonk_image_copy, &resized_label, meta.bonk_label_x as i64 - (meta.bonk_label_width/2) as i64, meta.bonk_label_y as i64 - (meta.bonk_label_height/2) as i64);
+    Ok(bonk_image_copy)
+}
+
+//where the corners of the avatar end up on the template (top left, top right, bottom right, bottom left)
+pub fn bonkee_corners(meta: &ImageData) -> [[f32; 2]; 4] {
+    if let Some(corners) = meta.perspective {
+        return corners
+    }
+    let center_x = meta.bonkee_x as f32;
+    let center_y = meta.bonkee_y as f32;
+    let left = center_x - (meta.bonkee_width/2) as f32;
+    let top = center_y - (meta.bonkee_height/2) as f32;
+    let right = left + meta.bonkee_width as f32;
+    let bottom = top + meta.bonkee_height as f32;
+    let (sin, cos) = meta.rotation.to_radians().sin_cos();
+    let rotate = |x: f32, y: f32| {
+        let (dx, dy) = (x - center_x, y - center_y);
+        [center_x + dx * cos - dy * sin, center_y + dx * sin + dy * cos]
+    };
+    [rotate(left, top), rotate(right, top), rotate(right, bottom), rotate(left, bottom)]
+}
+
+//the avatar resolution to use for a quad, the longer of each pair of opposite edges
+fn quad_size(corners: &[[f32; 2]; 4]) -> (u32, u32) {
+    let length = |a: [f32; 2], b: [f32; 2]| ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt();
+    let width = length(corners[0], corners[1]).max(length(corners[3], corners[2]));
+    let height = length(corners[0], corners[3]).max(length(corners[1], corners[2]));
+    (width.round().max(1.0) as u32, height.round().max(1.0) as u32)
+}
+
+fn apply_mask(avatar: &mut RgbaImage, mask: &Mask, mask_image: Option<&image::DynamicImage>, filter: imageops::FilterType) {
+    let (width, height) = avatar.dimensions();
+    let (w, h) = (width as f32, height as f32);
+    let coverage: Box<dyn Fn(u32, u32) -> f32> = match mask {
+        Mask::Square => return,
+        Mask::Circle => Box::new(move |x, y| {
+            //distance from the center scaled so the edge of the ellipse is at 1, then converted back to pixels for a 1px soft edge
+            let nx = (x as f32 + 0.5 - w / 2.0) / (w / 2.0);
+            let ny = (y as f32 + 0.5 - h / 2.0) / (h / 2.0);
+            let outside = ((nx * nx + ny * ny).sqrt() - 1.0) * w.min(h) / 2.0;
+            (0.5 - outside).clamp(0.0, 1.0)
+        }),
+        Mask::Rounded(radius) => {
+            let radius = (*radius as f32).min(w / 2.0).min(h / 2.0);
+            Box::new(move |x, y| {
+                //only the corners are cut, find the nearest corner circle's center
+                let px = x as f32 + 0.5;
+                let py = y as f32 + 0.5;
+                let cx = px.max(radius).min(w - radius);
+                let cy = py.max(radius).min(h - radius);
+                let outside = ((px - cx).powi(2) + (py - cy).powi(2)).sqrt() - radius;
+                (0.5 - outside).clamp(0.0, 1.0)
+            })
+        },
+        Mask::Image(_) => {
+            let mask_image = match mask_image {
+                Some(mask_image) => imageops::resize(&mask_image.to_luma_alpha8(), width, height, filter),
+                None => return,
+            };
+            Box::new(move |x, y| {
+                let pixel = mask_image.get_pixel(x, y);
+                (pixel[0] as f32 / 255.0) * (pixel[1] as f32 / 255.0)
+            })
+        },
+    };
+    for (x, y, pixel) in avatar.enumerate_pixels_mut() {
+        pixel[3] = (pixel[3] as f32 * coverage(x, y)).round() as u8;
+    }
+}
+
+//draws source stretched over the quad onto a transparent canvas the size of the template
+fn warp_to_quad(source: &RgbaImage, corners: &[[f32; 2]; 4], width: u32, height: u32) -> Result<RgbaImage, String> {
+    let (src_w, src_h) = (source.width() as f64, source.height() as f64);
+    let source_corners = [[0.0, 0.0], [src_w, 0.0], [src_w, src_h], [0.0, src_h]];
+    let dest_corners: Vec<[f64; 2]> = corners.iter().map(|c| [c[0] as f64, c[1] as f64]).collect();
+    //maps template pixels back to avatar pixels
+    let inverse = match homography(&dest_corners, &source_corners) {
+        Some(h) => h,
+        None => return Err("avatar corners do not form a usable shape".to_string()),
+    };
+    let mut canvas = RgbaImage::new(width, height);
+    //only bother with pixels inside the quad's bounding box, with a pixel of slack for the soft edge
+    let min_x = corners.iter().map(|c| c[0]).fold(f32::MAX, f32::min).floor().max(1.0) as u32 - 1;
+    let min_y = corners.iter().map(|c| c[1]).fold(f32::MAX, f32::min).floor().max(1.0) as u32 - 1;
+    let max_x = (corners.iter().map(|c| c[0]).fold(f32::MIN, f32::max).ceil().max(0.0) as u32 + 1).min(width);
+    let max_y = (corners.iter().map(|c| c[1]).fold(f32::MIN, f32::max).ceil().max(0.0) as u32 + 1).min(height);
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+            let denominator = inverse[6] * px + inverse[7] * py + 1.0;
+            if denominator.abs() < 1e-9 {
+                continue
+            }
+            let u = (inverse[0] * px + inverse[1] * py + inverse[2]) / denominator;
+            let v = (inverse[3] * px + inverse[4] * py + inverse[5]) / denominator;
+            if let Some(pixel) = sample_bilinear(source, u - 0.5, v - 0.5) {
+                canvas.put_pixel(x, y, pixel);
+            }
+        }
+    }
+    Ok(canvas)
+}
+
+//pixels outside the image count as transparent, which gives the warped edges a bit of antialiasing
+fn sample_bilinear(image: &RgbaImage, x: f64, y: f64) -> Option<Rgba<u8>> {
+    if x <= -1.0 || y <= -1.0 || x >= image.width() as f64 || y >= image.height() as f64 {
+        return None
+    }
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    //accumulate premultiplied so transparent neighbours don't darken the edges
+    let mut total = [0.0f64; 4];
+    for (dx, dy, weight) in [(0.0, 0.0, (1.0 - fx) * (1.0 - fy)), (1.0, 0.0, fx * (1.0 - fy)), (0.0, 1.0, (1.0 - fx) * fy), (1.0, 1.0, fx * fy)] {
+        let (sx, sy) = (x0 + dx, y0 + dy);
+        if sx < 0.0 || sy < 0.0 || sx >= image.width() as f64 || sy >= image.height() as f64 {
+            continue
+        }
+        let pixel = image.get_pixel(sx as u32, sy as u32);
+        let alpha = pixel[3] as f64 / 255.0 * weight;
+        for channel in 0..3 {
+            total[channel] += pixel[channel] as f64 * alpha;
+        }
+        total[3] += alpha;
+    }
+    if total[3] <= 0.0 {
+        return None
+    }
+    Some(Rgba([
+        (total[0] / total[3]).round().min(255.0) as u8,
+        (total[1] / total[3]).round().min(255.0) as u8,
+        (total[2] / total[3]).round().min(255.0) as u8,
+        (total[3] * 255.0).round().min(255.0) as u8,
+    ]))
+}
+
+//solves for the 3x3 projective transform (with the last entry fixed at 1) taking each of from to the matching to
+fn homography(from: &[[f64; 2]], to: &[[f64; 2]]) -> Option<[f64; 8]> {
+    let mut system = [[0.0f64; 9]; 8];
+    for i in 0..4 {
+        let ([x, y], [u, v]) = (from[i], to[i]);
+        system[i * 2] = [x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y, u];
+        system[i * 2 + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y, v];
+    }
+    //gaussian elimination with partial pivoting
+    for col in 0..8 {
+        let pivot = (col..8).max_by(|&a, &b| system[a][col].abs().partial_cmp(&system[b][col].abs()).unwrap_or(std::cmp::Ordering::Equal))?;
+        if system[pivot][col].abs() < 1e-9 {
+            return None
+        }
+        system.swap(col, pivot);
+        let pivot_row = system[col];
+        for (i, row) in system.iter_mut().enumerate() {
+            if i != col {
+                let factor = row[col] / pivot_row[col];
+                for (value, pivot_value) in row.iter_mut().zip(pivot_row.iter()).skip(col) {
+                    *value -= factor * pivot_value;
+                }
+            }
+        }
+    }
+    let mut solution = [0.0f64; 8];
+    for (i, value) in solution.iter_mut().enumerate() {
+        *value = system[i][8] / system[i][i];
+    }
+    Some(solution)
+}
+
+//selector is either a template name or '#tag', recent is the list of templates that were used lately in this channel
+pub fn select_bonk(meta_data: &[ImageData], selector: Option<&str>, recent: &[String]) -> Result<ImageData, String> {
+    let candidates: Vec<&ImageData> = match selector {
+        Some(tag) if tag.starts_with('#') => {
+            let tag = &tag[1..];
+            let tagged: Vec<&ImageData> = meta_data.iter().filter(|meta| meta.has_tag(tag)).collect();
+            if tagged.is_empty() {
+                return Err(format!("no bonk templates are tagged '{}'", tag))
+            }
+            tagged
+        },
+        //asking for a template by name always gets that template, even if it was just used
+        Some(name) => return match meta_data.iter().find(|meta| meta.matches_name(name)) {
+            Some(meta) => Ok(meta.clone()),
+            None => Err(format!("no bonk template named '{}'", name)),
+        },
+        None => meta_data.iter().collect(),
+    };
+    //templates with no weight can only be picked by name
+    let candidates: Vec<&ImageData> = candidates.into_iter().filter(|meta| meta.weight > 0).collect();
+    if candidates.is_empty() {
+        return Err("no bonk templates to choose from".to_string())
+    }
+    //avoid repeating recently used templates, unless that would leave nothing to pick from
+    let fresh: Vec<&ImageData> = candidates.iter()
+        .filter(|meta| !recent.contains(&meta.name))
+        .copied()
+        .collect();
+    let candidates = if fresh.is_empty() {candidates} else {fresh};
+    match candidates.choose_weighted(&mut rand::thread_rng(), |meta| meta.weight) {
+        Ok(meta) => Ok((*meta).clone()),
+        Err(e) => Err(format!("could not choose a bonk template: {}", e)),
+    }
+}
+
+//squashes several avatars into one square so they can all be bonked at once
+pub fn tile_avatars(avatars: &[image::DynamicImage]) -> image::DynamicImage {
+    const TILE_SIZE: u32 = 256;
+    if avatars.len() == 1 {
+        return avatars[0].clone()
+    }
+    let columns = (avatars.len() as f64).sqrt().ceil() as u32;
+    let tiles: Vec<image::DynamicImage> = avatars.iter()
+        .map(|avatar| image::DynamicImage::ImageRgba8(imageops::resize(avatar, TILE_SIZE, TILE_SIZE, imageops::FilterType::CatmullRom)))
+        .collect();
+    let sheet = contact_sheet(&tiles, TILE_SIZE, columns);
+    //a partly filled last row would leave the sheet taller than it is wide, stretch it back to a square
+    image::DynamicImage::ImageRgba8(imageops::resize(&sheet, columns * TILE_SIZE, columns * TILE_SIZE, imageops::FilterType::CatmullRom))
+}
+
+//pastes each image into its own cell_size square, centered
+pub fn contact_sheet(images: &[image::DynamicImage], cell_size: u32, columns: u32) -> image::DynamicImage {
+    let columns = columns.max(1).min(images.len().max(1) as u32);
+    let rows = (images.len() as u32).div_ceil(columns);
+    let mut sheet = image::RgbaImage::new(columns * cell_size, rows.max(1) * cell_size);
+    for (i, image) in images.iter().enumerate() {
+        let cell_x = (i as u32 % columns) * cell_size;
+        let cell_y = (i as u32 / columns) * cell_size;
+        let x = cell_x + cell_size.saturating_sub(image.width()) / 2;
+        let y = cell_y + cell_size.saturating_sub(image.height()) / 2;
+        imageops::overlay(&mut sheet, image, x as i64, y as i64);
+    }
+    image::DynamicImage::ImageRgba8(sheet)
+}
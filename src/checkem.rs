@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use serenity::model::channel::ReactionType;
+
+//a palindrome or straight has to be at least this many digits long at the end of the id to count
+const PALINDROME_LENGTH: usize = 6;
+const STRAIGHT_LENGTH: usize = 5;
+//trailing zeros needed for a round number
+const ROUND_ZEROS: u32 = 4;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Pattern {
+    Dubs,
+    Trips,
+    Quads,
+    Quints,
+    Sexes,
+    Septs,
+    Octs,
+    Nons,
+    //ten or more
+    Decs,
+    Palindrome,
+    Straight,
+    Round,
+}
+
+pub const ALL_PATTERNS: [Pattern; 12] = [
+    Pattern::Dubs, Pattern::Trips, Pattern::Quads, Pattern::Quints, Pattern::Sexes, Pattern::Septs,
+    Pattern::Octs, Pattern::Nons, Pattern::Decs, Pattern::Palindrome, Pattern::Straight, Pattern::Round,
+];
+
+impl Pattern {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pattern::Dubs => "dubs",
+            Pattern::Trips => "trips",
+            Pattern::Quads => "quads",
+            Pattern::Quints => "quints",
+            Pattern::Sexes => "sexes",
+            Pattern::Septs => "septs",
+            Pattern::Octs => "octs",
+            Pattern::Nons => "nons",
+            Pattern::Decs => "decs",
+            Pattern::Palindrome => "palindrome",
+            Pattern::Straight => "straight",
+            Pattern::Round => "round",
+        }
+    }
+
+    fn from_repeats(count: u32) -> Option<Pattern> {
+        match count {
+            0 | 1 => None,
+            2 => Some(Pattern::Dubs),
+            3 => Some(Pattern::Trips),
+            4 => Some(Pattern::Quads),
+            5 => Some(Pattern::Quints),
+            6 => Some(Pattern::Sexes),
+            7 => Some(Pattern::Septs),
+            8 => Some(Pattern::Octs),
+            9 => Some(Pattern::Nons),
+            _ => Some(Pattern::Decs),
+        }
+    }
+
+    //the custom emoji the bot has always used, these only exist in the server they were uploaded to
+    pub fn default_reaction(&self) -> ReactionType {
+        let custom = |id: u64, name: &str| ReactionType::Custom {
+            animated: false,
+            id: id.into(),
+            name: Some(name.to_string()),
+        };
+        match self {
+            Pattern::Dubs => custom(796856698098810931, "dubs"),
+            Pattern::Trips => custom(796861409203060776, "trips"),
+            Pattern::Quads => custom(796861430297001985, "quads"),
+            Pattern::Quints => custom(796861455894577163, "quints"),
+            Pattern::Sexes => custom(796861477004771328, "sexes"),
+            _ => self.fallback_reaction(),
+        }
+    }
+
+    //used when the configured or default emoji can't be reacted with
+    pub fn fallback_reaction(&self) -> ReactionType {
+        let emoji = match self {
+            Pattern::Dubs => "2\u{fe0f}\u{20e3}",
+            Pattern::Trips => "3\u{fe0f}\u{20e3}",
+            Pattern::Quads => "4\u{fe0f}\u{20e3}",
+            Pattern::Quints => "5\u{fe0f}\u{20e3}",
+            Pattern::Sexes => "6\u{fe0f}\u{20e3}",
+            Pattern::Septs => "7\u{fe0f}\u{20e3}",
+            Pattern::Octs => "8\u{fe0f}\u{20e3}",
+            Pattern::Nons => "9\u{fe0f}\u{20e3}",
+            Pattern::Decs => "🔟",
+            Pattern::Palindrome => "🪞",
+            Pattern::Straight => "📈",
+            Pattern::Round => "💯",
+        };
+        ReactionType::Unicode(emoji.to_string())
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        let string = string.to_lowercase();
+        match ALL_PATTERNS.iter().find(|pattern| pattern.name() == string) {
+            Some(pattern) => Ok(*pattern),
+            None => Err(format!("'{}' is not a pattern, try one of: {}", string, ALL_PATTERNS.iter().map(|p| p.name()).collect::<Vec<_>>().join(", "))),
+        }
+    }
+}
+
+//every pattern the id hits, an id can hit more than one (e.g. 10000 is quads and round)
+pub fn detect(id: u64) -> Vec<Pattern> {
+    let digits: Vec<u8> = id.to_string().bytes().map(|b| b - b'0').collect();
+    let mut found = Vec::new();
+    if let Some(pattern) = Pattern::from_repeats(trailing_repeats(&digits)) {
+        found.push(pattern);
+    }
+    if is_palindrome_tail(&digits) {
+        found.push(Pattern::Palindrome);
+    }
+    if trailing_straight(&digits) >= STRAIGHT_LENGTH {
+        found.push(Pattern::Straight);
+    }
+    if id != 0 && id.is_multiple_of(10u64.pow(ROUND_ZEROS)) {
+        found.push(Pattern::Round);
+    }
+    found
+}
+
+fn trailing_repeats(digits: &[u8]) -> u32 {
+    let last = match digits.last() {
+        Some(last) => last,
+        None => return 0,
+    };
+    digits.iter().rev().take_while(|digit| *digit == last).count() as u32
+}
+
+//the last few digits read the same both ways, without all being the same digit (that's just repeats)
+fn is_palindrome_tail(digits: &[u8]) -> bool {
+    if digits.len() < PALINDROME_LENGTH {
+        return false
+    }
+    let tail = &digits[digits.len() - PALINDROME_LENGTH..];
+    tail.iter().eq(tail.iter().rev()) && tail.iter().any(|digit| *digit != tail[0])
+}
+
+//how long the run of digits counting up or down by one is at the end of the id
+fn trailing_straight(digits: &[u8]) -> usize {
+    if digits.len() < 2 {
+        return digits.len()
+    }
+    let step = digits[digits.len() - 1] as i8 - digits[digits.len() - 2] as i8;
+    if step != 1 && step != -1 {
+        return 1
+    }
+    1 + digits.windows(2).rev()
+        .take_while(|pair| pair[1] as i8 - pair[0] as i8 == step)
+        .count()
+}
+
+//what to react with given a guild's overrides, None if the guild turned the pattern off
+pub fn reaction_for(overrides: &HashMap<Pattern, String>, pattern: Pattern) -> Option<ReactionType> {
+    match overrides.get(&pattern) {
+        Some(emoji) if emoji == "none" => None,
+        Some(emoji) => Some(parse_emoji(emoji).unwrap_or_else(|| pattern.fallback_reaction())),
+        None => Some(pattern.default_reaction()),
+    }
+}
+
+//a custom emoji (<:name:id> or <a:name:id>) or nothing but emoji, serenity takes any other text as a unicode emoji
+//that discord then refuses to react with
+pub fn parse_emoji(text: &str) -> Option<ReactionType> {
+    if text.starts_with('<') {
+        let parts: Vec<&str> = text.strip_prefix('<')?.strip_suffix('>')?.split(':').collect();
+        let custom = match parts[..] {
+            ["", name, id] | ["a", name, id] => !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()),
+            _ => false,
+        };
+        return if custom {ReactionType::from_str(text).ok()} else {None}
+    }
+    let mut chars = text.chars().peekable();
+    let mut pictures = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            //keycaps like 2️⃣ start with a plain character
+            '0'..='9' | '#' | '*' => {
+                if chars.peek() == Some(&'\u{fe0f}') {
+                    chars.next();
+                }
+                if chars.next() != Some('\u{20e3}') {
+                    return None
+                }
+                pictures += 1;
+            },
+            //joiners, variation selectors, tags and the keycap mark only go with the rest
+            '\u{200d}' | '\u{fe0e}' | '\u{fe0f}' | '\u{20e3}' | '\u{e0020}'..='\u{e007f}' => {},
+            '\u{a9}' | '\u{ae}' | '\u{203c}' | '\u{2049}' | '\u{2122}' | '\u{2139}' | '\u{2194}'..='\u{21ff}'
+                | '\u{2300}'..='\u{23ff}' | '\u{24c2}' | '\u{25aa}'..='\u{25fe}' | '\u{2600}'..='\u{27bf}'
+                | '\u{2934}' | '\u{2935}' | '\u{2b00}'..='\u{2bff}' | '\u{3030}' | '\u{303d}' | '\u{3297}' | '\u{3299}'
+                | '\u{1f000}'..='\u{1faff}' => pictures += 1,
+            _ => return None,
+        }
+    }
+    if pictures > 0 {Some(ReactionType::Unicode(text.to_string()))} else {None}
+}
@@ -0,0 +1,37 @@
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use crate::scheduler;
+
+//where "now" comes from and which wall clock it's read on, so reminders can be parsed and fired at a made up time
+//and in a made up timezone in the tests
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    //now on the bot's wall clock, which is what reminders, bonks and gets are kept in
+    fn local_naive(&self) -> NaiveDateTime;
+
+    //the first time in text that's after now, on the same wall clock
+    fn find_time(&self, text: &str) -> Option<NaiveDateTime>;
+}
+
+//the real time, read in tz
+pub struct SystemClock<Tz>(pub Tz);
+
+impl SystemClock<Local> {
+    pub fn local() -> SystemClock<Local> {
+        SystemClock(Local)
+    }
+}
+
+impl<Tz: TimeZone + Send + Sync> Clock for SystemClock<Tz> {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn local_naive(&self) -> NaiveDateTime {
+        self.now().with_timezone(&self.0).naive_local()
+    }
+
+    fn find_time(&self, text: &str) -> Option<NaiveDateTime> {
+        scheduler::find_time(text, &self.now().with_timezone(&self.0))
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+//why a command didn't work, which decides what the user is told and whether the owners hear about it
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandError {
+    //they asked for something that can't be done, the message is for them
+    User(String),
+    //discord or a website didn't answer, trying again later might work
+    Transient(String),
+    //a bug or a broken file, the message is for the log and the owners
+    Internal(String),
+}
+
+pub type CommandReply = Result<crate::invocation::Reply, CommandError>;
+
+impl CommandError {
+    //what the user sees, internal details stay in the log
+    pub fn user_message(&self) -> String {
+        match self {
+            CommandError::User(message) => message.clone(),
+            CommandError::Transient(message) => format!("{}, try again in a bit", message),
+            CommandError::Internal(_) => "something broke on my end, the bot owners have been told".to_string(),
+        }
+    }
+
+    //left on the message that failed
+    pub fn reaction(&self) -> char {
+        match self {
+            CommandError::User(_) => '❌',
+            CommandError::Transient(_) => '⏳',
+            CommandError::Internal(_) => '💥',
+        }
+    }
+
+    //for the logs and the metrics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CommandError::User(_) => "user",
+            CommandError::Transient(_) => "transient",
+            CommandError::Internal(_) => "internal",
+        }
+    }
+
+    pub fn is_internal(&self) -> bool {
+        matches!(self, CommandError::Internal(_))
+    }
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandError::User(message) => write!(f, "{}", message),
+            CommandError::Transient(message) => write!(f, "transient: {}", message),
+            CommandError::Internal(message) => write!(f, "internal: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+//plain messages are the user's mistake, like they always were
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::User(message)
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::User(message.to_string())
+    }
+}
+
+//what a failed prefix command hands to the after hook, anything that isn't a CommandError is a bug
+pub fn from_boxed(error: &(dyn std::error::Error + Send + Sync + 'static)) -> CommandError {
+    match error.downcast_ref::<CommandError>() {
+        Some(error) => error.clone(),
+        None => CommandError::Internal(crate::logging::chain(error)),
+    }
+}
+
+//keeps a broken command from flooding the owners' DMs, one report per interval and a count of what was held back
+pub struct ReportLimiter {
+    interval: Duration,
+    last_sent: Option<Instant>,
+    held_back: usize,
+}
+
+impl ReportLimiter {
+    pub fn new(interval: Duration) -> ReportLimiter {
+        ReportLimiter {interval, last_sent: None, held_back: 0}
+    }
+
+    //Some(how many were held back since the last one) if this one should be sent
+    pub fn allow(&mut self, now: Instant) -> Option<usize> {
+        match self.last_sent {
+            Some(last_sent) if now.duration_since(last_sent) < self.interval => {
+                self.held_back += 1;
+                None
+            },
+            _ => {
+                self.last_sent = Some(now);
+                Some(std::mem::take(&mut self.held_back))
+            },
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use serde::{Deserialize, Serialize};
+use crate::assets;
+use crate::checkem::Pattern;
+
+//every setting that can be changed with !config, and what it does
+pub const SETTINGS: &[(&str, &str)] = &[
+    ("prefix", "what commands start with, mentioning the bot always works too (up to 5 characters, no spaces)"),
+    ("mock_count", "how many messages a !mock target gets mocked for (1-20)"),
+    ("bonk_no_repeat", "how many of the last bonk templates used in a channel a random bonk avoids (0-10)"),
+    ("gets_tracking", "whether check 'em hits are logged for !gets (true/false)"),
+    ("hall_of_fame", "channel quads and better get reposted to (a channel or off)"),
+    ("moderator_role", "role that can use moderator commands like !features, on top of anyone who can manage messages (a role or off)"),
+];
+
+//everything that can be turned off per guild or channel with !features
+//commands go by their name, the rest are things the bot does on its own and mustn't share a name with a command
+pub const FEATURES: &[(&str, &str)] = &[
+    ("mock", "the !mock command"),
+    ("bonk", "the !bonk command"),
+    ("remind", "the !remind command"),
+    ("flip", "the !flip command"),
+    ("gets", "the !gets command"),
+    ("help", "the !help command"),
+    ("mock_replay", "repeating what !mock targets say"),
+    ("checkem_reactions", "check 'em reactions and logging gets"),
+    ("gallery", "looking up message ids in nsfw channels"),
+];
+
+pub fn is_feature(name: &str) -> bool {
+    FEATURES.iter().any(|(feature, _)| *feature == name)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct GuildConfig {
+    pub prefix: String,
+    pub mock_count: u32,
+    pub bonk_no_repeat: u32,
+    pub gets_tracking: bool,
+    pub hall_of_fame: Option<u64>,
+    pub moderator_role: Option<u64>,
+    //pattern -> emoji as it would be typed in chat, or "none" to not react at all, managed with !checkem
+    pub checkem_emoji: HashMap<Pattern, String>,
+    //features turned off for the whole guild
+    pub disabled: HashSet<String>,
+    //channel -> feature -> on or off, these win over the guild wide setting
+    pub channel_features: HashMap<u64, HashMap<String, bool>>,
+}
+
+impl Default for GuildConfig {
+    fn default() -> Self {
+        GuildConfig {
+            prefix: "!".to_string(),
+            mock_count: 3,
+            bonk_no_repeat: 3,
+            gets_tracking: true,
+            hall_of_fame: None,
+            moderator_role: None,
+            checkem_emoji: HashMap::new(),
+            disabled: HashSet::new(),
+            channel_features: HashMap::new(),
+        }
+    }
+}
+
+impl GuildConfig {
+    pub fn get(&self, key: &str) -> Result<String, String> {
+        match key {
+            "prefix" => Ok(self.prefix.clone()),
+            "mock_count" => Ok(self.mock_count.to_string()),
+            "bonk_no_repeat" => Ok(self.bonk_no_repeat.to_string()),
+            "gets_tracking" => Ok(self.gets_tracking.to_string()),
+            "hall_of_fame" => Ok(match self.hall_of_fame {
+                Some(channel) => format!("<#{}>", channel),
+                None => "off".to_string(),
+            }),
+            "moderator_role" => Ok(match self.moderator_role {
+                Some(role) => format!("<@&{}>", role),
+                None => "off".to_string(),
+            }),
+            _ => Err(unknown_key(key)),
+        }
+    }
+
+    //parses and validates value, nothing changes if it's no good
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "prefix" => self.prefix = parse_prefix(key, value)?,
+            "mock_count" => self.mock_count = parse_range(key, value, 1, 20)?,
+            "bonk_no_repeat" => self.bonk_no_repeat = parse_range(key, value, 0, 10)?,
+            "gets_tracking" => self.gets_tracking = parse_bool(key, value)?,
+            "hall_of_fame" => self.hall_of_fame = match value {
+                "off" | "none" => None,
+                _ => match serenity::utils::parse_channel(value).or_else(|| value.parse().ok()) {
+                    Some(channel) => Some(channel),
+                    None => return Err(format!("{} needs a channel or off, not '{}'", key, value)),
+                },
+            },
+            "moderator_role" => self.moderator_role = match value {
+                "off" | "none" => None,
+                _ => match serenity::utils::parse_role(value).or_else(|| value.parse().ok()) {
+                    Some(role) => Some(role),
+                    None => return Err(format!("{} needs a role or off, not '{}'", key, value)),
+                },
+            },
+            _ => return Err(unknown_key(key)),
+        }
+        Ok(())
+    }
+
+    pub fn reset(&mut self, key: &str) -> Result<(), String> {
+        let default = GuildConfig::default();
+        let value = default.get(key)?;
+        self.set(key, &value)
+    }
+
+    //anything that isn't a feature (like !config) can't be turned off
+    pub fn feature_enabled(&self, feature: &str, channel: u64) -> bool {
+        if !is_feature(feature) {
+            return true
+        }
+        match self.channel_features.get(&channel).and_then(|features| features.get(feature)) {
+            Some(enabled) => *enabled,
+            None => !self.disabled.contains(feature),
+        }
+    }
+
+    //checkem_reactions used to be called checkem, which turned off the !checkem command along with it
+    fn rename_old_features(&mut self) {
+        if self.disabled.remove("checkem") {
+            self.disabled.insert("checkem_reactions".to_string());
+        }
+        for features in self.channel_features.values_mut() {
+            if let Some(enabled) = features.remove("checkem") {
+                features.insert("checkem_reactions".to_string(), enabled);
+            }
+        }
+    }
+
+    //channel None changes it for the whole guild, which also drops that feature's channel overrides
+    pub fn set_feature(&mut self, feature: &str, channel: Option<u64>, enabled: bool) -> Result<(), String> {
+        if !is_feature(feature) {
+            return Err(format!("'{}' isn't a feature, try one of: {}", feature, FEATURES.iter().map(|(feature, _)| *feature).collect::<Vec<_>>().join(", ")))
+        }
+        match channel {
+            Some(channel) => {
+                self.channel_features.entry(channel).or_default().insert(feature.to_string(), enabled);
+            },
+            None => {
+                if enabled {
+                    self.disabled.remove(feature);
+                }
+                else {
+                    self.disabled.insert(feature.to_string());
+                }
+                for features in self.channel_features.values_mut() {
+                    features.remove(feature);
+                }
+                self.channel_features.retain(|_, features| !features.is_empty());
+            },
+        }
+        Ok(())
+    }
+}
+
+fn unknown_key(key: &str) -> String {
+    format!("'{}' isn't a setting, try one of: {}", key, SETTINGS.iter().map(|(key, _)| *key).collect::<Vec<_>>().join(", "))
+}
+
+//discord eats leading spaces and backticks/mentions would make a mess of it
+fn parse_prefix(key: &str, value: &str) -> Result<String, String> {
+    let length = value.chars().count();
+    if length == 0 || length > 5 || value.contains(char::is_whitespace) || value.contains(['`', '<', '@']) {
+        return Err(format!("{} needs 1 to 5 characters without spaces, backticks, < or @, not '{}'", key, value))
+    }
+    Ok(value.to_string())
+}
+
+fn parse_range(key: &str, value: &str, min: u32, max: u32) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(number) if number >= min && number <= max => Ok(number),
+        _ => Err(format!("{} needs a number from {} to {}, not '{}'", key, min, max, value)),
+    }
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "on" | "yes" => Ok(true),
+        "false" | "off" | "no" => Ok(false),
+        _ => Err(format!("{} needs true or false, not '{}'", key, value)),
+    }
+}
+
+//guild id -> settings, guilds that never changed anything aren't in here
+pub type GuildConfigs = HashMap<u64, GuildConfig>;
+
+//no file yet is no configs, one that doesn't read or parse is an error
+pub fn load_configs(dir: &str) -> Result<GuildConfigs, String> {
+    let mut configs = assets::read_saved_json::<GuildConfigs>(dir, "guild_config.json")?;
+    configs.values_mut().for_each(GuildConfig::rename_old_features);
+    Ok(configs)
+}
+
+pub fn save_configs(dir: &str, configs: &GuildConfigs) -> Result<(), Box<dyn Error>> {
+    let json_content = serde_json::to_string(configs)?;
+    std::fs::write(format!("{}/guild_config.json", dir), json_content)?;
+    Ok(())
+}
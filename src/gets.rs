@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::error::Error;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use crate::assets;
+use crate::checkem::Pattern;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GetRecord {
+    pub guild: Option<u64>,
+    pub channel: u64,
+    pub message: u64,
+    pub user: u64,
+    pub pattern: Pattern,
+    pub time: NaiveDateTime,
+}
+
+impl GetRecord {
+    pub fn link(&self) -> String {
+        match self.guild {
+            Some(guild) => format!("https://discord.com/channels/{}/{}/{}", guild, self.channel, self.message),
+            None => format!("https://discord.com/channels/@me/{}/{}", self.channel, self.message),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GetsLog {
+    pub records: Vec<GetRecord>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GetsScore {
+    pub user: u64,
+    pub points: u64,
+    pub count: usize,
+    pub best: Pattern,
+}
+
+//roughly how many times rarer than dubs each pattern is
+pub fn points(pattern: Pattern) -> u64 {
+    match pattern {
+        Pattern::Dubs => 1,
+        Pattern::Trips => 10,
+        Pattern::Quads | Pattern::Palindrome => 100,
+        Pattern::Quints | Pattern::Straight | Pattern::Round => 1_000,
+        Pattern::Sexes => 10_000,
+        Pattern::Septs => 100_000,
+        Pattern::Octs => 1_000_000,
+        Pattern::Nons => 10_000_000,
+        Pattern::Decs => 100_000_000,
+    }
+}
+
+//quads and anything at least as rare
+pub fn is_hall_of_fame(pattern: Pattern) -> bool {
+    points(pattern) >= points(Pattern::Quads)
+}
+
+pub fn leaderboard(log: &GetsLog, guild: Option<u64>) -> Vec<GetsScore> {
+    let mut scores: HashMap<u64, GetsScore> = HashMap::new();
+    for record in log.records.iter().filter(|record| record.guild == guild) {
+        let score = scores.entry(record.user).or_insert(GetsScore {
+            user: record.user,
+            points: 0,
+            count: 0,
+            best: record.pattern,
+        });
+        score.points += points(record.pattern);
+        score.count += 1;
+        if points(record.pattern) > points(score.best) {
+            score.best = record.pattern;
+        }
+    }
+    let mut scores: Vec<GetsScore> = scores.into_values().collect();
+    scores.sort_by(|a, b| b.points.cmp(&a.points).then_with(|| a.user.cmp(&b.user)));
+    scores
+}
+
+//this user's gets in the guild, newest first
+pub fn history(log: &GetsLog, guild: Option<u64>, user: u64) -> Vec<&GetRecord> {
+    let mut records: Vec<&GetRecord> = log.records.iter()
+        .filter(|record| record.guild == guild && record.user == user)
+        .collect();
+    records.sort_by_key(|record| std::cmp::Reverse(record.time));
+    records
+}
+
+pub fn load_gets(dir: &str) -> Result<GetsLog, String> {
+    assets::read_saved_json(dir, "gets.json")
+}
+
+pub fn save_gets(dir: &str, log: &GetsLog) -> Result<(), Box<dyn Error>> {
+    let json_content = serde_json::to_string(log)?;
+    std::fs::write(format!("{}/gets.json", dir), json_content)?;
+    Ok(())
+}
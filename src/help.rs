@@ -0,0 +1,110 @@
+use serenity::framework::standard::{Command, OnlyIn};
+use crate::invocation::Reply;
+
+//commands on each page of !help
+pub const PAGE_SIZE: usize = 6;
+
+//follows the path through subcommands, by name or alias. gives back the full name, e.g. "bonk leaderboard"
+pub fn find(commands: &[&'static Command], path: &[String]) -> Option<(String, &'static Command)> {
+    let (first, rest) = path.split_first()?;
+    let first = first.to_lowercase();
+    let command = commands.iter().find(|command| command.options.names.contains(&first.as_str()))?;
+    let name = command.options.names[0].to_string();
+    if rest.is_empty() {
+        return Some((name, command))
+    }
+    let (sub_name, sub_command) = find(command.options.sub_commands, rest)?;
+    Some((format!("{} {}", name, sub_name), sub_command))
+}
+
+//who's allowed to use it, from its checks and permissions
+pub fn needs(command: &Command) -> Option<String> {
+    let mut needs: Vec<String> = command.options.checks.iter().map(|check| check.name.to_lowercase()).collect();
+    if !command.options.required_permissions.is_empty() {
+        needs.push(command.options.required_permissions.to_string());
+    }
+    if needs.is_empty() {
+        None
+    }
+    else {
+        Some(needs.join(", "))
+    }
+}
+
+pub fn usage(prefix: &str, full_name: &str, command: &Command) -> String {
+    match command.options.usage {
+        Some(usage) => format!("{}{} {}", prefix, full_name, usage),
+        None => format!("{}{}", prefix, full_name),
+    }
+}
+
+//a page of every command enabled says yes to, pages start at 1
+pub fn overview<F: Fn(&str) -> bool>(commands: &[&'static Command], prefix: &str, page: usize, enabled: F) -> Result<Reply, String> {
+    let shown: Vec<&&Command> = commands.iter()
+        .filter(|command| command.options.help_available && enabled(command.options.names[0]))
+        .collect();
+    let pages = shown.len().div_ceil(PAGE_SIZE).max(1);
+    if page == 0 || page > pages {
+        return Err(format!("there {} only {} page{} of commands", if pages == 1 {"is"} else {"are"}, pages, if pages == 1 {""} else {"s"}))
+    }
+    let mut description = String::new();
+    for command in shown.iter().skip((page - 1) * PAGE_SIZE).take(PAGE_SIZE) {
+        description.push_str(&format!("`{}`\n", usage(prefix, command.options.names[0], command)));
+        if let Some(desc) = command.options.desc {
+            description.push_str(desc);
+        }
+        if let Some(needs) = needs(command) {
+            description.push_str(&format!(" *(needs {})*", needs));
+        }
+        description.push('\n');
+    }
+    description.push_str(&format!("\n`{}help <command>` for more about one", prefix));
+    if page < pages {
+        description.push_str(&format!(", `{}help {}` for the next page", prefix, page + 1));
+    }
+    //owner commands are only shown to owners, and only get typed
+    if shown.iter().any(|command| command.options.checks.iter().any(|check| check.name == "Owner")) {
+        description.push_str(". Everything but the owner commands works as a slash command too.");
+    }
+    else {
+        description.push_str(". They all work as slash commands too.");
+    }
+    Ok(Reply::embed(format!("Commands ({}/{})", page, pages), description))
+}
+
+pub fn details(prefix: &str, full_name: &str, command: &Command) -> Reply {
+    let options = command.options;
+    let mut description = String::new();
+    if let Some(desc) = options.desc {
+        description.push_str(&format!("{}\n\n", desc));
+    }
+    description.push_str(&format!("**Usage:** `{}`\n", usage(prefix, full_name, command)));
+    if options.names.len() > 1 {
+        let aliases: Vec<String> = options.names[1..].iter().map(|alias| format!("`{}{}`", prefix, alias)).collect();
+        description.push_str(&format!("**Aliases:** {}\n", aliases.join(", ")));
+    }
+    if let Some(needs) = needs(command) {
+        description.push_str(&format!("**Needs:** {}\n", needs));
+    }
+    if options.only_in == OnlyIn::Guild {
+        description.push_str("**Only in servers**\n");
+    }
+    if !options.examples.is_empty() {
+        description.push_str("**Examples:**\n");
+        for example in options.examples {
+            description.push_str(&format!("`{}{} {}`\n", prefix, full_name, example));
+        }
+    }
+    if !options.sub_commands.is_empty() {
+        description.push_str("**Subcommands:**\n");
+        for sub_command in options.sub_commands {
+            let sub_name = format!("{} {}", full_name, sub_command.options.names[0]);
+            description.push_str(&format!("`{}`", usage(prefix, &sub_name, sub_command)));
+            if let Some(desc) = sub_command.options.desc {
+                description.push_str(&format!(" - {}", desc));
+            }
+            description.push('\n');
+        }
+    }
+    Reply::embed(format!("{}{}", prefix, full_name), description)
+}
@@ -0,0 +1,115 @@
+use chrono::NaiveDateTime;
+use serenity::{
+    framework::standard::Args,
+    model::{channel::Message, user::User},
+};
+use crate::surface::ChatSurface;
+use tracing::{error, warn};
+
+//everything a command gets to know about how it was called, the same whether it was typed or a slash command
+#[derive(Debug, Clone)]
+pub struct Invocation {
+    pub guild: Option<u64>,
+    pub channel: u64,
+    pub author: User,
+    //users mentioned (or picked in slash command options), in order
+    pub users: Vec<User>,
+    //the arguments as typed, slash command options are flattened into the same shape
+    pub args: Vec<String>,
+    //urls of attached images
+    pub attachments: Vec<String>,
+    //author of the message being replied to
+    pub replied_to: Option<User>,
+}
+
+impl Invocation {
+    pub fn from_message(msg: &Message, mut args: Args) -> Invocation {
+        Invocation {
+            guild: msg.guild_id.map(|id| *id.as_u64()),
+            channel: *msg.channel_id.as_u64(),
+            author: msg.author.clone(),
+            users: msg.mentions.clone(),
+            args: args.iter::<String>().filter_map(|arg| arg.ok()).collect(),
+            //only images have dimensions
+            attachments: msg.attachments.iter()
+                .filter(|attachment| attachment.dimensions().is_some())
+                .map(|attachment| attachment.url.clone())
+                .collect(),
+            replied_to: msg.referenced_message.as_ref().map(|replied_to| replied_to.author.clone()),
+        }
+    }
+
+    //mentioned user, or whoever used the command
+    pub fn target(&self) -> &User {
+        self.users.first().unwrap_or(&self.author)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReplyEmbed {
+    pub title: String,
+    pub description: String,
+    pub thumbnail: Option<String>,
+    pub author: Option<String>,
+    pub author_icon: Option<String>,
+    pub timestamp: Option<String>,
+}
+
+//what a command wants to say back, commands that fail return a CommandError instead
+#[derive(Debug, Clone, Default)]
+pub struct Reply {
+    pub content: String,
+    pub embed: Option<ReplyEmbed>,
+    //image to attach, shown inside the embed if there is one. it's made for this reply alone and deleted once it's sent
+    pub file: Option<String>,
+    //only the user who called the command sees it, prefix commands have nowhere private to put these so they're skipped
+    pub ephemeral: bool,
+    //the sent reply becomes a reminder for this time
+    pub reminder: Option<NaiveDateTime>,
+}
+
+impl Reply {
+    pub fn text<S: Into<String>>(content: S) -> Reply {
+        Reply {content: content.into(), ..Default::default()}
+    }
+
+    pub fn embed<S: Into<String>>(title: S, description: String) -> Reply {
+        Reply {
+            embed: Some(ReplyEmbed {title: title.into(), description, ..Default::default()}),
+            ..Default::default()
+        }
+    }
+}
+
+//posts a reply in the channel, as a reply to msg if it's setting up a reminder
+pub async fn send_reply(chat: &dyn ChatSurface, channel: u64, msg: Option<&Message>, reply: Reply) -> Option<Message> {
+    if reply.ephemeral || (reply.content.is_empty() && reply.embed.is_none() && reply.file.is_none()) {
+        discard(&reply);
+        return None
+    }
+    let reference = msg.filter(|_| reply.reminder.is_some());
+    let sent = match chat.send(channel, reference, &reply).await {
+        Ok(sent) => Some(sent),
+        Err(why) => {
+            error!(error = %why, "could not send message");
+            None
+        }
+    };
+    discard(&reply);
+    sent
+}
+
+//for replies that won't be sent, so their image doesn't pile up in the data folder
+pub fn discard(reply: &Reply) {
+    if let Some(file) = &reply.file {
+        remove_attachment(file);
+    }
+}
+
+pub fn remove_attachment(file: &str) {
+    if let Err(e) = std::fs::remove_file(file) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!(file, error = %e, "could not delete attachment");
+        }
+    }
+}
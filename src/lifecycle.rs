@@ -0,0 +1,110 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+//whether the bot is still taking commands, and how many it's in the middle of
+pub struct Lifecycle {
+    shutting_down: AtomicBool,
+    in_flight: AtomicUsize,
+    //the !shutdown command asking for the same thing a signal does
+    requested: Notify,
+    pub started: Instant,
+}
+
+//held while a command or message is being handled, dropping it marks it done
+pub struct InFlight(Arc<Lifecycle>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Lifecycle {
+            shutting_down: AtomicBool::new(false),
+            in_flight: AtomicUsize::new(0),
+            requested: Notify::new(),
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Lifecycle {
+    //None once shutting down, so nothing new gets started
+    pub fn begin(self: &Arc<Self>) -> Option<InFlight> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        //counted first so a shutdown that starts in between still waits for it
+        if self.is_shutting_down() {
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            return None
+        }
+        Some(InFlight(self.clone()))
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    pub fn stop(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+    }
+
+    pub fn request(&self) {
+        self.requested.notify_one();
+    }
+
+    //how many were still going when the time ran out, 0 if everything finished
+    pub async fn wait_idle(&self, timeout: Duration) -> usize {
+        let deadline = Instant::now() + timeout;
+        while self.in_flight() > 0 && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        self.in_flight()
+    }
+
+    //a signal or !shutdown, whichever comes first
+    pub async fn wait_for_request(&self) -> &'static str {
+        tokio::select! {
+            name = signal() => name,
+            _ = self.requested.notified() => "!shutdown",
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn signal() -> &'static str {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            tracing::error!(error = %e, "can't listen for SIGTERM, only ctrl-c will shut down cleanly");
+            ctrl_c().await;
+            return "SIGINT"
+        }
+    };
+    tokio::select! {
+        _ = terminate.recv() => "SIGTERM",
+        _ = ctrl_c() => "SIGINT",
+    }
+}
+
+#[cfg(not(unix))]
+async fn signal() -> &'static str {
+    ctrl_c().await;
+    "ctrl-c"
+}
+
+//if ctrl-c can't be listened for it just never comes, instead of shutting down straight away
+async fn ctrl_c() {
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        tracing::error!(error = %e, "can't listen for ctrl-c");
+        std::future::pending::<()>().await;
+    }
+}
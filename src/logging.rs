@@ -0,0 +1,32 @@
+use std::error::Error;
+use tracing_subscriber::EnvFilter;
+
+pub const LOG_FORMATS: &[&str] = &["text", "json"];
+
+//RUST_LOG wins if it's set, otherwise the bot logs at level and everything else (serenity, reqwest...) only warns
+pub fn init(level: &str, format: &str) {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(format!("warn,shawty_bot={}", level)));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    if format == "json" {
+        builder.json().with_current_span(true).with_span_list(false).init();
+    }
+    else {
+        builder.init();
+    }
+}
+
+//the error and everything that caused it, e.g. "Http error: error sending request: connection refused"
+pub fn chain(error: &dyn Error) -> String {
+    let mut chain = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let cause_text = cause.to_string();
+        //serenity and reqwest often repeat their source in their own message
+        if !chain.contains(&cause_text) {
+            chain.push_str(": ");
+            chain.push_str(&cause_text);
+        }
+        source = cause.source();
+    }
+    chain
+}
@@ -1,29 +1,53 @@
 use std::env;
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::prelude::*;
+use std::process;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::time::{Duration, Instant};
 use std::error::Error;
+use std::sync::Arc;
 use rand::Rng;
-use rand::seq::SliceRandom;
 use serde::{Deserialize, Serialize};
+use chrono::Timelike;
 use serde_json::{Value};
 use serenity:: {
     async_trait,
-    model::{channel::Message, channel::ReactionType, gateway::Ready, gateway::Activity},
+    model::{channel::Message, channel::ReactionType, event::ResumedEvent, gateway::Ready, interactions::Interaction, id::{GuildId, UserId}, permissions::Permissions},
+    client::bridge::gateway::ShardManager,
     prelude::*,
-    framework::StandardFramework,
+    http::Http,
+    framework::{Framework, StandardFramework},
     framework::standard::{
-        CommandResult, macros::{group, command},
+        Args, CommandOptions, CommandResult, DispatchError, Reason, macros::{group, command, hook, check},
     },
     utils::{MessageBuilder},
 };
+use command_error::{CommandError, CommandReply};
+use invocation::{Invocation, Reply};
+use tracing::{debug, error, info, warn, Instrument};
 
 #[cfg(test)]
 mod test;
 
 mod scheduler;
 mod bonker;
+mod bonk_stats;
+mod avatar;
+mod checkem;
+mod gets;
+mod config;
+mod invocation;
+mod slash;
+mod help;
+mod permissions;
+mod assets;
+mod settings;
+mod activity;
+mod logging;
+mod command_error;
+mod metrics;
+mod web;
+mod lifecycle;
+mod surface;
+mod clock;
 
 struct MockTracker;
 
@@ -31,222 +55,1780 @@ impl TypeMapKey for MockTracker {
     type Value = HashMap<u64, isize>;
 }
 
-struct BotOwner;
+//user ids allowed to do anything, see permissions::load_owners
+struct BotOwners;
 
-impl TypeMapKey for BotOwner {
+impl TypeMapKey for BotOwners {
+    type Value = HashSet<u64>;
+}
+
+struct ShardManagerContainer;
+impl TypeMapKey for ShardManagerContainer {
+    type Value = Arc<Mutex<ShardManager>>;
+}
+
+struct ReminderList;
+impl TypeMapKey for ReminderList{
+    type Value = Vec<Reminder>;
+}
+
+//the last few bonk templates used in each channel, most recent last
+struct BonkHistory;
+impl TypeMapKey for BonkHistory{
+    type Value = HashMap<u64, VecDeque<String>>;
+}
+
+//every bonk ever handed out, for the leaderboards
+struct BonkStats;
+impl TypeMapKey for BonkStats{
+    type Value = Vec<bonk_stats::BonkRecord>;
+}
+
+//per guild settings, changed with !config
+struct GuildConfigs;
+impl TypeMapKey for GuildConfigs{
+    type Value = config::GuildConfigs;
+}
+
+//every get, for the !gets leaderboards
+struct GetsTracker;
+impl TypeMapKey for GetsTracker{
+    type Value = gets::GetsLog;
+}
+
+//activities and bonk templates, swapped out whole when the files change
+struct AssetRegistry;
+impl TypeMapKey for AssetRegistry{
+    type Value = Arc<assets::Assets>;
+}
+
+//when the owners were last told about a command breaking
+struct ErrorReports;
+impl TypeMapKey for ErrorReports{
+    type Value = command_error::ReportLimiter;
+}
+
+//counters and timings for /metrics
+struct BotMetrics;
+impl TypeMapKey for BotMetrics{
+    type Value = Arc<metrics::Metrics>;
+}
+
+//when the reminder loop last woke up, for /healthz
+struct SchedulerHeartbeat;
+impl TypeMapKey for SchedulerHeartbeat{
+    type Value = Instant;
+}
+
+//there once the first ready has started the background loops, so reconnects don't start more of them
+struct BackgroundStarted;
+impl TypeMapKey for BackgroundStarted{
+    type Value = ();
+}
+
+//whether the bot is shutting down, and what it's still in the middle of
+struct BotLifecycle;
+impl TypeMapKey for BotLifecycle{
+    type Value = Arc<lifecycle::Lifecycle>;
+}
+
+//where everything the bot says goes, discord itself outside of the tests
+struct Chat;
+impl TypeMapKey for Chat{
+    type Value = Arc<dyn surface::ChatSurface>;
+}
+
+//what time it is, a fixed one in the tests
+struct BotClock;
+impl TypeMapKey for BotClock{
+    type Value = Arc<dyn clock::Clock>;
+}
+
+//a status that stays put instead of rotating, set with !activity set or pin
+struct PinnedActivity;
+impl TypeMapKey for PinnedActivity{
+    type Value = Option<activity::Entry>;
+}
+
+//where things are kept, from the command line and settings file
+struct BotSettings;
+impl TypeMapKey for BotSettings{
+    type Value = settings::Settings;
+}
+
+//the bot's application id, slash command responses need it
+struct SlashApplication;
+impl TypeMapKey for SlashApplication{
     type Value = u64;
 }
 
-struct StatusTimer;
-impl TypeMapKey for StatusTimer{
-    type Value = Instant;
+
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Reminder {
+    date_time: chrono::NaiveDateTime,
+    message: serenity::model::channel::Message,
+    verification_message: serenity::model::channel::Message,
+    //who asked for it, 0 for reminders from before this was kept (the author of message)
+    #[serde(default)]
+    requester: u64,
+}
+//runs the standard framework inside a span for the message, the before hook fills in the command once it's known
+struct TracedFramework(StandardFramework);
+
+#[async_trait]
+impl Framework for TracedFramework {
+    async fn dispatch(&self, ctx: Context, msg: Message) {
+        let _in_flight = match begin_work(&ctx).await {
+            Some(in_flight) => in_flight,
+            None => return,
+        };
+        let span = tracing::info_span!("command",
+            guild = msg.guild_id.map(|id| *id.as_u64()),
+            channel = *msg.channel_id.as_u64(),
+            user = *msg.author.id.as_u64(),
+            command = tracing::field::Empty,
+        );
+        self.0.dispatch(ctx, msg).instrument(span).await
+    }
+}
+
+//owners get at most one DM about broken commands this often
+const REPORT_MINUTES: u64 = 10;
+
+//how long commands that are already running get to finish when shutting down
+const SHUTDOWN_GRACE_SECONDS: u64 = 15;
+
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let settings = match settings::load(&args) {
+        Ok(Some(settings)) => settings,
+        Ok(None) => {
+            println!("{}", settings::USAGE);
+            return
+        },
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        },
+    };
+    logging::init(&settings.log_level, &settings.log_format);
+    info!(assets = %settings.assets_dir, data = %settings.data_dir, "starting");
+    let token = match settings::read_token(&settings) {
+        Ok(token) => token,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        },
+    };
+    let http = Http::new_with_token(&token);
+    let me = match http.get_current_user().await {
+        Ok(user) => Some(user.id),
+        Err(e) => {
+            warn!(error = %logging::chain(&e), "couldn't get current user, commands won't work by mention");
+            None
+        }
+    };
+    let mut owners: HashSet<u64> = settings.owners.iter().copied().collect();
+    if owners.is_empty() {
+        owners = permissions::load_owners(&settings.data_dir);
+    }
+    if owners.is_empty() {
+        //nobody configured, so whoever owns the bot's application
+        match http.get_current_application_info().await {
+            Ok(info) => {
+                owners.insert(*info.owner.id.as_u64());
+            },
+            Err(e) => warn!(error = %logging::chain(&e), "couldn't get application info, there are no bot owners"),
+        }
+    }
+    info!(?owners, "bot owners");
+    let framework = framework(me, &owners);
+
+    let http_addr = settings.http_addr;
+    let lifecycle = Arc::new(lifecycle::Lifecycle::default());
+    let mut client = match Client::builder(&token).event_handler(Handler).framework(framework).await {
+        Ok(client) => client,
+        Err(e) => {
+            error!(error = %logging::chain(&e), "could not create the client");
+            process::exit(1);
+        },
+    };
+
+    {
+        let mut data = client.data.write().await;
+        load_state(&mut data, settings, owners, Arc::new(clock::SystemClock::local()));
+        data.insert::<ShardManagerContainer>(client.shard_manager.clone());
+        data.insert::<BotLifecycle>(lifecycle.clone());
+        data.insert::<Chat>(Arc::new(surface::Discord::new(client.cache_and_http.cache.clone(), client.cache_and_http.http.clone())));
+    }
+    if let Some(addr) = http_addr {
+        tokio::spawn(web::serve(addr, client.data.clone(), client.cache_and_http.http.clone()));
+    }
+
+    let shutting_down = tokio::spawn(shut_down_when_asked(client.data.clone(), client.shard_manager.clone(), lifecycle.clone()));
+    if let Err(why) = client.start().await {
+        error!(error = %logging::chain(&why), "client error");
+    }
+    //the shards stop partway through shutting down, the rest (the summary) still has to happen
+    if lifecycle.is_shutting_down() {
+        let _ = shutting_down.await;
+    }
+}
+
+//a data file that's broken at startup is started over, the error says which one
+fn or_empty<T: Default>(loaded: Result<T, String>) -> T {
+    loaded.unwrap_or_else(|e| {
+        error!(error = %e, "starting it over empty");
+        T::default()
+    })
+}
+
+//everything kept in the data folder and the assets, plus the bookkeeping that starts out empty
+fn load_state(data: &mut TypeMap, settings: settings::Settings, owners: HashSet<u64>, clock: Arc<dyn clock::Clock>) {
+    data.insert::<MockTracker>(load_mock_tracker(&settings.data_dir));
+    data.insert::<BotOwners>(owners);
+    data.insert::<BonkHistory>(HashMap::default());
+    data.insert::<BonkStats>(or_empty(bonk_stats::load_bonk_records(&settings.data_dir)));
+    data.insert::<GuildConfigs>(or_empty(config::load_configs(&settings.data_dir)));
+    data.insert::<GetsTracker>(or_empty(gets::load_gets(&settings.data_dir)));
+    let registry = match assets::load_assets(&settings.assets_dir) {
+        Ok(registry) => registry,
+        Err(errors) => {
+            error!(errors = %errors.join("\n"), "assets are broken, no activities or bonks until they're fixed");
+            assets::Assets::default()
+        },
+    };
+    data.insert::<AssetRegistry>(Arc::new(registry));
+    //attempt to load the reminder list from reminder_list.json in the data folder
+    let mut reminder_list: Vec<Reminder> = match std::fs::read_to_string(format!("{}/reminder_list.json", settings.data_dir)) {
+        Ok(string) => match serde_json::from_str(&string) {
+            Ok(data) => data,
+            Err(e) => {
+                error!(error = %e, "could not parse reminder_list.json");
+                Vec::new()
+            },
+        },
+        Err(e) => {
+            warn!(error = %e, "could not read reminder_list.json");
+            Vec::new()
+        },
+    };
+    let now = clock.local_naive();
+    let num_read = reminder_list.len();
+    reminder_list.retain(|reminder| reminder.date_time > now);
+    if num_read > reminder_list.len() {
+        info!(purged = num_read-reminder_list.len(), "purged expired timers that should have been fired");
+    }
+    data.insert::<ReminderList>(reminder_list);
+    data.insert::<PinnedActivity>(activity::load_pinned(&settings.data_dir));
+    data.insert::<ErrorReports>(command_error::ReportLimiter::new(Duration::from_secs(REPORT_MINUTES * 60)));
+    data.insert::<BotMetrics>(Arc::new(metrics::Metrics::default()));
+    data.insert::<BotClock>(clock);
+    data.insert::<BotSettings>(settings);
+}
+
+//every prefix command, the tests dispatch through the same one
+fn framework(me: Option<UserId>, owners: &HashSet<u64>) -> TracedFramework {
+    TracedFramework(StandardFramework::new()
+        //the prefix comes from each guild's config, "" turns off the built in one
+        .configure(|c| c.prefix("").dynamic_prefix(guild_prefix).on_mention(me)
+            .owners(owners.iter().map(|id| UserId(*id)).collect()))
+        .before(before)
+        .after(after)
+        .on_dispatch_error(dispatch_error)
+        .group(&MOCKER_GROUP)
+        .group(&OWNER_GROUP))
+}
+
+//on SIGTERM, ctrl-c or !shutdown: stop taking commands, give the ones running a chance to finish, save everything and disconnect
+async fn shut_down_when_asked(data: Arc<RwLock<TypeMap>>, shard_manager: Arc<Mutex<ShardManager>>, lifecycle: Arc<lifecycle::Lifecycle>) {
+    let reason = lifecycle.wait_for_request().await;
+    info!(reason, in_flight = lifecycle.in_flight(), "shutting down");
+    lifecycle.stop();
+    let abandoned = lifecycle.wait_idle(Duration::from_secs(SHUTDOWN_GRACE_SECONDS)).await;
+    if abandoned > 0 {
+        warn!(abandoned, "gave up waiting for commands to finish");
+    }
+    let (saved, errors) = flush_state(&data).await;
+    for e in &errors {
+        error!(error = %e, "could not save while shutting down");
+    }
+    shard_manager.lock().await.shutdown_all().await;
+    info!(
+        reason,
+        uptime_minutes = lifecycle.started.elapsed().as_secs() / 60,
+        abandoned,
+        saved = %saved.join(", "),
+        failed_to_save = errors.len(),
+        "shut down"
+    );
+}
+
+//writes out everything the bot keeps in the data folder, returning what was saved and what went wrong
+async fn flush_state(data: &RwLock<TypeMap>) -> (Vec<String>, Vec<String>) {
+    let data = data.read().await;
+    let dir = data_dir(&data);
+    let mut saved = Vec::new();
+    let mut errors = Vec::new();
+    let mut record = |name: String, result: Result<(), Box<dyn Error>>| match result {
+        Ok(()) => saved.push(name),
+        Err(e) => errors.push(format!("{}: {}", name, e)),
+    };
+    if let Some(reminders) = data.get::<ReminderList>() {
+        record(format!("{} reminders", reminders.len()), save_reminder_list(&dir, reminders));
+    }
+    if let Some(mocks) = data.get::<MockTracker>() {
+        record(format!("{} mock targets", mocks.len()), save_mock_tracker(&dir, mocks));
+    }
+    if let Some(configs) = data.get::<GuildConfigs>() {
+        record(format!("{} server configs", configs.len()), config::save_configs(&dir, configs));
+    }
+    if let Some(records) = data.get::<BonkStats>() {
+        record(format!("{} bonks", records.len()), bonk_stats::save_bonk_records(&dir, records));
+    }
+    if let Some(log) = data.get::<GetsTracker>() {
+        record(format!("{} gets", log.records.len()), gets::save_gets(&dir, log));
+    }
+    if let Some(pinned) = data.get::<PinnedActivity>() {
+        record("the pinned activity".to_string(), activity::save_pinned(&dir, pinned.as_ref()));
+    }
+    (saved, errors)
+}
+
+#[group("mocker")]
+#[commands(mock, help, bonk, remind, flip, checkem, gets, config, features, prefix)]
+struct Mocker;
+
+#[group("owner")]
+#[commands(reload_assets, shutdown, guilds, say, activity)]
+struct Owner;
+
+//answers a prefix command in the channel it came from, failures go on to the after hook
+async fn reply_to(ctx: &Context, msg: &Message, reply: CommandReply) -> CommandResult {
+    let reply = reply?;
+    let reminder = reply.reminder;
+    let sent = invocation::send_reply(chat(ctx).await.as_ref(), *msg.channel_id.as_u64(), Some(msg), reply).await;
+    if let (Some(date_time), Some(sent)) = (reminder, sent) {
+        schedule_reminder(ctx, date_time, msg.clone(), sent, *msg.author.id.as_u64()).await;
+    }
+    Ok(())
+}
+
+//every command by name, subcommands are "<command> <subcommand>". the prefix commands call these directly
+async fn run_command(ctx: &Context, name: &str, invocation: &Invocation) -> CommandReply {
+    match name {
+        "mock" => mock_reply(ctx, invocation).await,
+        "flip" => Ok(flip_reply()),
+        "help" => help_reply(ctx, invocation).await,
+        "bonk" => bonk_reply(ctx, invocation).await,
+        "bonk list" => bonk_list_reply(ctx).await,
+        "bonk stats" => bonk_stats_reply(ctx, invocation).await,
+        "bonk leaderboard" => bonk_leaderboard_reply(ctx, invocation).await,
+        "bonk top-templates" => bonk_top_templates_reply(ctx, invocation).await,
+        "remind" => remind_reply(invocation, clock(ctx).await.as_ref()),
+        "gets" => gets_reply(ctx, invocation).await,
+        "gets halloffame" => hall_of_fame_reply(ctx, invocation).await,
+        "checkem" | "checkem list" => Ok(checkem_list_reply(ctx, invocation).await),
+        "checkem set" => checkem_set_reply(ctx, invocation).await,
+        "checkem reset" => checkem_reset_reply(ctx, invocation).await,
+        "config" | "config list" => Ok(config_list_reply(ctx, invocation).await),
+        "config get" => config_get_reply(ctx, invocation).await,
+        "config set" => config_set_reply(ctx, invocation).await,
+        "config reset" => config_reset_reply(ctx, invocation).await,
+        "features" | "features list" => Ok(features_list_reply(ctx, invocation).await),
+        "features enable" => toggle_feature_reply(ctx, invocation, true).await,
+        "features disable" => toggle_feature_reply(ctx, invocation, false).await,
+        "prefix" => prefix_reply(ctx, invocation).await,
+        "reload-assets" => reload_assets_reply(ctx).await,
+        "shutdown" => shutdown_reply(ctx).await,
+        "guilds" => Ok(guilds_reply(ctx).await),
+        "say" => say_reply(ctx, invocation).await,
+        "activity" | "activity list" => Ok(activity_list_reply(ctx).await),
+        "activity add" => activity_add_reply(ctx, invocation).await,
+        "activity remove" => activity_remove_reply(ctx, invocation).await,
+        "activity set" => activity_set_reply(ctx, invocation).await,
+        "activity pin" => activity_pin_reply(ctx, invocation).await,
+        _ => Err(format!("there's no {} command", name).into()),
+    }
+}
+
+#[command]
+#[description = "Repeats the next few messages from everyone mentioned iN SPOngEbob TexT"]
+#[usage = "<user...>"]
+#[example = "@someone"]
+async fn mock(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, mock_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn mock_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    //add all mentioned users to the mock tracker
+    let me = current_user(ctx).await;
+    let owners = {
+        let data = ctx.data.read().await;
+        data.get::<BotOwners>().expect("could not get BotOwners!").clone()
+    };
+    let mock_count = guild_config(ctx, invocation.guild).await.mock_count;
+    let mut mocked = Vec::new();
+    for mentioned in &invocation.users {
+        let id = *mentioned.id.as_u64();
+        if id != me && !owners.contains(&id) {
+            info!(user = id, "now tracking user");
+            track_mocker(ctx, id, mock_count as isize).await;
+            mocked.push(mentioned.name.clone());
+        }
+    }
+    if mocked.is_empty() {
+        return Err("nobody to mock".into())
+    }
+    //keep it quiet so they don't see it coming
+    Ok(Reply {
+        content: format!("mocking {} for their next {} messages", mocked.join(", "), mock_count),
+        ephemeral: true,
+        ..Default::default()
+    })
+}
+
+#[command]
+#[description = "Flips a coin"]
+#[aliases("coin")]
+async fn flip(ctx: &Context, msg: &Message) -> CommandResult {
+    reply_to(ctx, msg, Ok(flip_reply())).await
+}
+
+fn flip_reply() -> Reply {
+    let myval ={
+        let mut rng = rand::thread_rng();
+        match rng.gen_bool(0.5) {
+            true => "Heads",
+            false => "Tails",
+        }
+    };
+    Reply::text(myval)
+}
+
+#[command]
+#[sub_commands(bonk_list, bonk_user_stats, bonk_leaderboard, bonk_top_templates)]
+#[description = "Bonks someone's avatar, or an image, link or custom emoji. Several targets get tiled together"]
+#[usage = "[target...] [template|#tag]"]
+#[example = "@someone"]
+#[example = "@someone @someone_else bonk3"]
+#[example = "@someone #soft"]
+async fn bonk(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, bonk_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn bonk_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    //the first argument that isn't someone/something to bonk picks the template
+    let selector = invocation.args.iter().find(|arg| !avatar::is_bonkee_arg(arg));
+    let chat = chat(ctx).await;
+    let bonkees = avatar::find_bonkees(chat.as_ref(), invocation).await;
+    let mut avatars = Vec::new();
+    let metrics = bot_metrics(ctx).await;
+    for bonkee in &bonkees {
+        let started = Instant::now();
+        let fetched = avatar::fetch_avatar(chat.as_ref(), &bonkee.image_url).await;
+        metrics.avatar_fetch.observe(started.elapsed());
+        match fetched {
+            Ok(image) => avatars.push(image),
+            Err(e) => return Err(e),
+        }
+    }
+    let recent: Vec<String> = {
+        let data = ctx.data.read().await;
+        match data.get::<BonkHistory>().and_then(|history| history.get(&invocation.channel)) {
+            Some(recent) => recent.iter().cloned().collect(),
+            None => Vec::new(),
+        }
+    };
+    let assets = current_assets(ctx).await;
+    let bonk_choice = match bonker::select_bonk(&assets.bonks, selector.map(|s| s.as_str()), &recent) {
+        Ok(choice) => choice,
+        Err(e) => return Err(e.into()),
+    };
+    let started = Instant::now();
+    let rendered = assets.render_bonk(&bonker::tile_avatars(&avatars), &bonk_choice);
+    metrics.bonk_render.observe(started.elapsed());
+    let bonk_image = match rendered {
+        Ok(bonked) => bonked,
+        Err(e) => return Err(CommandError::Internal(format!("could not render {}: {}", bonk_choice.name, e))),
+    };
+    let file = attachment_path(ctx, "bonk").await;
+    if let Err(e) = bonk_image.save_with_format(&file, image::ImageFormat::Png) {
+        invocation::remove_attachment(&file);
+        return Err(CommandError::Internal(format!("could not save the bonk: {}", e)))
+    }
+    //only a bonk that's actually posted counts toward the channel's no-repeat history
+    let no_repeat = guild_config(ctx, invocation.guild).await.bonk_no_repeat as usize;
+    record_bonk_choice(ctx, invocation.channel, &bonk_choice.name, no_repeat).await;
+    for user in bonkees.iter().filter_map(|bonkee| bonkee.user) {
+        record_bonk(ctx, invocation, user, &bonk_choice.name).await;
+    }
+    Ok(Reply {file: Some(file), ..Default::default()})
+}
+
+//somewhere in the data folder (which is known to be writable) for an image only this reply uses, send_reply deletes it once it's sent
+async fn attachment_path(ctx: &Context, name: &str) -> String {
+    format!("{}/{}-{:016x}.png", bot_settings(ctx).await.data_dir, name, rand::random::<u64>())
+}
+
+#[command("list")]
+#[description = "Every bonk template, with a preview"]
+async fn bonk_list(ctx: &Context, msg: &Message) -> CommandResult {
+    reply_to(ctx, msg, bonk_list_reply(ctx).await).await
+}
+
+async fn bonk_list_reply(ctx: &Context) -> CommandReply {
+    let assets = current_assets(ctx).await;
+    let meta_data = &assets.bonks;
+    if meta_data.is_empty() {
+        return Err("there are no bonk templates".into())
+    }
+    let gallery = match assets.gallery(160, 4) {
+        Ok(gallery) => gallery,
+        Err(e) => return Err(CommandError::Internal(format!("could not make the gallery: {}", e))),
+    };
+    //list the templates in the same order they appear in the gallery
+    let mut listing = MessageBuilder::new();
+    listing.push_line("Available bonks (left to right, top to bottom):");
+    for (i, meta) in meta_data.iter().enumerate() {
+        listing.push(format!("{}. ", i + 1)).push_mono(meta.short_name());
+        if !meta.tags.is_empty() {
+            listing.push(" ").push_italic(meta.tags.iter().map(|tag| format!("#{}", tag)).collect::<Vec<_>>().join(" "));
+        }
+        listing.push_line("");
+    }
+    let file = attachment_path(ctx, "gallery").await;
+    if let Err(e) = gallery.save_with_format(&file, image::ImageFormat::Png) {
+        invocation::remove_attachment(&file);
+        return Err(CommandError::Internal(format!("could not save the gallery: {}", e)))
+    }
+    Ok(Reply {content: listing.build(), file: Some(file), ..Default::default()})
+}
+
+#[command("stats")]
+#[description = "Bonks given and received, yours if nobody is mentioned"]
+#[usage = "[user]"]
+async fn bonk_user_stats(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, bonk_stats_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn bonk_stats_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    let user = invocation.target();
+    let stats = {
+        let data = ctx.data.read().await;
+        let records = data.get::<BonkStats>().expect("could not get BonkStats!");
+        bonk_stats::user_stats(records, invocation.guild, *user.id.as_u64())
+    };
+    let mut description = MessageBuilder::new();
+    description.push_line(format!("Bonks given: **{}**", stats.given))
+        .push_line(format!("Bonks received: **{}**", stats.received));
+    if let Some((template, count)) = &stats.favorite_template {
+        description.push("Favorite bonk: ").push_mono(template).push_line(format!(" ({} times)", count));
+    }
+    if let Some((target, count)) = stats.favorite_target {
+        description.push("Bonks ").mention(&serenity::model::id::UserId(target)).push_line(format!(" the most ({} times)", count));
+    }
+    if let Some((nemesis, count)) = stats.nemesis {
+        description.push("Bonked the most by ").mention(&serenity::model::id::UserId(nemesis)).push_line(format!(" ({} times)", count));
+    }
+    let mut reply = Reply::embed(format!("Bonk stats for {}", user.name), description.build());
+    if let Some(embed) = reply.embed.as_mut() {
+        embed.thumbnail = user.avatar_url();
+    }
+    Ok(reply)
+}
+
+#[command("leaderboard")]
+#[description = "The top bonkers"]
+#[usage = "[week|month|all] [chart]"]
+#[example = "week chart"]
+async fn bonk_leaderboard(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, bonk_leaderboard_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn bonk_leaderboard_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    let mut period = bonk_stats::Period::All;
+    let mut chart = false;
+    for arg in &invocation.args {
+        if arg == "chart" {
+            chart = true;
+        }
+        else if let Ok(parsed) = arg.parse() {
+            period = parsed;
+        }
+    }
+    let now = clock(ctx).await.local_naive();
+    let leaders = {
+        let data = ctx.data.read().await;
+        let records = data.get::<BonkStats>().expect("could not get BonkStats!");
+        bonk_stats::leaderboard(records, invocation.guild, period, now)
+    };
+    let leaders: Vec<(u64, usize)> = leaders.into_iter().take(10).collect();
+    let mut description = MessageBuilder::new();
+    if leaders.is_empty() {
+        description.push("Nobody has bonked anyone yet");
+    }
+    for (i, (bonker, count)) in leaders.iter().enumerate() {
+        description.push(format!("{}. ", i + 1)).mention(&serenity::model::id::UserId(*bonker)).push_line(format!(" - {} bonks", count));
+    }
+    let counts: Vec<usize> = leaders.iter().map(|(_, count)| *count).collect();
+    Ok(stats_reply(ctx, &format!("Top bonkers {}", period.describe()), description.build(), chart.then_some(counts)).await)
+}
+
+#[command("top-templates")]
+#[description = "The most used bonks"]
+#[usage = "[chart]"]
+async fn bonk_top_templates(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, bonk_top_templates_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn bonk_top_templates_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    let chart = invocation.args.iter().any(|arg| arg == "chart");
+    let templates = {
+        let data = ctx.data.read().await;
+        let records = data.get::<BonkStats>().expect("could not get BonkStats!");
+        bonk_stats::top_templates(records, invocation.guild)
+    };
+    let templates: Vec<(String, usize)> = templates.into_iter().take(10).collect();
+    let mut description = MessageBuilder::new();
+    if templates.is_empty() {
+        description.push("Nobody has bonked anyone yet");
+    }
+    for (i, (template, count)) in templates.iter().enumerate() {
+        description.push(format!("{}. ", i + 1)).push_mono(template).push_line(format!(" - {} bonks", count));
+    }
+    let counts: Vec<usize> = templates.iter().map(|(_, count)| *count).collect();
+    Ok(stats_reply(ctx, "Most popular bonks", description.build(), chart.then_some(counts)).await)
+}
+
+//if chart has values, a bar chart of them is attached to the embed
+async fn stats_reply(ctx: &Context, title: &str, description: String, chart: Option<Vec<usize>>) -> Reply {
+    let mut reply = Reply::embed(title, description);
+    if let Some(values) = chart.filter(|values| !values.is_empty()) {
+        let file = attachment_path(ctx, "chart").await;
+        match bonk_stats::render_bar_chart(&values).save_with_format(&file, image::ImageFormat::Png) {
+            Ok(()) => reply.file = Some(file),
+            Err(e) => {
+                invocation::remove_attachment(&file);
+                error!(error = %e, "could not save image")
+            },
+        }
+    }
+    reply
+}
+
+async fn record_bonk(ctx: &Context, invocation: &Invocation, bonkee: u64, template: &str) {
+    let now = clock(ctx).await.local_naive();
+    let mut data = ctx.data.write().await;
+    let dir = data_dir(&data);
+    let records = match data.get_mut::<BonkStats>() {
+        Some(records) => records,
+        None => {
+            error!("could not get mutable reference to the bonk stats!");
+            return
+        }
+    };
+    records.push(bonk_stats::BonkRecord {
+        bonker: *invocation.author.id.as_u64(),
+        bonkee,
+        template: template.to_string(),
+        guild: invocation.guild,
+        time: now,
+    });
+    if let Err(why) = bonk_stats::save_bonk_records(&dir, records) {
+        error!(error = &*why, "could not save bonk stats");
+    }
+}
+
+//remembers the template for the channel, keeping at most no_repeat of them
+async fn record_bonk_choice(ctx: &Context, channel: u64, template: &str, no_repeat: usize) {
+    let mut data = ctx.data.write().await;
+    let bonk_history = match data.get_mut::<BonkHistory>() {
+        Some(history) => history,
+        None => {
+            error!("could not get mutable reference to the bonk history!");
+            return
+        }
+    };
+    let recent = bonk_history.entry(channel).or_insert_with(VecDeque::new);
+    recent.push_back(template.to_string());
+    while recent.len() > no_repeat {
+        recent.pop_front();
+    }
+}
+
+#[command]
+#[only_in(guilds)]
+#[checks(Admin)]
+#[sub_commands(checkem_list, checkem_set, checkem_reset)]
+#[description = "Shows or changes the check 'em reactions"]
+async fn checkem(ctx: &Context, msg: &Message) -> CommandResult {
+    checkem_list(ctx, msg, Args::new("", &[])).await
+}
+
+#[command("list")]
+#[description = "The reaction for every pattern"]
+#[checks(Admin)]
+async fn checkem_list(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, Ok(checkem_list_reply(ctx, &Invocation::from_message(msg, args)).await)).await
+}
+
+async fn checkem_list_reply(ctx: &Context, invocation: &Invocation) -> Reply {
+    let overrides = guild_config(ctx, invocation.guild).await.checkem_emoji;
+    let mut listing = MessageBuilder::new();
+    for pattern in checkem::ALL_PATTERNS.iter() {
+        listing.push_mono(pattern.name()).push(": ");
+        match checkem::reaction_for(&overrides, *pattern) {
+            Some(reaction) => listing.push_line(reaction.to_string()),
+            None => listing.push_line("off"),
+        };
+    }
+    Reply::text(listing.build())
+}
+
+#[command("set")]
+#[num_args(2)]
+#[description = "Uses a different emoji for a pattern, or none to not react"]
+#[usage = "<pattern> <emoji|none>"]
+#[example = "quads 🔥"]
+#[checks(Admin)]
+async fn checkem_set(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, checkem_set_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn checkem_set_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    let pattern: checkem::Pattern = invocation.args.first().map(|arg| arg.as_str()).unwrap_or_default().parse()?;
+    let emoji = match invocation.args.get(1) {
+        Some(emoji) => emoji.clone(),
+        None => return Err("give me an emoji (or `none`)".into()),
+    };
+    if emoji != "none" && checkem::parse_emoji(&emoji).is_none() {
+        return Err(format!("'{}' isn't an emoji, use an emoji or a custom one", emoji).into())
+    }
+    update_guild_config(ctx, invocation.guild, |config| {
+        config.checkem_emoji.insert(pattern, emoji.clone());
+    }).await;
+    Ok(Reply::text(format!("{} will now get {}", pattern, if emoji == "none" {"no reaction"} else {&emoji})))
+}
+
+#[command("reset")]
+#[max_args(1)]
+#[description = "Goes back to the default emoji for one pattern, or all of them"]
+#[usage = "[pattern]"]
+#[checks(Admin)]
+async fn checkem_reset(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, checkem_reset_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn checkem_reset_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    //no pattern resets all of them
+    match invocation.args.first() {
+        Some(pattern) => {
+            let pattern: checkem::Pattern = pattern.parse()?;
+            update_guild_config(ctx, invocation.guild, |config| {
+                config.checkem_emoji.remove(&pattern);
+            }).await;
+            Ok(Reply::text(format!("{} is back to the default reaction", pattern)))
+        },
+        None => {
+            update_guild_config(ctx, invocation.guild, |config| config.checkem_emoji.clear()).await;
+            Ok(Reply::text("all patterns are back to their default reactions"))
+        },
+    }
+}
+
+#[command]
+#[sub_commands(gets_hall_of_fame)]
+#[description = "Check 'em leaderboard, or someone's gets"]
+#[usage = "[user]"]
+#[example = "@someone"]
+async fn gets(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, gets_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn gets_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    let guild = invocation.guild;
+    let mut listing = MessageBuilder::new();
+    let title = match invocation.users.first() {
+        Some(user) => {
+            let data = ctx.data.read().await;
+            let log = data.get::<GetsTracker>().expect("could not get GetsTracker!");
+            let history = gets::history(log, guild, *user.id.as_u64());
+            if history.is_empty() {
+                listing.push("No gets yet");
+            }
+            for pattern in checkem::ALL_PATTERNS.iter().rev() {
+                let count = history.iter().filter(|record| record.pattern == *pattern).count();
+                if count > 0 {
+                    listing.push_bold(pattern.name()).push_line(format!(": {}", count));
+                }
+            }
+            for record in history.iter().take(10) {
+                listing.push_line(format!("{} {} - {}", record.time.date(), record.pattern, record.link()));
+            }
+            format!("Gets for {}", user.name)
+        },
+        None => {
+            let data = ctx.data.read().await;
+            let log = data.get::<GetsTracker>().expect("could not get GetsTracker!");
+            let scores = gets::leaderboard(log, guild);
+            if scores.is_empty() {
+                listing.push("No gets yet");
+            }
+            for (i, score) in scores.iter().take(10).enumerate() {
+                listing.push(format!("{}. ", i + 1))
+                    .mention(&serenity::model::id::UserId(score.user))
+                    .push_line(format!(" - {} points from {} gets, best: {}", score.points, score.count, score.best));
+            }
+            "Top getters".to_string()
+        },
+    };
+    Ok(Reply::embed(title, listing.build()))
+}
+
+#[command("halloffame")]
+#[only_in(guilds)]
+#[checks(Admin)]
+#[num_args(1)]
+#[description = "Picks a channel quads and better get reposted to"]
+#[usage = "<channel|off>"]
+#[example = "#hall-of-fame"]
+async fn gets_hall_of_fame(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, hall_of_fame_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn hall_of_fame_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    //shortcut for !config set hall_of_fame
+    let arg = invocation.args.first().cloned().unwrap_or_default();
+    let mut result = Ok(());
+    let config = update_guild_config(ctx, invocation.guild, |config| result = config.set("hall_of_fame", &arg)).await;
+    result?;
+    Ok(Reply::text(match config.hall_of_fame {
+        Some(channel) => format!("quads and better will be posted in <#{}>", channel),
+        None => "the hall of fame is off".to_string(),
+    }))
+}
+
+#[command]
+#[only_in(guilds)]
+#[checks(Admin)]
+#[sub_commands(config_list, config_get, config_set, config_reset)]
+#[description = "Shows or changes this server's settings"]
+async fn config(ctx: &Context, msg: &Message) -> CommandResult {
+    config_list(ctx, msg, Args::new("", &[])).await
+}
+
+#[command("list")]
+#[description = "Every setting, its value and what it does"]
+#[checks(Admin)]
+async fn config_list(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, Ok(config_list_reply(ctx, &Invocation::from_message(msg, args)).await)).await
+}
+
+async fn config_list_reply(ctx: &Context, invocation: &Invocation) -> Reply {
+    let config = guild_config(ctx, invocation.guild).await;
+    let mut listing = MessageBuilder::new();
+    for (key, description) in config::SETTINGS {
+        listing.push_mono(key).push(" = ").push_bold(config.get(key).unwrap_or_default()).push_line("")
+            .push_italic_line(description);
+    }
+    listing.push_line("check 'em emoji are changed with !checkem");
+    Reply::text(listing.build())
+}
+
+#[command("get")]
+#[num_args(1)]
+#[description = "One setting's value"]
+#[usage = "<setting>"]
+#[example = "mock_count"]
+#[checks(Admin)]
+async fn config_get(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, config_get_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn config_get_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    let key = invocation.args.first().cloned().unwrap_or_default();
+    let value = guild_config(ctx, invocation.guild).await.get(&key)?;
+    Ok(Reply::text(format!("{} = {}", key, value)))
+}
+
+#[command("set")]
+#[min_args(2)]
+#[description = "Changes a setting"]
+#[usage = "<setting> <value>"]
+#[example = "mock_count 5"]
+#[checks(Admin)]
+async fn config_set(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, config_set_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn config_set_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    let key = invocation.args.first().cloned().unwrap_or_default();
+    //everything after the key, so values can have spaces
+    let value = invocation.args.iter().skip(1).cloned().collect::<Vec<_>>().join(" ");
+    let mut result = Ok(());
+    let config = update_guild_config(ctx, invocation.guild, |config| result = config.set(&key, &value)).await;
+    result?;
+    Ok(Reply::text(format!("{} = {}", key, config.get(&key).unwrap_or_default())))
+}
+
+#[command("reset")]
+#[max_args(1)]
+#[description = "Puts a setting back to its default, or all of them"]
+#[usage = "[setting]"]
+#[checks(Admin)]
+async fn config_reset(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, config_reset_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn config_reset_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    //no key resets everything, check 'em emoji included
+    match invocation.args.first() {
+        Some(key) => {
+            let mut result = Ok(());
+            let config = update_guild_config(ctx, invocation.guild, |config| result = config.reset(key)).await;
+            result?;
+            Ok(Reply::text(format!("{} = {}", key, config.get(key).unwrap_or_default())))
+        },
+        None => {
+            update_guild_config(ctx, invocation.guild, |config| *config = config::GuildConfig::default()).await;
+            Ok(Reply::text("all settings are back to their defaults"))
+        },
+    }
+}
+
+#[command]
+#[only_in(guilds)]
+#[checks(Moderator)]
+#[sub_commands(features_list, features_enable, features_disable)]
+#[description = "Turns commands and features on or off, for the server or one channel"]
+async fn features(ctx: &Context, msg: &Message) -> CommandResult {
+    features_list(ctx, msg, Args::new("", &[])).await
+}
+
+#[command("list")]
+#[description = "What's on and off"]
+#[checks(Moderator)]
+async fn features_list(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, Ok(features_list_reply(ctx, &Invocation::from_message(msg, args)).await)).await
+}
+
+async fn features_list_reply(ctx: &Context, invocation: &Invocation) -> Reply {
+    let config = guild_config(ctx, invocation.guild).await;
+    let channel = invocation.channel;
+    let mut listing = MessageBuilder::new();
+    for (feature, description) in config::FEATURES {
+        let on_off = |enabled| if enabled {"on"} else {"off"};
+        listing.push_mono(feature).push(": ").push_bold(on_off(!config.disabled.contains(*feature)));
+        if config.feature_enabled(feature, channel) == config.disabled.contains(*feature) {
+            listing.push(format!(" ({} here)", on_off(config.feature_enabled(feature, channel))));
+        }
+        listing.push_line("").push_italic_line(description);
+    }
+    for (channel, features) in &config.channel_features {
+        listing.push(format!("<#{}>:", channel));
+        for (feature, enabled) in features {
+            listing.push(format!(" {} {}", feature, if *enabled {"on"} else {"off"}));
+        }
+        listing.push_line("");
+    }
+    Reply::text(listing.build())
+}
+
+#[command("enable")]
+#[min_args(1)]
+#[max_args(2)]
+#[description = "Turns a feature on"]
+#[usage = "<feature> [#channel|here]"]
+#[example = "bonk here"]
+#[checks(Moderator)]
+async fn features_enable(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, toggle_feature_reply(ctx, &Invocation::from_message(msg, args), true).await).await
+}
+
+#[command("disable")]
+#[min_args(1)]
+#[max_args(2)]
+#[description = "Turns a feature off"]
+#[usage = "<feature> [#channel|here]"]
+#[example = "gallery"]
+#[checks(Moderator)]
+async fn features_disable(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, toggle_feature_reply(ctx, &Invocation::from_message(msg, args), false).await).await
+}
+
+//<feature> [channel|here], without a channel it's for the whole guild
+async fn toggle_feature_reply(ctx: &Context, invocation: &Invocation, enabled: bool) -> CommandReply {
+    let feature = invocation.args.first().cloned().unwrap_or_default().to_lowercase();
+    let channel = match invocation.args.get(1) {
+        Some(arg) if arg == "here" => Some(invocation.channel),
+        Some(arg) => match serenity::utils::parse_channel(arg) {
+            Some(channel) => Some(channel),
+            None => return Err(format!("'{}' isn't a channel, give me a channel or `here`", arg).into()),
+        },
+        None => None,
+    };
+    let mut result = Ok(());
+    update_guild_config(ctx, invocation.guild, |config| result = config.set_feature(&feature, channel, enabled)).await;
+    result?;
+    let state = if enabled {"on"} else {"off"};
+    Ok(Reply::text(match channel {
+        Some(channel) => format!("{} is {} in <#{}>", feature, state, channel),
+        None => format!("{} is {} everywhere in this server", feature, state),
+    }))
+}
+
+async fn feature_enabled(ctx: &Context, msg: &Message, feature: &str) -> bool {
+    guild_config(ctx, msg.guild_id.map(|id| *id.as_u64())).await.feature_enabled(feature, *msg.channel_id.as_u64())
+}
+
+//every command goes through here, so turned off commands never run
+#[hook]
+async fn before(ctx: &Context, msg: &Message, _command_name: &str) -> bool {
+    //subcommands are passed in by their own name (list, stats...), so go by what was typed instead
+    let config = guild_config(ctx, msg.guild_id.map(|id| *id.as_u64())).await;
+    let command = canonical_command(&invoked_command(&msg.content, &config.prefix));
+    tracing::Span::current().record("command", &command.as_str());
+    let enabled = config.feature_enabled(&command, *msg.channel_id.as_u64());
+    if enabled {
+        info!("running command");
+    }
+    else {
+        info!(%command, "ignoring command, it's turned off here");
+    }
+    enabled
+}
+
+#[hook]
+async fn guild_prefix(ctx: &Context, msg: &Message) -> Option<String> {
+    Some(guild_config(ctx, msg.guild_id.map(|id| *id.as_u64())).await.prefix)
+}
+
+//the first word after the prefix or the bot's mention, lowercased
+fn invoked_command(content: &str, prefix: &str) -> String {
+    after_prefix(content, prefix).split_whitespace().next().unwrap_or_default().to_lowercase()
+}
+
+//what comes after the prefix or the mention
+fn after_prefix<'a>(content: &'a str, prefix: &str) -> &'a str {
+    let content = content.trim_start();
+    match content.strip_prefix("<@") {
+        Some(mention) => mention.split_once('>').map(|(_, rest)| rest).unwrap_or_default(),
+        None => content.strip_prefix(prefix).unwrap_or(content),
+    }
+}
+
+//the deepest command or subcommand in what was typed, e.g. "checkem set" out of "!checkem set quads"
+fn typed_command(content: &str, prefix: &str) -> Option<(String, &'static serenity::framework::standard::Command)> {
+    let words: Vec<String> = after_prefix(content, prefix).split_whitespace().take(3).map(|word| word.to_string()).collect();
+    (1..=words.len()).rev().find_map(|length| {
+        help::find(MOCKER_GROUP.options.commands, &words[..length]).or_else(|| help::find(OWNER_GROUP.options.commands, &words[..length]))
+    })
+}
+
+#[command]
+#[only_in(guilds)]
+#[checks(Admin)]
+#[max_args(1)]
+#[description = "Shows or changes the prefix for typed commands"]
+#[usage = "[new_prefix]"]
+#[example = "?"]
+async fn prefix(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, prefix_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn prefix_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    //shortcut for !config get/set prefix
+    match invocation.args.first() {
+        Some(new_prefix) => {
+            let mut result = Ok(());
+            let config = update_guild_config(ctx, invocation.guild, |config| result = config.set("prefix", new_prefix)).await;
+            result?;
+            Ok(Reply::text(format!("commands now start with `{}`", config.prefix)))
+        },
+        None => Ok(Reply::text(format!("commands start with `{}`, or mention me", guild_config(ctx, invocation.guild).await.prefix))),
+    }
+}
+
+//the most the user can do in the guild, anyone but owners are plain users in DMs
+async fn member_role(ctx: &Context, guild: Option<u64>, user: u64) -> permissions::Role {
+    let is_owner = {
+        let data = ctx.data.read().await;
+        data.get::<BotOwners>().is_some_and(|owners| owners.contains(&user))
+    };
+    let guild = match guild {
+        Some(guild) if !is_owner => guild,
+        _ => return permissions::role_for(is_owner, Permissions::empty(), &[], None),
+    };
+    let member = match GuildId(guild).member(ctx, user).await {
+        Ok(member) => member,
+        Err(e) => {
+            warn!(user, error = %logging::chain(&e), "could not get member");
+            return permissions::Role::User
+        }
+    };
+    let member_permissions = match member.permissions(ctx).await {
+        Ok(member_permissions) => member_permissions,
+        Err(e) => {
+            warn!(user, error = %logging::chain(&e), "could not get permissions");
+            Permissions::empty()
+        }
+    };
+    let roles: Vec<u64> = member.roles.iter().map(|role| *role.as_u64()).collect();
+    permissions::role_for(false, member_permissions, &roles, guild_config(ctx, Some(guild)).await.moderator_role)
+}
+
+async fn require_role(ctx: &Context, msg: &Message, role: permissions::Role) -> Result<(), Reason> {
+    let has = member_role(ctx, msg.guild_id.map(|id| *id.as_u64()), *msg.author.id.as_u64()).await;
+    if has >= role {
+        Ok(())
+    }
+    else {
+        Err(Reason::UserAndLog {
+            user: format!("you need to be a {} for that", role),
+            log: format!("{} is a {}, not a {}", msg.author.name, has, role),
+        })
+    }
+}
+
+#[check]
+#[name = "Owner"]
+async fn owner_check(ctx: &Context, msg: &Message, _: &mut Args, _: &CommandOptions) -> Result<(), Reason> {
+    require_role(ctx, msg, permissions::Role::Owner).await
+}
+
+#[check]
+#[name = "Admin"]
+async fn admin_check(ctx: &Context, msg: &Message, _: &mut Args, _: &CommandOptions) -> Result<(), Reason> {
+    require_role(ctx, msg, permissions::Role::Admin).await
+}
+
+#[check]
+#[name = "Moderator"]
+async fn moderator_check(ctx: &Context, msg: &Message, _: &mut Args, _: &CommandOptions) -> Result<(), Reason> {
+    require_role(ctx, msg, permissions::Role::Moderator).await
+}
+
+//commands serenity wouldn't run get the same ❌ and reply as ones that refused
+#[hook]
+async fn dispatch_error(ctx: &Context, msg: &Message, error: DispatchError) {
+    let prefix = guild_config(ctx, msg.guild_id.map(|id| *id.as_u64())).await.prefix;
+    let typed = typed_command(&msg.content, &prefix);
+    let name = match &typed {
+        Some((name, _)) => name.clone(),
+        None => canonical_command(&invoked_command(&msg.content, &prefix)),
+    };
+    let usage = typed.and_then(|(name, command)| command.options.usage.map(|usage| format!("{}{} {}", prefix, name, usage)));
+    let error = dispatch_error_message(error, usage);
+    bot_metrics(ctx).await.command(&name, error.kind());
+    report_error(ctx, &name, &error).await;
+    let chat = chat(ctx).await;
+    if let Err(why) = chat.react(*msg.channel_id.as_u64(), *msg.id.as_u64(), error.reaction().into()).await {
+        warn!(error = %why, "could not react to message");
+    }
+    if let Err(why) = chat.send(*msg.channel_id.as_u64(), None, &Reply::text(error.user_message())).await {
+        error!(error = %why, "could not send message");
+    }
+}
+
+//what to tell them about a command that didn't get to run, usage is how it's meant to be typed
+fn dispatch_error_message(error: DispatchError, usage: Option<String>) -> CommandError {
+    let with_usage = |message: String| match &usage {
+        Some(usage) => format!("{}, it goes `{}`", message, usage),
+        None => message,
+    };
+    let message = match error {
+        DispatchError::CheckFailed(check, reason) => match reason {
+            Reason::User(user) => user,
+            Reason::UserAndLog {user, log} => {
+                info!(check, reason = %log, "failed check");
+                user
+            },
+            Reason::Log(log) => {
+                info!(check, reason = %log, "failed check");
+                "you can't use that here".to_string()
+            },
+            _ => "you can't use that here".to_string(),
+        },
+        DispatchError::NotEnoughArguments {min, given} => with_usage(format!("that needs at least {} argument{}, you gave {}", min, if min == 1 {""} else {"s"}, given)),
+        DispatchError::TooManyArguments {max, given} => with_usage(format!("that takes at most {} argument{}, you gave {}", max, if max == 1 {""} else {"s"}, given)),
+        DispatchError::Ratelimited(info) => format!("slow down, try again in {} seconds", info.rate_limit.as_secs().max(1)),
+        DispatchError::OnlyForGuilds => "that only works in a server".to_string(),
+        DispatchError::OnlyForDM => "that only works in DMs".to_string(),
+        DispatchError::OnlyForOwners => "only the bot owners can do that".to_string(),
+        DispatchError::LackingRole => "you don't have the role for that".to_string(),
+        DispatchError::LackingPermissions(permissions) => format!("you need {} for that", permissions),
+        DispatchError::CommandDisabled(_) => "that command is turned off".to_string(),
+        DispatchError::BlockedUser | DispatchError::BlockedGuild | DispatchError::BlockedChannel => "you can't use commands here".to_string(),
+        _ => "I couldn't run that".to_string(),
+    };
+    CommandError::User(message)
+}
+
+//a failed command gets a reaction and a reply saying what went wrong
+#[hook]
+async fn after(ctx: &Context, msg: &Message, command_name: &str, result: CommandResult) {
+    let error = result.err().map(|e| command_error::from_boxed(e.as_ref()));
+    //the hook only gets a subcommand's own name, so the rest comes from what was typed
+    let prefix = guild_config(ctx, msg.guild_id.map(|id| *id.as_u64())).await.prefix;
+    let name = full_command_name(&canonical_command(&invoked_command(&msg.content, &prefix)), command_name);
+    bot_metrics(ctx).await.command(&name, error.as_ref().map_or("ok", |e| e.kind()));
+    let error = match error {
+        Some(error) => error,
+        None => return,
+    };
+    report_error(ctx, &name, &error).await;
+    let chat = chat(ctx).await;
+    if let Err(why) = chat.react(*msg.channel_id.as_u64(), *msg.id.as_u64(), error.reaction().into()).await {
+        warn!(error = %why, "could not react to message");
+    }
+    if let Err(why) = chat.send(*msg.channel_id.as_u64(), None, &Reply::text(error.user_message())).await {
+        error!(error = %why, "could not send message");
+    }
+}
+
+//logs a failed command, DMing the owners about internal ones at most once every REPORT_MINUTES
+async fn report_error(ctx: &Context, command: &str, error: &CommandError) {
+    match error {
+        CommandError::User(e) => info!(command, error = %e, "command refused"),
+        CommandError::Transient(e) => warn!(command, error = %e, "command failed, might work later"),
+        CommandError::Internal(e) => error!(command, error = %e, "command failed"),
+    }
+    if !error.is_internal() {
+        return
+    }
+    let held_back = {
+        let mut data = ctx.data.write().await;
+        match data.get_mut::<ErrorReports>() {
+            Some(reports) => reports.allow(std::time::Instant::now()),
+            None => return,
+        }
+    };
+    if let Some(held_back) = held_back {
+        let mut report = format!("`{}` failed: {}", command, error);
+        if held_back > 0 {
+            report.push_str(&format!("\n({} more since the last report)", held_back));
+        }
+        notify_owners(ctx, &report).await;
+    }
+}
+
+#[command("reload-assets")]
+#[aliases("reload")]
+#[checks(Owner)]
+#[description = "Re-reads the activities, bonk templates, settings, stats and gets, keeping whatever doesn't load, and re-registers the slash commands"]
+async fn reload_assets(ctx: &Context, msg: &Message) -> CommandResult {
+    reply_to(ctx, msg, reload_assets_reply(ctx).await).await
+}
+
+async fn reload_assets_reply(ctx: &Context) -> CommandReply {
+    let errors = reload_all(&ctx.http, &ctx.data).await;
+    if errors.is_empty() {
+        Ok(Reply::text("reloaded"))
+    }
+    else {
+        Err(format!("reloaded, except:\n{}", errors.join("\n")).into())
+    }
+}
+
+#[command]
+#[checks(Owner)]
+#[description = "Logs the bot out"]
+async fn shutdown(ctx: &Context, msg: &Message) -> CommandResult {
+    reply_to(ctx, msg, shutdown_reply(ctx).await).await
+}
+
+//the same as a SIGTERM, which waits for this command to finish (and say bye) first
+async fn shutdown_reply(ctx: &Context) -> CommandReply {
+    match ctx.data.read().await.get::<BotLifecycle>() {
+        Some(lifecycle) => lifecycle.request(),
+        None => return Err(CommandError::Internal("could not get the lifecycle to shut down".to_string())),
+    }
+    Ok(Reply::text("bye"))
+}
+
+#[command]
+#[checks(Owner)]
+#[description = "Every server the bot is in"]
+async fn guilds(ctx: &Context, msg: &Message) -> CommandResult {
+    reply_to(ctx, msg, Ok(guilds_reply(ctx).await)).await
+}
+
+async fn guilds_reply(ctx: &Context) -> Reply {
+    let mut listing = MessageBuilder::new();
+    for guild in ctx.cache.guilds().await {
+        match ctx.cache.guild_field(guild, |guild| (guild.name.clone(), guild.member_count)).await {
+            Some((name, members)) => listing.push_line(format!("{} ({}) - {} members", name, guild, members)),
+            None => listing.push_line(format!("{} (not cached)", guild)),
+        };
+    }
+    Reply::embed("Servers", listing.build())
+}
+
+#[command]
+#[checks(Owner)]
+#[min_args(1)]
+#[description = "Says something, in another channel if the first word is one"]
+#[usage = "[#channel] <message>"]
+#[example = "#general hello"]
+async fn say(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    //the message itself is the answer, there's only something to reply with if it didn't work
+    match say_reply(ctx, &Invocation::from_message(msg, args)).await {
+        Ok(_) => Ok(()),
+        Err(e) => reply_to(ctx, msg, Err(e)).await,
+    }
+}
+
+async fn say_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    let (channel, words) = match invocation.args.first().and_then(|arg| serenity::utils::parse_channel(arg.as_str())) {
+        Some(channel) => (channel, &invocation.args[1..]),
+        None => (invocation.channel, &invocation.args[..]),
+    };
+    if words.is_empty() {
+        return Err("say what?".into())
+    }
+    chat(ctx).await.send(channel, None, &Reply::text(words.join(" "))).await?;
+    Ok(Reply {
+        content: format!("said it in <#{}>", channel),
+        ephemeral: true,
+        ..Default::default()
+    })
+}
+
+//None once the bot is shutting down, otherwise hold on to it until the work is done
+async fn begin_work(ctx: &Context) -> Option<lifecycle::InFlight> {
+    let lifecycle = ctx.data.read().await.get::<BotLifecycle>().cloned()?;
+    let in_flight = lifecycle.begin();
+    if in_flight.is_none() {
+        debug!("shutting down, ignoring it");
+    }
+    in_flight
+}
+
+async fn chat(ctx: &Context) -> Arc<dyn surface::ChatSurface> {
+    let data = ctx.data.read().await;
+    data.get::<Chat>().cloned().expect("could not get Chat!")
+}
+
+//0 if discord won't say, which nobody's id is
+async fn current_user(ctx: &Context) -> u64 {
+    match chat(ctx).await.current_user().await {
+        Ok(me) => me,
+        Err(e) => {
+            error!(error = %e, "couldn't get current user");
+            0
+        }
+    }
+}
+
+async fn clock(ctx: &Context) -> Arc<dyn clock::Clock> {
+    let data = ctx.data.read().await;
+    data.get::<BotClock>().cloned().unwrap_or_else(|| Arc::new(clock::SystemClock::local()))
+}
+
+async fn bot_metrics(ctx: &Context) -> Arc<metrics::Metrics> {
+    let data = ctx.data.read().await;
+    data.get::<BotMetrics>().cloned().unwrap_or_default()
+}
+
+async fn bot_settings(ctx: &Context) -> settings::Settings {
+    let data = ctx.data.read().await;
+    data.get::<BotSettings>().cloned().expect("could not get BotSettings!")
 }
 
-struct ReminderList;
-impl TypeMapKey for ReminderList{
-    type Value = Vec<Reminder>;
+//for saving while the data is already locked
+fn data_dir(data: &TypeMap) -> String {
+    data.get::<BotSettings>().map(|settings| settings.data_dir.clone()).expect("could not get BotSettings!")
 }
 
+async fn current_assets(ctx: &Context) -> Arc<assets::Assets> {
+    let data = ctx.data.read().await;
+    data.get::<AssetRegistry>().cloned().unwrap_or_default()
+}
 
+//everything !reload-assets (and the admin api's /admin/reload) does, returning what didn't load
+async fn reload_all(http: &Http, data: &RwLock<TypeMap>) -> Vec<String> {
+    let mut errors = reload_registry(data).await;
+    errors.extend(reload_data(data).await);
+    //picks up new bonk templates for /bonk
+    let bonks = data.read().await.get::<AssetRegistry>().map(|assets| assets.bonks.clone()).unwrap_or_default();
+    if slash::register(http, &bonks).await.is_none() {
+        errors.push("the slash commands couldn't be updated".to_string());
+    }
+    errors
+}
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct Reminder {
-    date_time: chrono::NaiveDateTime,
-    message: serenity::model::channel::Message,
-    verification_message: serenity::model::channel::Message,
+//swaps in freshly loaded assets, or leaves the old ones alone and says what's wrong
+async fn reload_registry(data: &RwLock<TypeMap>) -> Vec<String> {
+    let dir = data.read().await.get::<BotSettings>().map(|settings| settings.assets_dir.clone()).expect("could not get BotSettings!");
+    match assets::load_assets(&dir) {
+        Ok(registry) => {
+            info!(activities = registry.activities.len(), bonks = registry.bonks.len(), "loaded assets");
+            let mut data = data.write().await;
+            data.insert::<AssetRegistry>(Arc::new(registry));
+            Vec::new()
+        },
+        Err(errors) => {
+            warn!(errors = %errors.join("\n"), "keeping the old assets");
+            errors
+        },
+    }
 }
 
-#[tokio::main]
-async fn main() {
-    println!("{}", mock_string("this is a test string"));
-
-    let token = match env::var("DISCORD_TOKEN") {
-        Ok(tok) => tok,
-        Err(_) => {
-            let mut content = String::new();
-            File::open("./assets/key").expect("could not find env DISCORD_TOKEN or file containing bot key")
-                .read_to_string(&mut content).expect("could not read contents of assets/key");
-            content
+//the files the bot keeps itself, a file that doesn't parse leaves what's in memory alone
+async fn reload_data(data: &RwLock<TypeMap>) -> Vec<String> {
+    let mut errors = Vec::new();
+    let dir = data_dir(&*data.read().await);
+    let configs = config::load_configs(&dir);
+    let records = bonk_stats::load_bonk_records(&dir);
+    let gets = gets::load_gets(&dir);
+    let mut data = data.write().await;
+    match configs {
+        Ok(configs) => {
+            data.insert::<GuildConfigs>(configs);
+        },
+        Err(e) => errors.push(e),
+    }
+    match records {
+        Ok(records) => {
+            data.insert::<BonkStats>(records);
+        },
+        Err(e) => errors.push(e),
+    }
+    match gets {
+        Ok(gets) => {
+            data.insert::<GetsTracker>(gets);
         },
+        Err(e) => errors.push(e),
+    }
+    errors
+}
+
+#[command]
+#[checks(Owner)]
+#[sub_commands(activity_list, activity_add, activity_remove, activity_set, activity_pin)]
+#[description = "Shows or changes the statuses the bot rotates through"]
+async fn activity(ctx: &Context, msg: &Message) -> CommandResult {
+    reply_to(ctx, msg, Ok(activity_list_reply(ctx).await)).await
+}
+
+#[command("list")]
+#[checks(Owner)]
+#[description = "Every status, numbered, and the pinned one if there is one"]
+async fn activity_list(ctx: &Context, msg: &Message) -> CommandResult {
+    reply_to(ctx, msg, Ok(activity_list_reply(ctx).await)).await
+}
+
+async fn activity_list_reply(ctx: &Context) -> Reply {
+    let pinned = {
+        let data = ctx.data.read().await;
+        data.get::<PinnedActivity>().cloned().flatten()
     };
-    let framework = StandardFramework::new()
-        .configure(|c| c.prefix("!"))
-        .group(&MOCKER_GROUP);
+    let mut listing = MessageBuilder::new();
+    if let Some(pinned) = pinned {
+        listing.push_line(format!("pinned: {}", pinned.describe())).push_line("");
+    }
+    for (i, entry) in current_assets(ctx).await.activities.iter().enumerate() {
+        listing.push_line(format!("{}. {}", i + 1, entry.describe()));
+    }
+    Reply::embed("Activities", listing.build())
+}
 
-    let mut client = Client::builder(&token)
-        .event_handler(Handler)
-        .framework(framework)
-        .await
-        .expect("Error creating client");
+#[command("add")]
+#[checks(Owner)]
+#[min_args(1)]
+#[description = "Adds a status to the rotation"]
+#[usage = "[playing|listening|competing|streaming <link>] <text>"]
+#[example = "listening {bonk_count} bonks"]
+#[example = "mineycrafta"]
+async fn activity_add(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, activity_add_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
 
-    {
-        let mut data = client.data.write().await;
-        data.insert::<MockTracker>(HashMap::default());
-        data.insert::<BotOwner>(277158017869414400);
-        data.insert::<StatusTimer>(Instant::now());
-        //attempt to load the reminder list from assets/reminder_list.json
-        let mut reminder_list: Vec<Reminder> = match std::fs::read_to_string("./assets/reminder_list.json") {
-            Ok(string) => match serde_json::from_str(&string) {
-                Ok(data) => data,
-                Err(e) => {
-                    println!("could not parse JSON: {}", e);
-                    Vec::new()
-                },
-            },
-            Err(e) => {
-                println!("could not read 'reminder_list.json': {}", e);
-                Vec::new()
-            },
-        };
-        let now = chrono::Local::now().naive_local();
-        let num_read = reminder_list.len();
-        reminder_list.retain(|reminder| reminder.date_time > now);
-        if num_read > reminder_list.len() {
-            println!("purged {} expired timers that should have been fired", num_read-reminder_list.len());
-        }
-        data.insert::<ReminderList>(reminder_list);
+async fn activity_add_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    let entry = activity::Entry::parse(&invocation.args)?;
+    let mut entries = current_assets(ctx).await.activities.clone();
+    let added = entry.describe();
+    entries.push(entry);
+    save_activities(ctx, &entries).await?;
+    Ok(Reply::text(format!("added {} as #{}", added, entries.len())))
+}
+
+#[command("remove")]
+#[checks(Owner)]
+#[num_args(1)]
+#[description = "Takes a status out of the rotation, by its number in the list"]
+#[usage = "<number>"]
+#[example = "3"]
+async fn activity_remove(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, activity_remove_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn activity_remove_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    let mut entries = current_assets(ctx).await.activities.clone();
+    let index = activity_number(invocation.args.first(), entries.len())?;
+    let removed = entries.remove(index);
+    save_activities(ctx, &entries).await?;
+    Ok(Reply::text(format!("removed {}", removed.describe())))
+}
+
+#[command("set")]
+#[checks(Owner)]
+#[min_args(1)]
+#[description = "Shows this status until it's unpinned, without adding it to the rotation"]
+#[usage = "[playing|listening|competing|streaming <link>] <text>"]
+#[example = "competing the bonk olympics"]
+async fn activity_set(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, activity_set_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn activity_set_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    let entry = activity::Entry::parse(&invocation.args)?;
+    let errors = activity::check(std::slice::from_ref(&entry));
+    if !errors.is_empty() {
+        return Err(errors.join("\n").into())
     }
+    let reply = format!("pinned {}, `!activity pin off` to go back to rotating", entry.describe());
+    pin_activity(ctx, Some(entry)).await?;
+    Ok(Reply::text(reply))
+}
 
-    if let Err(why) = client.start().await {
-        println!("Client error: {:?}", why);
+#[command("pin")]
+#[checks(Owner)]
+#[num_args(1)]
+#[description = "Shows one of the statuses in the list until it's unpinned, or off to go back to rotating"]
+#[usage = "<number|off>"]
+#[example = "2"]
+#[example = "off"]
+async fn activity_pin(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, activity_pin_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+async fn activity_pin_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    if invocation.args.first().is_some_and(|arg| arg == "off") {
+        pin_activity(ctx, None).await?;
+        return Ok(Reply::text("unpinned, back to rotating"))
     }
+    let entries = current_assets(ctx).await.activities.clone();
+    let entry = entries[activity_number(invocation.args.first(), entries.len())?].clone();
+    let reply = format!("pinned {}", entry.describe());
+    pin_activity(ctx, Some(entry)).await?;
+    Ok(Reply::text(reply))
 }
 
-#[group("mocker")]
-#[commands(mock, help, bonk, remind, flip)]
-struct Mocker;
+//a 1-based number from !activity list, as an index
+fn activity_number(arg: Option<&String>, count: usize) -> Result<usize, String> {
+    match arg.and_then(|arg| arg.parse::<usize>().ok()) {
+        Some(number) if number >= 1 && number <= count => Ok(number - 1),
+        _ => Err(format!("that needs a number from 1 to {}, see `!activity list`", count)),
+    }
+}
 
-#[command]
-async fn mock(ctx: &Context, msg: &Message) -> CommandResult {
-    //add all mentioned users to the mock tracker
-    let me = match ctx.http.as_ref().get_current_user().await {
-        Ok(user) => *user.id.as_u64(),
-        Err(e) => {
-            println!("couldn't get current user??? {}", e);
-            0
-        }
-    };
-    let bot_owner = {
-        let data = ctx.data.read().await;
-        *data.get::<BotOwner>().expect("could not get BotOwner!")
-    };
-    for mentioned in &msg.mentions {
-        let id = *mentioned.id.as_u64();
-        if id != me && id != bot_owner {
-            println!("now tracking user: {}", mentioned.name);
-            track_mocker(ctx, id, 3).await;
-        }
+//checks, writes and reloads activities.json, nothing changes if the new list has a problem
+async fn save_activities(ctx: &Context, entries: &[activity::Entry]) -> Result<(), CommandError> {
+    let errors = activity::check(entries);
+    if !errors.is_empty() {
+        return Err(errors.join("\n").into())
+    }
+    if let Err(e) = activity::save_entries(&bot_settings(ctx).await.assets_dir, entries) {
+        return Err(CommandError::Internal(format!("could not save activities: {}", e)))
+    }
+    let errors = reload_registry(&ctx.data).await;
+    if !errors.is_empty() {
+        return Err(format!("saved, but the assets didn't reload:\n{}", errors.join("\n")).into())
     }
     Ok(())
 }
 
-#[command]
-async fn flip(ctx: &Context, msg: &Message) -> CommandResult {
-    let myval ={
-        let mut rng = rand::thread_rng();
-        match rng.gen_bool(0.5) {
-            true => "Heads",
-            false => "Tails",
+async fn pin_activity(ctx: &Context, pinned: Option<activity::Entry>) -> Result<(), CommandError> {
+    {
+        let mut data = ctx.data.write().await;
+        let dir = data_dir(&data);
+        if let Err(e) = activity::save_pinned(&dir, pinned.as_ref()) {
+            return Err(CommandError::Internal(format!("could not save pinned activity: {}", e)))
         }
-    };
-    if let Err(why) = msg.channel_id.say(ctx, format!("{}", myval)).await {
-        println!("Could not send message: {}", why);
+        data.insert::<PinnedActivity>(pinned);
     }
+    update_activity(ctx).await;
     Ok(())
 }
 
-#[command]
-async fn bonk(ctx: &Context, msg: &Message) -> CommandResult {
-    let bonkee = match msg.mentions.get(0) {
-        Some(user) => user,
-        None => &msg.author,
-    };
-    let image_url = match bonkee.avatar_url() {
-        Some(url) => str::replace(&url, ".webp", ".png"),
-        None => {
-            println!("no image url");
-            return Ok(())
+//checks every few seconds for edits to the asset files and reloads them, telling the owners when that fails
+async fn watch_assets(ctx: Context) {
+    let dir = bot_settings(&ctx).await.assets_dir;
+    let mut last_seen = assets::last_modified(&dir, &current_assets(&ctx).await.files());
+    loop {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        let modified = assets::last_modified(&dir, &current_assets(&ctx).await.files());
+        if modified == last_seen {
+            continue
         }
-    };
-    let avatar = match reqwest::blocking::get(&image_url) {
-        Ok(resp) => match resp.bytes() {
-            Ok(text) => match image::load_from_memory_with_format(&text, image::ImageFormat::Png) {
-                Ok(image) => image,
-                Err(e) => {
-                    println!("could not parse avatar image: {}", e);
-                    return Ok(())
-                }
-            },
-            Err(e) => {
-                println!("could not get image response body: {}", e);
-                return Ok(())
-            } 
-        },
-        Err(e) => {
-            println!("could not fetch image: {}", e);
-            return Ok(())
+        last_seen = modified;
+        info!("assets changed, reloading");
+        let errors = reload_registry(&ctx.data).await;
+        if errors.is_empty() {
+            slash::register(&ctx.http, &current_assets(&ctx).await.bonks).await;
         }
-    };
-    let bonk_choice = match bonker::choose_bonk() {
-        Ok(choice) => choice,
-        Err(e) => {
-            println!("{}", e);
-            return Ok(())
+        else {
+            notify_owners(&ctx, &format!("couldn't reload the assets, still using the old ones:\n{}", errors.join("\n"))).await;
         }
+    }
+}
+
+//DMs every bot owner
+async fn notify_owners(ctx: &Context, text: &str) {
+    let owners = {
+        let data = ctx.data.read().await;
+        data.get::<BotOwners>().cloned().unwrap_or_default()
     };
-    let bonk_image = match bonker::overlay_bonk(avatar, &bonk_choice) {
-        Ok(bonked) => bonked,
-        Err(e) => {
-            println!("{}", e);
-            return Ok(())
+    let chat = chat(ctx).await;
+    for owner in owners {
+        if let Err(e) = chat.dm(owner, text).await {
+            warn!(owner, error = %e, "could not DM owner");
         }
+    }
+}
+
+//the settings for a guild, defaults in DMs
+async fn guild_config(ctx: &Context, guild: Option<u64>) -> config::GuildConfig {
+    let guild = match guild {
+        Some(guild) => guild,
+        None => return config::GuildConfig::default(),
     };
-    match bonk_image.save_with_format("bonked.png", image::ImageFormat::Png) {
-        Ok(()) => {
-            let _ = msg.channel_id.send_message(&ctx.http, |m| {
-                m.add_file("bonked.png");
-                m
-            }).await;
-        },
-        Err(e) => {
-            println!("could not save image: {}", e);
-            return Ok(())
-        }
+    let data = ctx.data.read().await;
+    match data.get::<GuildConfigs>().and_then(|configs| configs.get(&guild)) {
+        Some(config) => config.clone(),
+        None => config::GuildConfig::default(),
+    }
+}
+
+//changes and saves the settings for a guild, returning what they ended up as
+async fn update_guild_config<F: FnOnce(&mut config::GuildConfig)>(ctx: &Context, guild: Option<u64>, update: F) -> config::GuildConfig {
+    let guild = match guild {
+        Some(guild) => guild,
+        None => return config::GuildConfig::default(),
     };
-    Ok(())
+    let mut data = ctx.data.write().await;
+    let dir = data_dir(&data);
+    let configs = data.get_mut::<GuildConfigs>().expect("could not get mutable GuildConfigs!");
+    let config = configs.entry(guild).or_insert_with(config::GuildConfig::default);
+    update(config);
+    let config = config.clone();
+    if let Err(why) = config::save_configs(&dir, configs) {
+        error!(error = &*why, "could not save guild config");
+    }
+    config
 }
 
 #[command]
-async fn help(ctx: &Context, msg: &Message) -> CommandResult {
-    if let Err(why) = msg.channel_id.say(&ctx.http, "Fuck you").await {
-        println!("Error sending message: {:?}", why);
+#[description = "Lists the commands, or how to use one"]
+#[usage = "[command [subcommand]] [page]"]
+#[example = "bonk"]
+#[example = "bonk leaderboard"]
+#[example = "2"]
+async fn help(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    reply_to(ctx, msg, help_reply(ctx, &Invocation::from_message(msg, args)).await).await
+}
+
+//only lists what's turned on where it was asked
+async fn help_reply(ctx: &Context, invocation: &Invocation) -> CommandReply {
+    let config = guild_config(ctx, invocation.guild).await;
+    let mut commands = MOCKER_GROUP.options.commands.to_vec();
+    if member_role(ctx, invocation.guild, *invocation.author.id.as_u64()).await == permissions::Role::Owner {
+        commands.extend_from_slice(OWNER_GROUP.options.commands);
+    }
+    let page = invocation.args.iter().find_map(|arg| arg.parse::<usize>().ok());
+    let path: Vec<String> = invocation.args.iter().filter(|arg| arg.parse::<usize>().is_err()).cloned().collect();
+    if path.is_empty() {
+        return help::overview(&commands, &config.prefix, page.unwrap_or(1), |name| config.feature_enabled(name, invocation.channel)).map_err(CommandError::from)
+    }
+    match help::find(&commands, &path) {
+        Some((full_name, command)) if config.feature_enabled(&canonical_command(&path[0]), invocation.channel) => {
+            Ok(help::details(&config.prefix, &full_name, command))
+        },
+        _ => Err(format!("there's no `{}` command, `{}help` lists them", path.join(" "), config.prefix).into()),
+    }
+}
+
+//the command's real name if it's an alias
+fn canonical_command(name: &str) -> String {
+    let path = [name.to_string()];
+    match help::find(MOCKER_GROUP.options.commands, &path).or_else(|| help::find(OWNER_GROUP.options.commands, &path)) {
+        Some((name, _)) => name,
+        None => name.to_lowercase(),
+    }
+}
+
+//"bonk list" out of the command and the subcommand that ran, just "bonk" if it wasn't a subcommand
+fn full_command_name(command: &str, ran: &str) -> String {
+    if command == ran {
+        command.to_string()
+    }
+    else {
+        format!("{} {}", command, ran)
     }
-    Ok(())
 }
 
 #[command]
-async fn remind(ctx: &Context, msg: &Message) -> CommandResult {
-    let parsed_time = scheduler::find_time(&msg.content);
-    if let Some(parsed_time) = parsed_time {
-        if let Ok(message) = msg.reply(&ctx.http, format!("I will remind you about this message on `{}` at `{}`\nother users can react with a 🕑 to also be notified", parsed_time.date(), parsed_time.time())).await {
-            if let Err(why) = message.react(&ctx.http, '🕑').await {
-                println!("Error! could not react to message {:?}: {}", msg, why)
-            }
-            let new_reminder = Reminder {
-                date_time: parsed_time,
-                message: msg.clone(),
-                verification_message: message.clone(),
-            };
-            let mut data = ctx.data.write().await;
-            let reminder_list = data.get_mut::<ReminderList>().expect("could not get mutable ReminderList!");
-            reminder_list.push(new_reminder);
-            if let Err(why) = save_reminder_list(&reminder_list) {
-                println!("could not save reminder list: {}", why);
-            }
-        }
+#[description = "Reminds you about the message at the time in it, others can join in by reacting"]
+#[usage = "<time>"]
+#[example = "in 3 days check the oven"]
+#[example = "5:45pm"]
+#[example = "2021/06/11 dentist"]
+#[aliases("remindme")]
+async fn remind(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let clock = clock(ctx).await;
+    reply_to(ctx, msg, remind_reply(&Invocation::from_message(msg, args), clock.as_ref())).await
+}
+
+fn remind_reply(invocation: &Invocation, clock: &dyn clock::Clock) -> CommandReply {
+    let parsed_time = clock.find_time(&invocation.args.join(" "));
+    debug!(?parsed_time, "parsed reminder time");
+    match parsed_time {
+        Some(parsed_time) => Ok(Reply {
+            content: format!("I will remind you about this message on `{}` at `{}`\nother users can react with a 🕑 to also be notified", parsed_time.date(), parsed_time.time()),
+            reminder: Some(parsed_time),
+            ..Default::default()
+        }),
+        None => Err("I couldn't find a time in that".into()),
     }
-    println!("{:?}", parsed_time);
-    Ok(())
+}
+
+//verification_message gets the 🕑 other users can react to, message is what gets replied to when it goes off
+async fn schedule_reminder(ctx: &Context, date_time: chrono::NaiveDateTime, message: Message, verification_message: Message, requester: u64) {
+    if let Err(why) = chat(ctx).await.react(*verification_message.channel_id.as_u64(), *verification_message.id.as_u64(), '🕑'.into()).await {
+        error!(message = *verification_message.id.as_u64(), error = %why, "could not react to message")
+    }
+    let new_reminder = Reminder {
+        date_time,
+        message,
+        verification_message,
+        requester,
+    };
+    let mut data = ctx.data.write().await;
+    let dir = data_dir(&data);
+    let reminder_list = data.get_mut::<ReminderList>().expect("could not get mutable ReminderList!");
+    reminder_list.push(new_reminder);
+    if let Err(why) = save_reminder_list(&dir, reminder_list) {
+        error!(error = &*why, "could not save reminder list");
+    }
+}
+
+//takes a reminder out by the id of the message it's confirmed on, false if there's no such reminder
+async fn cancel_reminder(data: &RwLock<TypeMap>, id: u64) -> Result<bool, String> {
+    let mut data = data.write().await;
+    let dir = data_dir(&data);
+    let reminder_list = data.get_mut::<ReminderList>().expect("could not get mutable ReminderList!");
+    let count = reminder_list.len();
+    reminder_list.retain(|reminder| *reminder.verification_message.id.as_u64() != id);
+    if reminder_list.len() == count {
+        return Ok(false)
+    }
+    info!(reminder = id, "reminder cancelled");
+    save_reminder_list(&dir, reminder_list).map_err(|e| format!("could not save the reminder list: {}", e))?;
+    Ok(true)
 }
 
 struct Handler;
@@ -254,195 +1836,272 @@ struct Handler;
 #[async_trait]
 impl EventHandler for Handler {
     async fn message(&self, ctx: Context, msg: Message) {
-        //println!("{}: {}", msg.author.name, msg.content);
-        let mocks = check_mocker(&ctx, *msg.author.id.as_u64()).await;
-        if mocks > 0 {
-            //mock this user, then decrement their value in the tracker
-            decrement_mocker(&ctx, *msg.author.id.as_u64()).await;
-            println!("mocking user: {} ({} mocks left)", msg.author.name, mocks-1);
-            let mocked_msg = mock_string(&msg.content);
-            if let Err(why) = msg.channel_id.say(&ctx.http, mocked_msg).await {
-                println!("Error sending message: {:?}", why);
-            }
-        }
-        //check if enough time has elapsed since the last time we changed status
-        //change at most every hour
-        let time_wait = 60*60;
-        let elapsed_time = check_status_time(&ctx).await;
-        if elapsed_time.as_secs() > time_wait {
-            update_activity(&ctx).await;
-        }
-        //check em
-        let mut id = *msg.id.as_u64();
-        let mut count = 0i32;
-        loop {
-            count += 1;
-            let digit = id % 10;
-            id /= 10;
-            if digit != (id % 10) || id == 0 {
-                break
-            }
-        }
-        let emoji = match count {
-            2 => {
-                Some(ReactionType::Custom {
-                    animated: false,
-                    id: 796856698098810931.into(),
-                    name: Some("dubs".to_string()),
-                })
-            },
-            3 => {
-                Some(ReactionType::Custom {
-                    animated: false,
-                    id: 796861409203060776.into(),
-                    name: Some("trips".to_string()),
-                })
-            },
-            4 => {
-                Some(ReactionType::Custom {
-                    animated: false,
-                    id: 796861430297001985.into(),
-                    name: Some("quads".to_string()),
-                })
-            },
-            5 => {
-                Some(ReactionType::Custom {
-                    animated: false,
-                    id: 796861455894577163.into(),
-                    name: Some("quints".to_string()),
-                })
-            },
-            6 => {
-                Some(ReactionType::Custom {
-                    animated: false,
-                    id: 796861477004771328.into(),
-                    name: Some("sexes".to_string()),
-                })
-            },
-            _ => None,
+        let _in_flight = match begin_work(&ctx).await {
+            Some(in_flight) => in_flight,
+            None => return,
         };
-        match emoji {
-            Some(emoji) => {
-                if let Err(why) = msg.react(&ctx.http, emoji).await {
-                    println!("could not react to message: {}", why);
-                }
-            },
-            _ => (),
-        }
-        //ehem...culture time
-        let me = match ctx.http.as_ref().get_current_user().await {
-            Ok(user) => *user.id.as_u64(),
-            Err(e) => {
-                println!("couldn't get current user??? {}", e);
-                0
-            }
+        let span = tracing::info_span!("message",
+            guild = msg.guild_id.map(|id| *id.as_u64()),
+            channel = *msg.channel_id.as_u64(),
+            user = *msg.author.id.as_u64(),
+        );
+        handle_message(ctx, msg).instrument(span).await
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let _in_flight = match begin_work(&ctx).await {
+            Some(in_flight) => in_flight,
+            None => return,
         };
-        if let Some(channel) = msg.channel(&ctx).await {
-            if channel.is_nsfw() && msg.author.id != me {
-                let test_id = *msg.id.as_u64() % 1_000_000;
-                let mut banned_tags: Vec<u64> = Vec::new();
-                //no loli please
-                banned_tags.push(19440);
-                match reqwest::get(&format!("https://nhentai.net/api/gallery/{}", test_id)).await {
-                    Ok(response) => {
-                        if response.status() == 200 {
-                            match response.text().await {
-                                Ok(raw) => {
-                                    match serde_json::from_str(&raw) {
-                                        Ok(Value::Object(map)) => {
-                                            let banned = match map.get("tags") {
-                                                Some(Value::Array(array)) => {
-                                                    array.iter().any( |tag| {
-                                                        if let Some(Value::Number(id)) = tag.get("id") {
-                                                            banned_tags.iter().any( |&banned_id| banned_id == id.as_u64().unwrap_or(0))
-                                                        }
-                                                        else {
-                                                            false
-                                                        }
-                                                    })
-                                                },
-                                                _ => false,
-                                            };
-                                            if banned {
-                                                println!("{} banned tag detected", test_id);
-                                            }
-                                            else {
-                                                let title = match &map["title"]["english"] {
-                                                    Value::String(title) => format!("{}\n<https://nhentai.net/g/{}>", title.as_str(), test_id),
-                                                    _ => format!("<https://nhentai.net/g/{}>", test_id),
-                                                };
-                                                if let Err(why) = msg.channel_id.say(&ctx.http, title).await {
-                                                    println!("Error sending message: {:?}", why);
-                                                }
-                                            }
-                                        },
-                                        Ok(_) => println!("didn't get an object back"),
-                                        Err(why) => println!("could not parse json response: {}", why),
-                                    }
-                                },
-                                Err(why) => println!("could not get text of response: {}", why),
-                            }
-                        }
-                        else {
-                            println!("{} not found", test_id);
-                        }
-                    },
-                    Err(why) => println!("could not send request to website: {}", why),
-                }
-            }
+        let application = {
+            let data = ctx.data.read().await;
+            data.get::<SlashApplication>().copied()
+        };
+        match application {
+            Some(application) => slash::handle(&ctx, application, interaction).await,
+            None => warn!("got a slash command before they were registered"),
         }
     }
 
+    async fn resume(&self, ctx: Context, _: ResumedEvent) {
+        info!("gateway resumed");
+        bot_metrics(&ctx).await.gateway_reconnect();
+    }
+
     async fn ready(&self, ctx: Context, ready: Ready) {
-        println!("{} is connected!", ready.user.name);
-        let ctx2 = ctx.clone();
-        tokio::spawn(async move {
-            let ctx = ctx2;
-            let me = match ctx.http.as_ref().get_current_user().await {
-                Ok(user) => *user.id.as_u64(),
-                Err(e) => {
-                    println!("couldn't get current user??? {}", e);
-                    0
-                }
-            };
-            //every minute, wake up and fire any reminders that have expired
-            loop {
-                tokio::time::sleep(Duration::from_secs(60)).await;
-                {
-                    let mut data = ctx.data.write().await;
-                    let now = chrono::Local::now().naive_local();
-                    let reminder_list = data.get_mut::<ReminderList>().expect("could not get mutable reminder list");
-                    let expired_reminders = reminder_list.iter().filter(|reminder| reminder.date_time < now);
-                    for reminder in expired_reminders {
-                        let mut other_users = reminder.verification_message.reaction_users(&ctx, '🕑', None, None).await.unwrap_or(Vec::new());
-                        other_users.retain(|user| *user.id.as_u64() != me);
-
-                        if let Err(why) = reminder.message.reply_ping(&ctx, "Reminding you of this message").await {
-                            println!("Error! could not post reply message: {}", why);
-                        }
-                        if other_users.len() > 0 {
-                            let mut msg_content = MessageBuilder::new();
-                            for user in other_users {
-                                msg_content.mention(&user);
+        info!(user = %ready.user.name, "connected");
+        if let Some(application) = slash::register(&ctx.http, &current_assets(&ctx).await.bonks).await {
+            let mut data = ctx.data.write().await;
+            data.insert::<SlashApplication>(application);
+        }
+        start_background(ctx).await;
+    }
+}
+
+//ready fires again after every full reconnect, only the first one starts the loops
+async fn start_background(ctx: Context) -> bool {
+    {
+        let mut data = ctx.data.write().await;
+        if data.contains_key::<BackgroundStarted>() {
+            return false
+        }
+        data.insert::<BackgroundStarted>(());
+    }
+    tokio::spawn(watch_assets(ctx.clone()).instrument(tracing::info_span!("watch_assets")));
+    tokio::spawn(rotate_activity(ctx.clone()).instrument(tracing::info_span!("rotate_activity")));
+    tokio::spawn(fire_reminders(ctx).instrument(tracing::info_span!("reminders")));
+    true
+}
+
+//mocking, check em and the gallery, everything that looks at plain messages
+async fn handle_message(ctx: Context, msg: Message) {
+    let mocks = check_mocker(&ctx, *msg.author.id.as_u64()).await;
+    if mocks > 0 && feature_enabled(&ctx, &msg, "mock_replay").await {
+        //mock this user, then decrement their value in the tracker
+        decrement_mocker(&ctx, *msg.author.id.as_u64()).await;
+        bot_metrics(&ctx).await.mock_replay();
+        info!(mocks_left = mocks-1, "mocking user");
+        let mocked_msg = mock_string(&msg.content);
+        if let Err(why) = chat(&ctx).await.send(*msg.channel_id.as_u64(), None, &Reply::text(mocked_msg)).await {
+            error!(error = %why, "could not send message");
+        }
+    }
+    //check em
+    if feature_enabled(&ctx, &msg, "checkem_reactions").await {
+        check_em(&ctx, &msg).await;
+    }
+    //ehem...culture time
+    let me = current_user(&ctx).await;
+    let chat = chat(&ctx).await;
+    if *msg.author.id.as_u64() != me && feature_enabled(&ctx, &msg, "gallery").await && chat.is_nsfw(*msg.channel_id.as_u64()).await {
+        let test_id = *msg.id.as_u64() % 1_000_000;
+        let mut banned_tags: Vec<u64> = Vec::new();
+        //no loli please
+        banned_tags.push(19440);
+        match chat.fetch(&format!("https://nhentai.net/api/gallery/{}", test_id)).await {
+            Ok(response) => {
+                if response.status == 200 {
+                    match serde_json::from_slice(&response.body) {
+                        Ok(Value::Object(map)) => {
+                            let banned = match map.get("tags") {
+                                Some(Value::Array(array)) => {
+                                    array.iter().any( |tag| {
+                                        if let Some(Value::Number(id)) = tag.get("id") {
+                                            banned_tags.iter().any( |&banned_id| banned_id == id.as_u64().unwrap_or(0))
+                                        }
+                                        else {
+                                            false
+                                        }
+                                    })
+                                },
+                                _ => false,
+                            };
+                            if banned {
+                                info!(test_id, "banned tag detected");
                             }
-                            println!("{}", msg_content);
-                            if let Err(why) = reminder.message.channel_id.say(&ctx, msg_content).await {
-                                println!("Error! could not post reply message: {}", why);
+                            else {
+                                let title = match &map["title"]["english"] {
+                                    Value::String(title) => format!("{}\n<https://nhentai.net/g/{}>", title.as_str(), test_id),
+                                    _ => format!("<https://nhentai.net/g/{}>", test_id),
+                                };
+                                if let Err(why) = chat.send(*msg.channel_id.as_u64(), None, &Reply::text(title)).await {
+                                    error!(error = %why, "could not send message");
+                                }
                             }
-                        }
-                    }
-                    reminder_list.retain(|reminder| reminder.date_time > now);
-                    if let Err(why) = save_reminder_list(reminder_list) {
-                        println!("could not save reminder list: {}", why);
+                        },
+                        Ok(_) => warn!("didn't get an object back"),
+                        Err(why) => warn!(error = %why, "could not parse json response"),
                     }
                 }
+                else {
+                    debug!(test_id, "not found");
+                }
+            },
+            Err(why) => warn!(error = %why, "could not send request to website"),
+        }
+    }
+}
+
+//replies to reminders once they're due, pinging whoever reacted too
+async fn fire_reminders(ctx: Context) {
+    let me = current_user(&ctx).await;
+    let clock = clock(&ctx).await;
+    ctx.data.write().await.insert::<SchedulerHeartbeat>(Instant::now());
+    //every minute, wake up and fire any reminders that have expired
+    loop {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        //a shutdown waits for a round that's already started, so reminders are never half fired
+        let _in_flight = match begin_work(&ctx).await {
+            Some(in_flight) => in_flight,
+            None => break,
+        };
+        fire_due_reminders(&ctx, me, clock.local_naive()).await;
+    }
+}
+
+//one round of the reminder loop, me is the bot so its own 🕑 doesn't get pinged
+async fn fire_due_reminders(ctx: &Context, me: u64, now: chrono::NaiveDateTime) {
+    let metrics = bot_metrics(ctx).await;
+    let chat = chat(ctx).await;
+    let mut data = ctx.data.write().await;
+    data.insert::<SchedulerHeartbeat>(Instant::now());
+    let dir = data_dir(&data);
+    let reminder_list = data.get_mut::<ReminderList>().expect("could not get mutable reminder list");
+    let expired_reminders = reminder_list.iter().filter(|reminder| reminder.date_time <= now);
+    for reminder in expired_reminders {
+        let channel = *reminder.message.channel_id.as_u64();
+        info!(message = *reminder.message.id.as_u64(), channel, "firing reminder");
+        metrics.scheduler_lag.observe((now - reminder.date_time).to_std().unwrap_or_default());
+        let verification = &reminder.verification_message;
+        let mut other_users = chat.reaction_users(*verification.channel_id.as_u64(), *verification.id.as_u64(), '🕑'.into()).await.unwrap_or_default();
+        other_users.retain(|user| *user != me);
+
+        let mut reminder_content = MessageBuilder::new();
+        //slash command reminders hang off the bot's own reply, so ping whoever asked
+        if reminder.requester != 0 && reminder.requester != *reminder.message.author.id.as_u64() {
+            reminder_content.mention(&UserId(reminder.requester)).push(" ");
+        }
+        reminder_content.push("Reminding you of this message");
+        match chat.send(channel, Some(&reminder.message), &Reply::text(reminder_content.build())).await {
+            Ok(_) => metrics.reminder_fired(),
+            Err(why) => {
+                error!(error = %why, "could not post reminder");
+                metrics.reminder_failed();
+            },
+        }
+        if !other_users.is_empty() {
+            let mut msg_content = MessageBuilder::new();
+            for user in other_users {
+                msg_content.mention(&UserId(user));
             }
-        });
-        update_activity(&ctx).await;
+            debug!(mentions = %msg_content, "pinging others");
+            if let Err(why) = chat.send(channel, None, &Reply::text(msg_content.build())).await {
+                error!(error = %why, "could not post reminder");
+            }
+        }
+    }
+    reminder_list.retain(|reminder| reminder.date_time > now);
+    if let Err(why) = save_reminder_list(&dir, reminder_list) {
+        error!(error = &*why, "could not save reminder list");
     }
 }
 
+//reacts to every repeating digit pattern at the end of the message id
+async fn check_em(ctx: &Context, msg: &Message) {
+    let patterns = checkem::detect(*msg.id.as_u64());
+    if patterns.is_empty() {
+        return
+    }
+    let config = guild_config(ctx, msg.guild_id.map(|id| *id.as_u64())).await;
+    if config.gets_tracking {
+        record_gets(ctx, msg, &patterns, config.hall_of_fame).await;
+    }
+    let reactions: Vec<(checkem::Pattern, ReactionType)> = patterns.into_iter()
+        .filter_map(|pattern| checkem::reaction_for(&config.checkem_emoji, pattern).map(|reaction| (pattern, reaction)))
+        .collect();
+    let chat = chat(ctx).await;
+    for (pattern, reaction) in reactions {
+        if let Err(why) = chat.react(*msg.channel_id.as_u64(), *msg.id.as_u64(), reaction.clone()).await {
+            //custom emoji from another server are the usual culprit, try again with plain unicode
+            let fallback = pattern.fallback_reaction();
+            if reaction == fallback {
+                warn!(error = %why, "could not react to message");
+            }
+            else if let Err(why) = chat.react(*msg.channel_id.as_u64(), *msg.id.as_u64(), fallback).await {
+                warn!(error = %why, "could not react to message");
+            }
+        }
+    }
+}
 
+//logs the gets and reposts the best one to the hall of fame, if the guild has one
+async fn record_gets(ctx: &Context, msg: &Message, patterns: &[checkem::Pattern], hall_of_fame: Option<u64>) {
+    let guild = msg.guild_id.map(|id| *id.as_u64());
+    let now = clock(ctx).await.local_naive();
+    {
+        let mut data = ctx.data.write().await;
+        let dir = data_dir(&data);
+        let log = match data.get_mut::<GetsTracker>() {
+            Some(log) => log,
+            None => {
+                error!("could not get mutable reference to the gets tracker!");
+                return
+            }
+        };
+        for pattern in patterns {
+            log.records.push(gets::GetRecord {
+                guild,
+                channel: *msg.channel_id.as_u64(),
+                message: *msg.id.as_u64(),
+                user: *msg.author.id.as_u64(),
+                pattern: *pattern,
+                time: now,
+            });
+        }
+        if let Err(why) = gets::save_gets(&dir, log) {
+            error!(error = &*why, "could not save gets");
+        }
+    }
+    let best = patterns.iter().copied().max_by_key(|pattern| gets::points(*pattern));
+    if let (Some(channel), Some(best)) = (hall_of_fame, best) {
+        if gets::is_hall_of_fame(best) {
+            let post = Reply {
+                embed: Some(invocation::ReplyEmbed {
+                    title: format!("{} in {}", best.name().to_uppercase(), msg.id),
+                    description: format!("{}\n\n[Jump to message]({})", msg.content, msg.link()),
+                    author: Some(msg.author.name.clone()),
+                    author_icon: msg.author.avatar_url(),
+                    timestamp: Some(msg.timestamp.to_rfc3339()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            if let Err(why) = chat(ctx).await.send(channel, None, &post).await {
+                error!(error = %why, "could not post to the hall of fame");
+            }
+        }
+    }
+}
 
 async fn track_mocker(ctx: &Context, user: u64, amount: isize) {
     let mut data = ctx.data.write().await;
@@ -456,7 +2115,7 @@ async fn decrement_mocker(ctx: &Context, user: u64) {
     let mock_tracker = match data.get_mut::<MockTracker>() {
         Some(tracker) => tracker,
         None => {
-            println!("could not get mutable reference to the mock tracker!");
+            error!("could not get mutable reference to the mock tracker!");
             return
         }
     };
@@ -472,7 +2131,7 @@ async fn check_mocker(ctx: &Context, user: u64) -> isize {
     let mock_tracker = match data.get::<MockTracker>() {
         Some(value) => value,
         _ => {
-            println!("couldn't get tracker");
+            error!("couldn't get tracker");
             return 0
         },
     };
@@ -482,52 +2141,74 @@ async fn check_mocker(ctx: &Context, user: u64) -> isize {
     }
 }
 
-//time since last update
-async fn check_status_time(ctx: &Context) -> Duration {
-    let data = ctx.data.read().await;
-    let last_change = match data.get::<StatusTimer>() {
-        Some(value) => value,
-        _ => {
-            println!("couldn't get last status change!");
-            return Duration::from_secs(0)
-        },
-    };
-    Instant::now() - *last_change
+//changes the status every activity_minutes, whether or not anyone is talking
+async fn rotate_activity(ctx: Context) {
+    let minutes = bot_settings(&ctx).await.activity_minutes;
+    loop {
+        update_activity(&ctx).await;
+        tokio::time::sleep(Duration::from_secs(minutes * 60)).await;
+    }
 }
 
-async fn update_status_time(ctx: &Context) {
-    let mut data = ctx.data.write().await;
-    let status_time = match data.get_mut::<StatusTimer>() {
-        Some(time) => time,
+async fn update_activity(ctx: &Context) {
+    let pinned = {
+        let data = ctx.data.read().await;
+        data.get::<PinnedActivity>().cloned().flatten()
+    };
+    let assets = current_assets(ctx).await;
+    let hour = clock(ctx).await.local_naive().hour();
+    let entry = match pinned.as_ref().or_else(|| activity::pick(&assets.activities, hour)) {
+        Some(entry) => entry,
         None => {
-            println!("could not get mutable reference to the status timer!");
+            warn!("no activities to choose from right now");
             return
         },
     };
-    *status_time = Instant::now();
+    let mut values = HashMap::new();
+    for variable in entry.variables() {
+        if let Some((name, _)) = activity::VARIABLES.iter().find(|(name, _)| *name == variable) {
+            values.insert(*name, activity_value(ctx, name).await);
+        }
+    }
+    let text = activity::fill(&entry.text, &values);
+    info!(activity = %text, "activity changed");
+    ctx.set_activity(entry.to_activity(&text)).await;
 }
 
-async fn update_activity(ctx: &Context) {
-    match std::fs::read_to_string("./assets/activities.json") {
-        Ok(string) => match serde_json::from_str::<Vec<String>>(&string) {
-            Ok(data) => {
-                let choice = data.choose(&mut rand::thread_rng());
-                match choice {
-                    Some(item) => {
-                        //let mut activity = Activity::playing("Custom Status");
-                        //activity.kind = ActivityType::Custom;
-                        //activity.state = Some(item.to_string());
-                        //ctx.set_activity(activity);
-                        ctx.set_activity(Activity::playing(item)).await;
-                    },
-                    None => println!("activities has no elements to choose from"),
-                }
-            },
-            Err(e) => println!("could not parse JSON: {}", e),
+//what a {variable} in an activity stands for right now
+async fn activity_value(ctx: &Context, variable: &str) -> String {
+    match variable {
+        "guild_count" => ctx.cache.guild_count().await.to_string(),
+        "next_reminder" => {
+            let next = {
+                let data = ctx.data.read().await;
+                data.get::<ReminderList>().and_then(|reminders| reminders.iter().map(|reminder| reminder.date_time).min())
+            };
+            let now = clock(ctx).await.local_naive();
+            match next {
+                Some(next) => activity::describe_wait(next - now),
+                None => "nothing".to_string(),
+            }
         },
-        Err(e) => println!("could not read 'activities.json': {}", e),
-    };
-    update_status_time(&ctx).await;
+        "top_bonker" => {
+            let top = {
+                let data = ctx.data.read().await;
+                data.get::<BonkStats>().and_then(|records| bonk_stats::top_bonker(records))
+            };
+            match top {
+                Some((user, _)) => match UserId(user).to_user(ctx).await {
+                    Ok(user) => user.name,
+                    Err(_) => "someone".to_string(),
+                },
+                None => "nobody".to_string(),
+            }
+        },
+        "bonk_count" => {
+            let data = ctx.data.read().await;
+            data.get::<BonkStats>().map_or(0, |records| records.len()).to_string()
+        },
+        _ => format!("{{{}}}", variable),
+    }
 }
 
 fn mock_string(to_mock: &str) -> String {
@@ -549,9 +2230,29 @@ fn mock_string(to_mock: &str) -> String {
 
 
 
+//who's being mocked and how many more times, so a restart doesn't let anyone off
+fn load_mock_tracker(dir: &str) -> HashMap<u64, isize> {
+    match std::fs::read_to_string(format!("{}/mock_tracker.json", dir)) {
+        Ok(string) => match serde_json::from_str(&string) {
+            Ok(data) => data,
+            Err(e) => {
+                error!(error = %e, "could not parse mock_tracker.json");
+                HashMap::new()
+            },
+        },
+        Err(_) => HashMap::new(),
+    }
+}
+
+fn save_mock_tracker(dir: &str, tracker: &HashMap<u64, isize>) -> Result<(), Box<dyn Error>> {
+    let json_content = serde_json::to_string(tracker)?;
+    std::fs::write(format!("{}/mock_tracker.json", dir), json_content)?;
+    Ok(())
+}
+
 //serialize the reminder list
-fn save_reminder_list(reminder_list: &Vec<Reminder>) -> Result<(), Box<dyn Error>> {
+fn save_reminder_list(dir: &str, reminder_list: &Vec<Reminder>) -> Result<(), Box<dyn Error>> {
     let json_content = serde_json::to_string(&reminder_list)?;
-    std::fs::write("./assets/reminder_list.json", json_content)?;
+    std::fs::write(format!("{}/reminder_list.json", dir), json_content)?;
     Ok(())
 }
\ No newline at end of file
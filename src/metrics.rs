@@ -0,0 +1,137 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+//upper bounds in seconds
+const LATENCY_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+//the reminder loop wakes up once a minute, so anything under that is on time
+const LAG_BUCKETS: &[f64] = &[1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+pub struct Histogram {
+    bounds: &'static [f64],
+    counts: Mutex<HistogramCounts>,
+}
+
+#[derive(Default)]
+struct HistogramCounts {
+    //one per bound, not cumulative
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Histogram {
+        Histogram {bounds, counts: Mutex::new(HistogramCounts {buckets: vec![0; bounds.len()], ..Default::default()})}
+    }
+
+    pub fn observe(&self, value: Duration) {
+        let seconds = value.as_secs_f64();
+        let mut counts = self.counts.lock().unwrap();
+        if let Some(bucket) = self.bounds.iter().position(|bound| seconds <= *bound) {
+            counts.buckets[bucket] += 1;
+        }
+        counts.sum += seconds;
+        counts.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, help: &str) {
+        let counts = self.counts.lock().unwrap();
+        header(out, name, help, "histogram");
+        let mut cumulative = 0;
+        for (bound, count) in self.bounds.iter().zip(&counts.buckets) {
+            cumulative += count;
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative);
+        }
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, counts.count);
+        let _ = writeln!(out, "{}_sum {}", name, counts.sum);
+        let _ = writeln!(out, "{}_count {}", name, counts.count);
+    }
+}
+
+//everything /metrics shows, shared by the commands, the reminder loop and the web server
+pub struct Metrics {
+    //(command, outcome) -> times run
+    commands: Mutex<BTreeMap<(String, &'static str), u64>>,
+    pub bonk_render: Histogram,
+    pub avatar_fetch: Histogram,
+    //how late reminders go off
+    pub scheduler_lag: Histogram,
+    reminders_fired: AtomicU64,
+    reminders_failed: AtomicU64,
+    mock_replays: AtomicU64,
+    gateway_reconnects: AtomicU64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            commands: Mutex::new(BTreeMap::new()),
+            bonk_render: Histogram::new(LATENCY_BUCKETS),
+            avatar_fetch: Histogram::new(LATENCY_BUCKETS),
+            scheduler_lag: Histogram::new(LAG_BUCKETS),
+            reminders_fired: AtomicU64::new(0),
+            reminders_failed: AtomicU64::new(0),
+            mock_replays: AtomicU64::new(0),
+            gateway_reconnects: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Metrics {
+    //outcome is "ok" or a CommandError's kind
+    pub fn command(&self, name: &str, outcome: &'static str) {
+        *self.commands.lock().unwrap().entry((name.to_string(), outcome)).or_insert(0) += 1;
+    }
+
+    pub fn reminder_fired(&self) {
+        self.reminders_fired.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn reminder_failed(&self) {
+        self.reminders_failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn mock_replay(&self) {
+        self.mock_replays.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn gateway_reconnect(&self) {
+        self.gateway_reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    //the prometheus text format, reminders_pending is read off the reminder list when it's asked for
+    pub fn render(&self, reminders_pending: usize) -> String {
+        let mut out = String::new();
+        header(&mut out, "shawty_commands_total", "commands run, by name and outcome", "counter");
+        for ((name, outcome), count) in self.commands.lock().unwrap().iter() {
+            let _ = writeln!(out, "shawty_commands_total{{command=\"{}\",outcome=\"{}\"}} {}", escape(name), outcome, count);
+        }
+        self.bonk_render.render(&mut out, "shawty_bonk_render_seconds", "time spent drawing a bonk");
+        self.avatar_fetch.render(&mut out, "shawty_avatar_fetch_seconds", "time spent downloading an avatar or image");
+        self.scheduler_lag.render(&mut out, "shawty_reminder_lag_seconds", "how long after their time reminders went off");
+        header(&mut out, "shawty_reminders_pending", "reminders waiting to go off", "gauge");
+        let _ = writeln!(out, "shawty_reminders_pending {}", reminders_pending);
+        counter(&mut out, "shawty_reminders_fired_total", "reminders that went off", &self.reminders_fired);
+        counter(&mut out, "shawty_reminders_failed_total", "reminders that couldn't be posted", &self.reminders_failed);
+        counter(&mut out, "shawty_mock_replays_total", "messages repeated back by !mock", &self.mock_replays);
+        counter(&mut out, "shawty_gateway_reconnects_total", "times the gateway connection was resumed after dropping", &self.gateway_reconnects);
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn counter(out: &mut String, name: &str, help: &str, value: &AtomicU64) {
+    header(out, name, help, "counter");
+    let _ = writeln!(out, "{} {}", name, value.load(Ordering::Relaxed));
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
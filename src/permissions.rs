@@ -0,0 +1,67 @@
+use std::collections::HashSet;
+use std::fmt;
+use serenity::model::permissions::Permissions;
+use tracing::{error, warn};
+
+//who's allowed to do what, each level can do everything the ones below it can
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    User,
+    //manage messages, or the guild's moderator_role
+    Moderator,
+    //manage server
+    Admin,
+    //whoever runs the bot, can do anything anywhere
+    Owner,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Role::User => "user",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+            Role::Owner => "bot owner",
+        })
+    }
+}
+
+pub fn role_for(is_owner: bool, permissions: Permissions, roles: &[u64], moderator_role: Option<u64>) -> Role {
+    if is_owner {
+        Role::Owner
+    }
+    else if permissions.manage_guild() || permissions.administrator() {
+        Role::Admin
+    }
+    else if permissions.manage_messages() || moderator_role.is_some_and(|role| roles.contains(&role)) {
+        Role::Moderator
+    }
+    else {
+        Role::User
+    }
+}
+
+//comma separated ids, whitespace is fine
+pub fn parse_owners(list: &str) -> HashSet<u64> {
+    list.split(',').filter_map(|id| id.trim().parse().ok()).collect()
+}
+
+//BOT_OWNERS in the environment, then owners.json in the data folder, empty if neither is there
+pub fn load_owners(dir: &str) -> HashSet<u64> {
+    if let Ok(list) = std::env::var("BOT_OWNERS") {
+        return parse_owners(&list)
+    }
+    match std::fs::read_to_string(format!("{}/owners.json", dir)) {
+        Ok(string) => match serde_json::from_str(&string) {
+            Ok(owners) => owners,
+            Err(e) => {
+                error!(error = %e, "could not parse owners.json");
+                HashSet::new()
+            },
+        },
+        Err(e) => {
+            warn!(error = %e, "could not read owners.json");
+            HashSet::new()
+        },
+    }
+}
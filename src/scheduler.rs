@@ -1,236 +1,397 @@
-use date_time_parser::DateParser;
-use date_time_parser::TimeParser;
-use chrono::{NaiveDateTime, Datelike};
-use regex::Regex;
-
-
-pub fn find_time(time_string: &str) -> Option<NaiveDateTime> {
-    let time_string = time_string.to_lowercase();
-    return match get_exact_datetime(&time_string) {
-        Some(datetime) => Some(datetime),
-        None => {
-            match get_offset_time(&time_string) {
-                Some(datetime) => Some(datetime),
-                None => {
-                    get_fuzzy_time(&time_string)
-                }
-            }
-        }
-    }
-}
-
-//returns a time in the future based on a 'natural' string
-fn get_fuzzy_time(string_time: &str) -> Option<NaiveDateTime> {
-    let date = DateParser::parse(string_time);
-    let time = TimeParser::parse(string_time);
-    match (date, time) {
-        (None, None) => return None,
-        _ => ()
-    }
-    //at least one of date/time was successfully parsed
-    let time = time.unwrap_or(chrono::NaiveTime::from_hms(0, 0, 0));
-    let date = date.unwrap_or(chrono::Local::now().naive_local().date());
-
-    //DILEMMA
-    //at this point, we could have something like '7:30'
-    //if time did not have an am/pm specifier, then it could potentially be in the past, but that might not be what they meant
-    //we can maybe resolve this by checking if the currently parsed date is in the past, 
-    //  and if the time component is before noon, try adding 12 hours and see if that puts us in the future
-    let mut datetime = chrono::NaiveDateTime::new(date, time);
-    let time_now = chrono::Local::now().naive_local();
-    if datetime < time_now && time < chrono::NaiveTime::from_hms(12, 0, 0) {
-        datetime = datetime + chrono::Duration::hours(12);
-    }
-
-    //make sure the date is in the future
-    if datetime > time_now {Some(datetime)}
-    else {None}
-}
-
-//in X <timescale>
-//X <timescale> from now
-//X <timescale>
-//next <timescale>
-fn get_offset_time(time_string: &str) -> Option<NaiveDateTime> {
-
-    let mut potential_datetimes: Vec<NaiveDateTime> = Vec::new();
-    let time_now = chrono::Local::now().naive_local();
-
-    let re = Regex::new(r"(\d+)\s?(\S+)").unwrap();
-    for cap in re.captures_iter(time_string) {
-        let offset = match cap[1].parse::<i32>() {
-            Ok(offset) => offset,
-            _ => continue,
-        };
-        let time_scale = match str_to_timescale(&cap[2]) {
-            Some(time_scale) => time_scale*offset,
-            _ => continue,
-        };
-        potential_datetimes.push(time_now + time_scale);
-    }
-
-    let re = Regex::new(r"next\s(\S+)").unwrap();
-    for cap in re.captures_iter(time_string) {
-        let time_scale = match str_to_timescale(&cap[1]) {
-            Some(time_scale) => time_scale,
-            _ => continue,
-        };
-        potential_datetimes.push(time_now + time_scale);
-    }
-    potential_datetimes = potential_datetimes.into_iter().filter(|element| element > &time_now).collect();
-    if potential_datetimes.len() > 0 {
-        Some(potential_datetimes[0])
-    }
-    else {
-        None
-    }
-}
-
-//for getting exact timestamps
-//date
-//YYYY_MM_DD
-//YY_MM_DD
-//MM_DD_YYYY
-//MM_DD_YY
-//MM_DD
-//time
-//HH:MM:SS
-//H:MM:SS
-fn get_exact_datetime(time_string: &str) -> Option<NaiveDateTime> {
-    let mut potential_dates = Vec::new();
-    let mut potential_times = Vec::new();
-    //date regexes
-    //allowed separators
-    let sep_set = r"[/\- \._\\]?";
-    //unambiguous yyyy mm dd
-    let date_regex_1 = Regex::new(&format!("{}{}{}{}{}", r"(\d{4})", sep_set, r"(\d{2})", sep_set, r"(\d{2})")).unwrap();
-    for cap in date_regex_1.captures_iter(time_string) {
-        let curr_string = format!("{}-{}-{}", &cap[1], &cap[2], &cap[3]);
-        match chrono::NaiveDate::parse_from_str(&curr_string, "%Y-%m-%d") {
-            Ok(date) => potential_dates.push(date),
-            _ => continue,
-        };
-    }
-    //unambiguous  mm dd yyyy
-    let date_regex_2 = Regex::new(&format!("{}{}{}{}{}", r"(\d{2})", sep_set, r"(\d{2})", sep_set, r"(\d{4})")).unwrap();
-    for cap in date_regex_2.captures_iter(time_string) {
-        let curr_string = format!("{}-{}-{}", &cap[3], &cap[1], &cap[2]);
-        match chrono::NaiveDate::parse_from_str(&curr_string, "%Y-%m-%d") {
-            Ok(date) => potential_dates.push(date),
-            _ => continue,
-        };
-    }
-    //ambiguous, could be yymmdd or mmddyy
-    let date_regex_3 = Regex::new(&format!("{}{}{}{}{}", r"(\d{2})", sep_set, r"(\d{2})", sep_set, r"(\d{2})")).unwrap();
-    for cap in date_regex_3.captures_iter(time_string) {
-        //attempt to determine which one is the year (which ever one is greater than 12)
-        let potential_year_1 = match cap[1].parse::<i32>() {
-            Ok(year) => year,
-            _ => continue,
-        };
-        if potential_year_1 > 12 || potential_year_1 == 0 {
-            //if we are here, then the format is yymmdd
-            //TODO: change that hardcoded 20 to get the current century prefix
-            let curr_string = format!("20{}-{}-{}", &cap[1], &cap[2], &cap[3]);
-            match chrono::NaiveDate::parse_from_str(&curr_string, "%Y-%m-%d") {
-                Ok(date) => potential_dates.push(date),
-                _ => continue,
-            };
-        }
-        else {
-            let potential_year_2 = match cap[2].parse::<i32>() {
-                Ok(year) => year,
-                _ => continue,
-            };
-            if potential_year_2 > 12 || potential_year_2 == 0 {
-                //if we are here, then the format is mmddyy
-                //TODO: change that hardcoded 20 to get the current century prefix
-                let curr_string = format!("20{}-{}-{}", &cap[3], &cap[1], &cap[2]);
-                match chrono::NaiveDate::parse_from_str(&curr_string, "%Y-%m-%d") {
-                    Ok(date) => potential_dates.push(date),
-                    _ => continue,
-                };
-            }
-        }
-        //if neither of the previous conditions fired, then the date was hopelessly ambiguous
-    }
-    //year not included
-    let date_regex_4 = Regex::new(&format!("{}{}{}", r"(\d{2})", sep_set, r"(\d{2})")).unwrap();
-    for cap in date_regex_4.captures_iter(time_string) {
-        //check if that date with the current year is in the past, and if it is then add one year
-        let current_year = chrono::Local::now().year();
-        let curr_string = format!("{}-{}-{}", current_year, &cap[1], &cap[2]);
-        match chrono::NaiveDate::parse_from_str(&curr_string, "%Y-%m-%d") {
-            Ok(date) if date >= chrono::Local::today().naive_local() => potential_dates.push(date),
-            Ok(date) => potential_dates.push(date + chrono::Duration::days(365)),
-            _ => continue,
-        };
-    }
-
-    //dates are extracted, attempt to extract times
-    let am_pm_regex = r"(a\.?m?\.?|p\.?m?\.?)";
-    let time_regex = Regex::new(&format!(r"{}:{}\s*{}", r"(\d{1}|\d{2})", r"(\d{2})", am_pm_regex)).unwrap();
-    for cap in time_regex.captures_iter(time_string) {
-        match &cap[1].parse::<i32>() {
-            Ok(hour) if *hour < 12 => {
-                //check if they specified am or pm
-                let hour = match &cap[3].chars().next() {
-                    Some('p') => *hour + 12,
-                    _ => *hour,
-                };
-                match chrono::NaiveTime::parse_from_str(&format!("{}:{}", hour, &cap[2]), "%H:%M") {
-                    Ok(time) => {
-                        potential_times.push(time);
-                    },
-                    Err(_) =>  {
-                        continue;
-                    }
-                }
-            },
-            _ => continue,
-        }
-    }
-    //try to extract a time that doesn't have the am/pm specifier
-    let time_regex = Regex::new(&format!(r"{}:{}", r"(\d{1}|\d{2})", r"(\d{2})")).unwrap();
-    for cap in time_regex.captures_iter(time_string) {
-        match chrono::NaiveTime::parse_from_str(&format!("{}:{}", &cap[1], &cap[2]), "%H:%M") {
-            Ok(time) => potential_times.push(time),
-            _ => continue,
-        }
-    }
-
-    let now = chrono::Local::now().naive_local();
-    if potential_dates.len() < 1 {
-        return None
-    }
-    if potential_times.len() < 1 {
-        potential_times.push(now.time());
-    }
-
-    //okay, now theoretically potential_dates and potential_times are filled up, and we simply need to find the first pair that is in the future
-    for date in &potential_dates {
-        for time in &potential_times {
-            let datetime = chrono::NaiveDateTime::new(date.clone(), time.clone());
-            if datetime > now {
-                return Some(datetime)
-            }
-        }
-    }
-    None
-}
-
-fn str_to_timescale(string: &str) -> Option<chrono::Duration> {
-    match string {
-        "millisecond" | "milliseconds" => Some(chrono::Duration::milliseconds(1)),
-        "second" | "seconds" | "sec" | "secs" => Some(chrono::Duration::seconds(1)),
-        "minute" | "minutes" | "min" | "mins" | "minaltatitatude" => Some(chrono::Duration::minutes(1)),
-        "hour" | "hours" => Some(chrono::Duration::hours(1)),
-        "day" | "days" => Some(chrono::Duration::days(1)),
-        "week" | "weeks" => Some(chrono::Duration::weeks(1)),
-        "month" | "months" => Some(chrono::Duration::weeks(1)*4),
-        "year" | "years" => Some(chrono::Duration::days(1)*365),
-        "decade" | "decades" => Some(chrono::Duration::days(1)*365*10),
-        "century" | "centuries" => Some(chrono::Duration::days(1)*365*100),
-        _ => None,
-    }
+use date_time_parser::DateParser;
+use date_time_parser::TimeParser;
+use std::sync::OnceLock;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone};
+use regex::Regex;
+
+//nothing gets scheduled further ahead than this
+pub const FURTHEST_DAYS: i64 = 1000 * 365;
+
+//allowed separators between the parts of a date
+const SEP_SET: &str = r"[/\- \._\\]?";
+
+//built once, everything here runs on every !remind
+struct Patterns {
+    offset: Regex,
+    next: Regex,
+    //yyyy mm dd
+    date_1: Regex,
+    //mm dd yyyy
+    date_2: Regex,
+    //yy mm dd or mm dd yy
+    date_3: Regex,
+    //mm dd
+    date_4: Regex,
+    time_am_pm: Regex,
+    time: Regex,
+    //what date_time_parser looks for, copied from it so anything it would unwrap can be taken out first
+    parser_slash_date: Regex,
+    parser_slash_date_year: Regex,
+    parser_month_date: Regex,
+    parser_in_months: Regex,
+    parser_relative_month: Regex,
+    parser_time: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let am_pm_regex = r"(a\.?m?\.?|p\.?m?\.?)";
+        Patterns {
+            offset: Regex::new(r"(\d+)\s?(\S+)").unwrap(),
+            next: Regex::new(r"next\s(\S+)").unwrap(),
+            date_1: Regex::new(&format!("{}{}{}{}{}", r"(\d{4})", SEP_SET, r"(\d{2})", SEP_SET, r"(\d{2})")).unwrap(),
+            date_2: Regex::new(&format!("{}{}{}{}{}", r"(\d{2})", SEP_SET, r"(\d{2})", SEP_SET, r"(\d{4})")).unwrap(),
+            date_3: Regex::new(&format!("{}{}{}{}{}", r"(\d{2})", SEP_SET, r"(\d{2})", SEP_SET, r"(\d{2})")).unwrap(),
+            date_4: Regex::new(&format!("{}{}{}", r"(\d{2})", SEP_SET, r"(\d{2})")).unwrap(),
+            time_am_pm: Regex::new(&format!(r"{}:{}\s*{}", r"(\d{1}|\d{2})", r"(\d{2})", am_pm_regex)).unwrap(),
+            time: Regex::new(&format!(r"{}:{}", r"(\d{1}|\d{2})", r"(\d{2})")).unwrap(),
+            parser_slash_date: Regex::new(r"(\d{1,2})/(\d{1,2})").unwrap(),
+            parser_slash_date_year: Regex::new(r"(\d{1,2})/(\d{1,2})/(\d{4}|\d{2})").unwrap(),
+            parser_month_date: Regex::new(r"(?i)(jan|january|feb|mar|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)(r?uary|ch|il|e|y|ust|tember|ober|ember|\b)\s(\d{1,2})?").unwrap(),
+            parser_in_months: Regex::new(r"in\s(\d{1,3})\smonths?").unwrap(),
+            parser_relative_month: Regex::new(r"(?i)(next|last|this)\smonth").unwrap(),
+            parser_time: Regex::new(r"(?i)(^|\b)(\d{1,2}):?(\d{2})?([ap]m?)?($|\b)").unwrap(),
+        }
+    })
+}
+
+//the first time in the string that's after now, as a wall clock time in now's timezone
+pub fn find_time<Tz: TimeZone>(time_string: &str, now: &DateTime<Tz>) -> Option<NaiveDateTime> {
+    let time_string = time_string.to_lowercase();
+    let found = match get_exact_datetime(&time_string, now) {
+        Some(datetime) => Some(datetime),
+        None => {
+            match get_offset_time(&time_string, now) {
+                Some(datetime) => Some(datetime),
+                None => {
+                    get_fuzzy_time(&time_string, now)
+                }
+            }
+        }
+    };
+    found.map(|datetime| datetime.naive_local())
+}
+
+//in the future, but not absurdly far in it
+fn in_range<Tz: TimeZone>(datetime: &DateTime<Tz>, now: &DateTime<Tz>) -> bool {
+    match now.clone().checked_add_signed(Duration::days(FURTHEST_DAYS)) {
+        Some(furthest) => datetime > now && *datetime <= furthest,
+        None => false,
+    }
+}
+
+//a wall clock time in tz. in the hour skipped when the clocks go forward it's read as if they hadn't yet (2:30 becomes 3:30),
+//in the hour that repeats when they go back it's the first one
+fn local<Tz: TimeZone>(tz: &Tz, naive: NaiveDateTime) -> Option<DateTime<Tz>> {
+    if let Some(datetime) = tz.from_local_datetime(&naive).earliest() {
+        return Some(datetime)
+    }
+    let before = tz.offset_from_local_datetime(&naive.checked_sub_signed(Duration::hours(3))?).earliest()?.fix();
+    Some(tz.from_utc_datetime(&naive.checked_sub_signed(Duration::seconds(before.local_minus_utc() as i64))?))
+}
+
+//returns a time in the future based on a 'natural' string
+fn get_fuzzy_time<Tz: TimeZone>(string_time: &str, now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+    let today = now.naive_local();
+    //parse_fuzzy shouldn't panic, but only as long as the copied regexes match the crate's. if they've drifted apart
+    //this reminder goes unparsed instead of the command failing
+    let (date, time) = match std::panic::catch_unwind(|| parse_fuzzy(string_time, today)) {
+        Ok(parsed) => parsed?,
+        Err(_) => {
+            tracing::error!(text = string_time, "date_time_parser panicked, the patterns copied from it in scheduler.rs need updating");
+            return None
+        },
+    };
+    if let (None, None) = (date, time) {
+        return None
+    }
+    //at least one of date/time was successfully parsed
+    let time = time.unwrap_or(chrono::NaiveTime::from_hms(0, 0, 0));
+    let date = date.unwrap_or(now.naive_local().date());
+
+    //DILEMMA
+    //at this point, we could have something like '7:30'
+    //if time did not have an am/pm specifier, then it could potentially be in the past, but that might not be what they meant
+    //we can maybe resolve this by checking if the currently parsed date is in the past, 
+    //  and if the time component is before noon, try adding 12 hours and see if that puts us in the future
+    let mut datetime = chrono::NaiveDateTime::new(date, time);
+    if datetime < now.naive_local() && time < chrono::NaiveTime::from_hms(12, 0, 0) {
+        datetime = datetime.checked_add_signed(chrono::Duration::hours(12))?;
+    }
+
+    //make sure the date is in the future
+    let datetime = local(&now.timezone(), datetime)?;
+    if in_range(&datetime, now) {Some(datetime)}
+    else {None}
+}
+
+//what date_time_parser makes of text once everything it would panic on is taken out, None if today is too close to the
+//end of time for it. the tests call this directly, so a panic find_time would have caught fails them instead
+pub fn parse_fuzzy(text: &str, today: NaiveDateTime) -> Option<(Option<NaiveDate>, Option<NaiveTime>)> {
+    let date = DateParser::parse_relative(&parser_date_text(text, today.date())?, today.date());
+    let time = TimeParser::parse_relative(&parser_time_text(text), today.time());
+    Some((date, time))
+}
+
+//date_time_parser unwraps whatever date it finds, so a 21st month ("2021/03/14" reads as 21/03/14), a 31st of june or
+//"next month" on the 31st would panic. those are blanked out until everything it could pick is a real date
+fn parser_date_text(text: &str, today: NaiveDate) -> Option<String> {
+    //"in 999 days" has to fit too
+    today.checked_add_signed(Duration::days(1000))?;
+    let patterns = patterns();
+    //today's day in another month of this year, which is what "in 2 months" and "next month" become
+    let month_away = |month: i32| if month < 1 {None} else {NaiveDate::from_ymd_opt(today.year(), month as u32, today.day())};
+    let mut text = ascii_digits(text);
+    loop {
+        let with_year = patterns.parser_slash_date_year.captures_iter(&text)
+            .find(|cap| NaiveDate::from_ymd_opt(number(&cap[3]), number(&cap[1]), number(&cap[2])).is_none());
+        let without_year = patterns.parser_slash_date.captures_iter(&text)
+            .find(|cap| NaiveDate::from_ymd_opt(today.year(), number(&cap[1]), number(&cap[2])).is_none());
+        let month_date = patterns.parser_month_date.captures_iter(&text)
+            .find(|cap| cap.get(3).is_some_and(|day| NaiveDate::from_ymd_opt(today.year(), month_number(&cap[1]), number(day.as_str())).is_none()));
+        let in_months = patterns.parser_in_months.captures_iter(&text)
+            .find(|cap| month_away(today.month() as i32 + number::<i32>(&cap[1])).is_none());
+        let relative_month = patterns.parser_relative_month.captures_iter(&text)
+            .find(|cap| month_away(today.month() as i32 + match cap[1].to_lowercase().as_str() {"next" => 1, "last" => -1, _ => 0}).is_none());
+        let bad = match with_year.or(without_year).or(month_date).or(in_months).or(relative_month) {
+            Some(cap) => cap.get(0).unwrap().range(),
+            None => return Some(text),
+        };
+        text.replace_range(bad, " ");
+    }
+}
+
+//same for times, which it also unwraps: 25:00, 7:99 or 13pm
+fn parser_time_text(text: &str) -> String {
+    let patterns = patterns();
+    let mut text = ascii_digits(text);
+    loop {
+        //it drops anything that looks like a date before looking, which can leave a new one behind
+        let without_dates = patterns.parser_slash_date.replace_all(&text, "").to_string();
+        if without_dates != text {
+            text = without_dates;
+            continue
+        }
+        let bad = patterns.parser_time.captures_iter(&text).find(|cap| {
+            let mut hour: u32 = number(&cap[2]);
+            let minute: u32 = cap.get(3).map_or(0, |minute| number(minute.as_str()));
+            match cap.get(4) {
+                Some(meridiem) if meridiem.as_str().to_lowercase().contains('p') && hour != 12 => hour += 12,
+                None if hour < 9 => hour += 12,
+                _ => (),
+            }
+            hour >= 24 || minute >= 60
+        });
+        match bad {
+            Some(cap) => {
+                let bad = cap.get(0).unwrap().range();
+                text.replace_range(bad, " ");
+            },
+            None => return text,
+        }
+    }
+}
+
+//its \d is a digit in any script, and only ascii ones can be parsed
+fn ascii_digits(text: &str) -> String {
+    text.chars().map(|c| if c.is_numeric() && !c.is_ascii_digit() {' '} else {c}).collect()
+}
+
+//a few digits the patterns already matched
+fn number<T: std::str::FromStr + Default>(digits: &str) -> T {
+    digits.parse().unwrap_or_default()
+}
+
+fn month_number(name: &str) -> u32 {
+    ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"].iter()
+        .position(|month| name.eq_ignore_ascii_case(month))
+        .map_or(0, |month| month as u32 + 1)
+}
+
+//in X <timescale>
+//X <timescale> from now
+//X <timescale>
+//next <timescale>
+fn get_offset_time<Tz: TimeZone>(time_string: &str, now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+
+    let mut potential_datetimes: Vec<DateTime<Tz>> = Vec::new();
+
+    for cap in patterns().offset.captures_iter(time_string) {
+        let offset = match cap[1].parse::<i32>() {
+            Ok(offset) => offset,
+            _ => continue,
+        };
+        let time_scale = match str_to_timescale(&cap[2]) {
+            Some(time_scale) => time_scale,
+            _ => continue,
+        };
+        potential_datetimes.extend(add_offset(now, time_scale, offset));
+    }
+
+    for cap in patterns().next.captures_iter(time_string) {
+        let time_scale = match str_to_timescale(&cap[1]) {
+            Some(time_scale) => time_scale,
+            _ => continue,
+        };
+        potential_datetimes.extend(add_offset(now, time_scale, 1));
+    }
+    potential_datetimes.retain(|element| in_range(element, now));
+    potential_datetimes.into_iter().next()
+}
+
+//"in 2 hours" is 2 real hours even if the clocks change, "in 2 days" is the same time on the clock 2 days later
+//None if it's too far off to work out, "2147483647 centuries" doesn't fit in a Duration
+fn add_offset<Tz: TimeZone>(now: &DateTime<Tz>, time_scale: Duration, count: i32) -> Option<DateTime<Tz>> {
+    let offset = time_scale.num_milliseconds().checked_mul(count as i64)?;
+    if offset > Duration::days(FURTHEST_DAYS).num_milliseconds() {
+        return None
+    }
+    let offset = Duration::milliseconds(offset);
+    if time_scale < Duration::days(1) {
+        return now.clone().checked_add_signed(offset)
+    }
+    local(&now.timezone(), now.naive_local().checked_add_signed(offset)?)
+}
+
+//for getting exact timestamps
+//date
+//YYYY_MM_DD
+//YY_MM_DD
+//MM_DD_YYYY
+//MM_DD_YY
+//MM_DD
+//time
+//HH:MM:SS
+//H:MM:SS
+fn get_exact_datetime<Tz: TimeZone>(time_string: &str, now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+    let century = now.year() / 100;
+    let mut potential_dates = Vec::new();
+    let mut potential_times = Vec::new();
+    //unambiguous yyyy mm dd
+    for cap in patterns().date_1.captures_iter(time_string) {
+        let curr_string = format!("{}-{}-{}", &cap[1], &cap[2], &cap[3]);
+        match chrono::NaiveDate::parse_from_str(&curr_string, "%Y-%m-%d") {
+            Ok(date) => potential_dates.push(date),
+            _ => continue,
+        };
+    }
+    //unambiguous  mm dd yyyy
+    for cap in patterns().date_2.captures_iter(time_string) {
+        let curr_string = format!("{}-{}-{}", &cap[3], &cap[1], &cap[2]);
+        match chrono::NaiveDate::parse_from_str(&curr_string, "%Y-%m-%d") {
+            Ok(date) => potential_dates.push(date),
+            _ => continue,
+        };
+    }
+    //ambiguous, could be yymmdd or mmddyy
+    for cap in patterns().date_3.captures_iter(time_string) {
+        //attempt to determine which one is the year (which ever one is greater than 12)
+        let potential_year_1 = match cap[1].parse::<i32>() {
+            Ok(year) => year,
+            _ => continue,
+        };
+        if potential_year_1 > 12 || potential_year_1 == 0 {
+            //if we are here, then the format is yymmdd
+            let curr_string = format!("{}{}-{}-{}", century, &cap[1], &cap[2], &cap[3]);
+            match chrono::NaiveDate::parse_from_str(&curr_string, "%Y-%m-%d") {
+                Ok(date) => potential_dates.push(date),
+                _ => continue,
+            };
+        }
+        else {
+            let potential_year_2 = match cap[2].parse::<i32>() {
+                Ok(year) => year,
+                _ => continue,
+            };
+            if potential_year_2 > 12 || potential_year_2 == 0 {
+                //if we are here, then the format is mmddyy
+                let curr_string = format!("{}{}-{}-{}", century, &cap[3], &cap[1], &cap[2]);
+                match chrono::NaiveDate::parse_from_str(&curr_string, "%Y-%m-%d") {
+                    Ok(date) => potential_dates.push(date),
+                    _ => continue,
+                };
+            }
+        }
+        //if neither of the previous conditions fired, then the date was hopelessly ambiguous
+    }
+    //year not included
+    for cap in patterns().date_4.captures_iter(time_string) {
+        //check if that date with the current year is in the past, and if it is then use next year's
+        let today = now.naive_local().date();
+        let (month, day) = match (cap[1].parse(), cap[2].parse()) {
+            (Ok(month), Ok(day)) => (month, day),
+            _ => continue,
+        };
+        //feb 29th is only in some years, so look until there is one
+        let date = (today.year()..today.year() + 8)
+            .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+            .find(|date| *date >= today);
+        if let Some(date) = date {
+            potential_dates.push(date);
+        }
+    }
+
+    //dates are extracted, attempt to extract times
+    for cap in patterns().time_am_pm.captures_iter(time_string) {
+        match &cap[1].parse::<i32>() {
+            Ok(hour) if *hour <= 12 => {
+                //check if they specified am or pm, 12am is midnight and 12pm is noon
+                let hour = match &cap[3].chars().next() {
+                    Some('p') => *hour % 12 + 12,
+                    _ => *hour % 12,
+                };
+                match chrono::NaiveTime::parse_from_str(&format!("{}:{}", hour, &cap[2]), "%H:%M") {
+                    Ok(time) => {
+                        potential_times.push(time);
+                    },
+                    Err(_) =>  {
+                        continue;
+                    }
+                }
+            },
+            _ => continue,
+        }
+    }
+    //try to extract a time that doesn't have the am/pm specifier
+    for cap in patterns().time.captures_iter(time_string) {
+        match chrono::NaiveTime::parse_from_str(&format!("{}:{}", &cap[1], &cap[2]), "%H:%M") {
+            Ok(time) => potential_times.push(time),
+            _ => continue,
+        }
+    }
+
+    if potential_dates.is_empty() {
+        return None
+    }
+    if potential_times.is_empty() {
+        potential_times.push(now.naive_local().time());
+    }
+
+    //okay, now theoretically potential_dates and potential_times are filled up, and we simply need to find the first pair that is in the future
+    for date in &potential_dates {
+        for time in &potential_times {
+            match local(&now.timezone(), chrono::NaiveDateTime::new(*date, *time)) {
+                Some(datetime) if in_range(&datetime, now) => return Some(datetime),
+                _ => continue,
+            }
+        }
+    }
+    None
+}
+
+fn str_to_timescale(string: &str) -> Option<chrono::Duration> {
+    match string {
+        "millisecond" | "milliseconds" => Some(chrono::Duration::milliseconds(1)),
+        "second" | "seconds" | "sec" | "secs" => Some(chrono::Duration::seconds(1)),
+        "minute" | "minutes" | "min" | "mins" | "minaltatitatude" => Some(chrono::Duration::minutes(1)),
+        "hour" | "hours" => Some(chrono::Duration::hours(1)),
+        "day" | "days" => Some(chrono::Duration::days(1)),
+        "week" | "weeks" => Some(chrono::Duration::weeks(1)),
+        "month" | "months" => Some(chrono::Duration::weeks(1)*4),
+        "year" | "years" => Some(chrono::Duration::days(1)*365),
+        "decade" | "decades" => Some(chrono::Duration::days(1)*365*10),
+        "century" | "centuries" => Some(chrono::Duration::days(1)*365*100),
+        _ => None,
+    }
 }
\ No newline at end of file
@@ -0,0 +1,175 @@
+use std::net::SocketAddr;
+use serde::{Deserialize, Serialize};
+
+pub const USAGE: &str = "usage: shawty_bot [options]
+    --config <file>       settings file (default shawty_bot.toml, if it's there)
+    --assets <dir>        activities, bonk templates and their images (default ./assets)
+    --data <dir>          where the bot keeps server settings, stats and reminders, made if it's missing (default ./assets)
+    --token-file <file>   the bot token, if DISCORD_TOKEN isn't set (default <data>/key)
+    --owner <id>          a bot owner, can be given more than once
+    --log-level <level>   error, warn, info, debug or trace (default info)
+    --log-format <format> text or json (default text)
+    --activity-minutes <n>  how often the bot's status changes (default 60)
+    --http <addr>         serve /metrics, /healthz and the admin api on this address, e.g. 127.0.0.1:9100 (off by default)
+    --help                show this";
+
+//read from the working directory when --config isn't given, it's fine for it not to be there
+pub const DEFAULT_CONFIG: &str = "shawty_bot.toml";
+pub const LOG_LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+
+//the settings file, and the flags once they're parsed. anything left out falls back to the default
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SettingsFile {
+    pub assets_dir: Option<String>,
+    pub data_dir: Option<String>,
+    pub token_file: Option<String>,
+    pub owners: Vec<u64>,
+    pub log_level: Option<String>,
+    pub log_format: Option<String>,
+    pub activity_minutes: Option<u64>,
+    pub http_addr: Option<String>,
+    pub admin_token: Option<String>,
+}
+
+//everything the bot needs to know before it connects
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub assets_dir: String,
+    pub data_dir: String,
+    pub token_file: String,
+    //empty means fall back to BOT_OWNERS/owners.json, see permissions::load_owners
+    pub owners: Vec<u64>,
+    pub log_level: String,
+    pub log_format: String,
+    pub activity_minutes: u64,
+    //None means no http server
+    pub http_addr: Option<SocketAddr>,
+    //what the admin api wants in the Authorization header, no token means no admin api
+    #[serde(skip)]
+    pub admin_token: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub config: Option<String>,
+    pub help: bool,
+    pub overrides: SettingsFile,
+}
+
+pub fn parse_args(args: &[String]) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            parsed.help = true;
+            continue
+        }
+        let mut value = || args.next().cloned().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--config" => parsed.config = Some(value()?),
+            "--assets" => parsed.overrides.assets_dir = Some(value()?),
+            "--data" => parsed.overrides.data_dir = Some(value()?),
+            "--token-file" => parsed.overrides.token_file = Some(value()?),
+            "--log-level" => parsed.overrides.log_level = Some(value()?),
+            "--log-format" => parsed.overrides.log_format = Some(value()?),
+            "--http" => parsed.overrides.http_addr = Some(value()?),
+            "--activity-minutes" => {
+                let minutes = value()?;
+                match minutes.parse() {
+                    Ok(minutes) => parsed.overrides.activity_minutes = Some(minutes),
+                    Err(_) => return Err(format!("--activity-minutes needs a number, not '{}'", minutes)),
+                }
+            },
+            "--owner" => {
+                let owner = value()?;
+                match owner.parse() {
+                    Ok(owner) => parsed.overrides.owners.push(owner),
+                    Err(_) => return Err(format!("--owner needs a user id, not '{}'", owner)),
+                }
+            },
+            _ => return Err(format!("unknown option '{}'\n{}", arg, USAGE)),
+        }
+    }
+    Ok(parsed)
+}
+
+pub fn parse_file(name: &str, contents: &str) -> Result<SettingsFile, String> {
+    toml::from_str(contents).map_err(|e| format!("{} isn't valid: {}", name, e))
+}
+
+//flags win over the file, owners from the flags replace the file's
+pub fn resolve(file: SettingsFile, flags: SettingsFile) -> Result<Settings, String> {
+    let data_dir = flags.data_dir.or(file.data_dir).unwrap_or_else(|| "./assets".to_string());
+    let log_level = flags.log_level.or(file.log_level).unwrap_or_else(|| "info".to_string()).to_lowercase();
+    if !LOG_LEVELS.contains(&log_level.as_str()) {
+        return Err(format!("log level has to be one of {}, not '{}'", LOG_LEVELS.join(", "), log_level))
+    }
+    let log_format = flags.log_format.or(file.log_format).unwrap_or_else(|| "text".to_string()).to_lowercase();
+    if !crate::logging::LOG_FORMATS.contains(&log_format.as_str()) {
+        return Err(format!("log format has to be one of {}, not '{}'", crate::logging::LOG_FORMATS.join(", "), log_format))
+    }
+    let activity_minutes = flags.activity_minutes.or(file.activity_minutes).unwrap_or(60);
+    if activity_minutes == 0 {
+        return Err("activity minutes has to be at least 1".to_string())
+    }
+    let http_addr = match flags.http_addr.or(file.http_addr) {
+        Some(addr) => match addr.parse() {
+            Ok(addr) => Some(addr),
+            Err(_) => return Err(format!("the http address has to look like 127.0.0.1:9100, not '{}'", addr)),
+        },
+        None => None,
+    };
+    Ok(Settings {
+        assets_dir: flags.assets_dir.or(file.assets_dir).unwrap_or_else(|| "./assets".to_string()),
+        token_file: flags.token_file.or(file.token_file).unwrap_or_else(|| format!("{}/key", data_dir)),
+        owners: if flags.owners.is_empty() {file.owners} else {flags.owners},
+        data_dir,
+        log_level,
+        log_format,
+        activity_minutes,
+        http_addr,
+        admin_token: flags.admin_token.or(file.admin_token).filter(|token| !token.trim().is_empty()),
+    })
+}
+
+//everything from the command line, None means --help was asked for
+pub fn load(args: &[String]) -> Result<Option<Settings>, String> {
+    let args = parse_args(args)?;
+    if args.help {
+        return Ok(None)
+    }
+    let file = match &args.config {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(contents) => parse_file(path, &contents)?,
+            Err(e) => return Err(format!("could not read {}: {}", path, e)),
+        },
+        None => match std::fs::read_to_string(DEFAULT_CONFIG) {
+            Ok(contents) => parse_file(DEFAULT_CONFIG, &contents)?,
+            Err(_) => SettingsFile::default(),
+        },
+    };
+    let mut flags = args.overrides;
+    //a secret doesn't belong in a flag where ps can see it
+    flags.admin_token = std::env::var("SHAWTY_ADMIN_TOKEN").ok();
+    let settings = resolve(file, flags)?;
+    if !std::path::Path::new(&settings.assets_dir).is_dir() {
+        return Err(format!("the assets folder {} doesn't exist (--assets to use another one)", settings.assets_dir))
+    }
+    if let Err(e) = std::fs::create_dir_all(&settings.data_dir) {
+        return Err(format!("could not make the data folder {}: {}", settings.data_dir, e))
+    }
+    Ok(Some(settings))
+}
+
+//DISCORD_TOKEN, or else the token file
+pub fn read_token(settings: &Settings) -> Result<String, String> {
+    if let Ok(token) = std::env::var("DISCORD_TOKEN") {
+        return Ok(token.trim().to_string())
+    }
+    match std::fs::read_to_string(&settings.token_file) {
+        Ok(token) if !token.trim().is_empty() => Ok(token.trim().to_string()),
+        Ok(_) => Err(format!("{} is empty, it should have the bot token in it", settings.token_file)),
+        Err(e) => Err(format!("no bot token: DISCORD_TOKEN isn't set and {} couldn't be read ({}), --token-file to use another file", settings.token_file, e)),
+    }
+}
@@ -0,0 +1,437 @@
+use serde_json::{json, Map, Value};
+use serenity::{
+    http::Http,
+    model::{
+        id::UserId,
+        interactions::{ApplicationCommandInteractionDataOption, Interaction, InteractionType},
+    },
+    prelude::*,
+};
+use crate::{checkem, config};
+use crate::bonker::ImageData;
+use crate::permissions::Role;
+use crate::command_error::CommandReply;
+use crate::invocation::{self, Invocation};
+use tracing::{error, info, warn, Instrument};
+
+//option types, from discord's docs
+const SUB_COMMAND: u8 = 1;
+const STRING: u8 = 3;
+const INTEGER: u8 = 4;
+const BOOLEAN: u8 = 5;
+const USER: u8 = 6;
+const CHANNEL: u8 = 7;
+
+//discord won't take more choices than this for one option
+const MAX_CHOICES: usize = 25;
+//flag for responses only the user who used the command can see
+const EPHEMERAL: u64 = 64;
+
+pub struct SlashOption {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub kind: u8,
+    pub required: bool,
+    //empty means anything can be typed
+    pub choices: Vec<String>,
+}
+
+pub struct SlashCommand {
+    pub name: &'static str,
+    pub description: &'static str,
+    //the prefix command this runs, subcommands get added on the end
+    pub command: &'static str,
+    //who can use it, same as the prefix version's check
+    pub role: Role,
+    pub options: Vec<SlashOption>,
+    //a command with subcommands can only be used through one of them
+    pub subcommands: Vec<(&'static str, &'static str, Vec<SlashOption>)>,
+}
+
+fn option(kind: u8, name: &'static str, description: &'static str, required: bool) -> SlashOption {
+    SlashOption {name, description, kind, required, choices: Vec::new()}
+}
+
+fn choice(name: &'static str, description: &'static str, required: bool, choices: Vec<String>) -> SlashOption {
+    SlashOption {name, description, kind: STRING, required, choices}
+}
+
+fn command(name: &'static str, description: &'static str, options: Vec<SlashOption>) -> SlashCommand {
+    SlashCommand {name, description, command: name, role: Role::User, options, subcommands: Vec::new()}
+}
+
+//discord shows every global command to everyone, the role check is what keeps them to the owners
+fn owner_command(name: &'static str, description: &'static str, options: Vec<SlashOption>) -> SlashCommand {
+    SlashCommand {role: Role::Owner, ..command(name, description, options)}
+}
+
+//what !activity add and set take: [kind [link]] text
+fn activity_options() -> Vec<SlashOption> {
+    let kinds = vec!["playing".to_string(), "listening".to_string(), "competing".to_string(), "streaming".to_string()];
+    vec![
+        choice("kind", "what the bot is doing, playing if not given", false, kinds),
+        option(STRING, "link", "the stream, needed for streaming", false),
+        option(STRING, "text", "what it says, can use {variables} like {bonk_count}", true),
+    ]
+}
+
+//every slash command, bonk_choices is what the template option offers
+pub fn commands(bonk_choices: Vec<String>) -> Vec<SlashCommand> {
+    let patterns: Vec<String> = checkem::ALL_PATTERNS.iter().map(|pattern| pattern.name().to_string()).collect();
+    let settings: Vec<String> = config::SETTINGS.iter().map(|(key, _)| key.to_string()).collect();
+    let features: Vec<String> = config::FEATURES.iter().map(|(feature, _)| feature.to_string()).collect();
+    let periods = vec!["week".to_string(), "month".to_string(), "all".to_string()];
+    vec![
+        command("mock", "repeat what someone says in spongebob text for a while", vec![
+            option(USER, "user", "who to mock", true),
+        ]),
+        command("flip", "flip a coin", Vec::new()),
+        command("help", "list the commands, or how to use one", vec![
+            choice("command", "which command", false, crate::MOCKER_GROUP.options.commands.iter().map(|command| command.options.names[0].to_string()).collect()),
+            option(INTEGER, "page", "which page of the list", false),
+        ]),
+        command("bonk", "bonk someone", vec![
+            option(USER, "user", "who to bonk, yourself if nobody", false),
+            option(STRING, "image", "link to an image or a custom emoji to bonk instead", false),
+            choice("template", "which bonk to use, by name or #tag", false, bonk_choices),
+        ]),
+        SlashCommand {
+            name: "bonkstats",
+            description: "bonk templates and who's been bonking who",
+            command: "bonk",
+            role: Role::User,
+            options: Vec::new(),
+            subcommands: vec![
+                ("list", "every bonk template", Vec::new()),
+                ("stats", "bonks given and received", vec![
+                    option(USER, "user", "whose stats, yours if nobody", false),
+                ]),
+                ("leaderboard", "the top bonkers", vec![
+                    choice("period", "how far back to count", false, periods),
+                    option(BOOLEAN, "chart", "include a bar chart", false),
+                ]),
+                ("top-templates", "the most used bonks", vec![
+                    option(BOOLEAN, "chart", "include a bar chart", false),
+                ]),
+            ],
+        },
+        command("remind", "get reminded about something", vec![
+            option(STRING, "when", "like 'in 3 days', 'next week', 2021/06/11 or 5:45pm", true),
+        ]),
+        command("gets", "check 'em leaderboard", vec![
+            option(USER, "user", "show this user's gets instead", false),
+        ]),
+        SlashCommand {
+            name: "checkem",
+            description: "change the check 'em reactions",
+            command: "checkem",
+            role: Role::Admin,
+            options: Vec::new(),
+            subcommands: vec![
+                ("list", "the reaction for every pattern", Vec::new()),
+                ("set", "use a different emoji for a pattern", vec![
+                    choice("pattern", "which pattern", true, patterns.clone()),
+                    option(STRING, "emoji", "the emoji, or none to not react", true),
+                ]),
+                ("reset", "go back to the default emoji", vec![
+                    choice("pattern", "which pattern, all of them if none", false, patterns),
+                ]),
+            ],
+        },
+        SlashCommand {
+            name: "config",
+            description: "change how the bot behaves in this server",
+            command: "config",
+            role: Role::Admin,
+            options: Vec::new(),
+            subcommands: vec![
+                ("list", "every setting and its value", Vec::new()),
+                ("get", "one setting's value", vec![
+                    choice("setting", "which setting", true, settings.clone()),
+                ]),
+                ("set", "change a setting", vec![
+                    choice("setting", "which setting", true, settings.clone()),
+                    option(STRING, "value", "the new value", true),
+                ]),
+                ("reset", "put settings back to their defaults", vec![
+                    choice("setting", "which setting, everything if none", false, settings),
+                ]),
+            ],
+        },
+        SlashCommand {
+            name: "features",
+            description: "turn commands and features on or off",
+            command: "features",
+            role: Role::Moderator,
+            options: Vec::new(),
+            subcommands: vec![
+                ("list", "what's on and off", Vec::new()),
+                ("enable", "turn a feature on", vec![
+                    choice("feature", "which feature", true, features.clone()),
+                    option(CHANNEL, "channel", "only in this channel", false),
+                ]),
+                ("disable", "turn a feature off", vec![
+                    choice("feature", "which feature", true, features),
+                    option(CHANNEL, "channel", "only in this channel", false),
+                ]),
+            ],
+        },
+        SlashCommand {
+            role: Role::Admin,
+            ..command("prefix", "see or change the prefix for typed commands", vec![
+                option(STRING, "prefix", "the new prefix", false),
+            ])
+        },
+        owner_command("reload-assets", "re-read the assets, settings, stats and gets (bot owners only)", Vec::new()),
+        owner_command("shutdown", "log the bot out (bot owners only)", Vec::new()),
+        owner_command("guilds", "every server the bot is in (bot owners only)", Vec::new()),
+        owner_command("say", "say something as the bot (bot owners only)", vec![
+            option(CHANNEL, "channel", "where to say it, here if not given", false),
+            option(STRING, "message", "what to say", true),
+        ]),
+        SlashCommand {
+            subcommands: vec![
+                ("list", "every status, numbered, and the pinned one", Vec::new()),
+                ("add", "add a status to the rotation", activity_options()),
+                ("remove", "take a status out of the rotation", vec![
+                    option(INTEGER, "number", "its number in the list", true),
+                ]),
+                ("set", "show this status until it's unpinned, without adding it to the rotation", activity_options()),
+                ("pin", "show one of the statuses until it's unpinned", vec![
+                    option(STRING, "which", "its number in the list, or off to go back to rotating", true),
+                ]),
+            ],
+            ..owner_command("activity", "the statuses the bot rotates through (bot owners only)", Vec::new())
+        },
+    ]
+}
+
+//template names and #tags
+fn bonk_choices(templates: &[ImageData]) -> Vec<String> {
+    let mut choices: Vec<String> = templates.iter().map(|template| template.short_name().to_string()).collect();
+    for tag in templates.iter().flat_map(|template| template.tags.iter()) {
+        let tag = format!("#{}", tag);
+        if !choices.contains(&tag) {
+            choices.push(tag);
+        }
+    }
+    choices
+}
+
+fn option_json(option: &SlashOption) -> Value {
+    let mut json = json!({
+        "type": option.kind,
+        "name": option.name,
+        "description": option.description,
+        "required": option.required,
+    });
+    //past discord's limit it's left for anything to be typed, the command checks what it gets either way.
+    //suggesting as you type would be better, but serenity 0.10 can't receive autocomplete interactions
+    if option.choices.len() > MAX_CHOICES {
+        warn!(option = option.name, choices = option.choices.len(), limit = MAX_CHOICES, "too many choices for discord, leaving it free text");
+    }
+    else if !option.choices.is_empty() {
+        json["choices"] = option.choices.iter().map(|choice| json!({"name": choice, "value": choice})).collect();
+    }
+    json
+}
+
+pub fn command_json(command: &SlashCommand) -> Value {
+    let options: Vec<Value> = if command.subcommands.is_empty() {
+        command.options.iter().map(option_json).collect()
+    }
+    else {
+        command.subcommands.iter().map(|(name, description, options)| json!({
+            "type": SUB_COMMAND,
+            "name": name,
+            "description": description,
+            "options": options.iter().map(option_json).collect::<Vec<_>>(),
+        })).collect()
+    };
+    json!({
+        "name": command.name,
+        "description": command.description,
+        "options": options,
+    })
+}
+
+//creates (or updates) every slash command and removes ones that aren't around anymore, returns the application id
+pub async fn register(http: &Http, bonks: &[ImageData]) -> Option<u64> {
+    let application = match http.get_current_application_info().await {
+        Ok(info) => *info.id.as_u64(),
+        Err(e) => {
+            error!(error = %crate::logging::chain(&e), "couldn't get application info, no slash commands");
+            return None
+        }
+    };
+    let commands = commands(bonk_choices(bonks));
+    for command in &commands {
+        if let Err(e) = http.create_global_application_command(application, &command_json(command)).await {
+            error!(command = command.name, error = %crate::logging::chain(&e), "could not register slash command");
+        }
+    }
+    match http.get_global_application_commands(application).await {
+        Ok(registered) => {
+            for old in registered.iter().filter(|old| !commands.iter().any(|command| command.name == old.name)) {
+                info!(command = %old.name, "removing old slash command");
+                if let Err(e) = http.delete_global_application_command(application, *old.id.as_u64()).await {
+                    error!(command = %old.name, error = %crate::logging::chain(&e), "could not remove slash command");
+                }
+            }
+        },
+        Err(e) => error!(error = %crate::logging::chain(&e), "could not get registered slash commands"),
+    }
+    Some(application)
+}
+
+//turns the options into args in the order they're declared, so they look like what would be typed
+//users also go in users, and true booleans become their name (like typing chart)
+pub fn flatten_options(declared: &[SlashOption], given: &[ApplicationCommandInteractionDataOption]) -> (Vec<String>, Vec<u64>) {
+    let mut args = Vec::new();
+    let mut users = Vec::new();
+    for option in declared {
+        let value = match given.iter().find(|given| given.name == option.name).and_then(|given| given.value.as_ref()) {
+            Some(value) => value,
+            None => continue,
+        };
+        match (option.kind, value) {
+            (USER, Value::String(id)) => {
+                if let Ok(id) = id.parse() {
+                    users.push(id);
+                    args.push(format!("<@{}>", id));
+                }
+            },
+            (CHANNEL, Value::String(id)) => args.push(format!("<#{}>", id)),
+            (BOOLEAN, Value::Bool(true)) => args.push(option.name.to_string()),
+            (BOOLEAN, _) => {},
+            (_, Value::String(string)) => args.push(string.clone()),
+            (_, value) => args.push(value.to_string()),
+        }
+    }
+    (args, users)
+}
+
+pub async fn handle(ctx: &Context, application: u64, interaction: Interaction) {
+    let span = tracing::info_span!("slash_command",
+        guild = interaction.guild_id.as_u64(),
+        channel = interaction.channel_id.as_u64(),
+        user = interaction.member.user.id.as_u64(),
+        command = interaction.data.as_ref().map(|data| data.name.as_str()).unwrap_or_default(),
+    );
+    handle_traced(ctx, application, interaction).instrument(span).await
+}
+
+async fn handle_traced(ctx: &Context, application: u64, interaction: Interaction) {
+    if interaction.kind != InteractionType::ApplicationCommand {
+        return
+    }
+    let data = match &interaction.data {
+        Some(data) => data,
+        None => return,
+    };
+    let commands = commands(Vec::new());
+    let command = match commands.iter().find(|command| command.name == data.name) {
+        Some(command) => command,
+        None => {
+            warn!(command = %data.name, "got unknown slash command");
+            return
+        }
+    };
+    //discord wants an answer within 3 seconds and bonks can take longer, so say we're thinking first
+    if let Err(e) = ctx.http.create_interaction_response(*interaction.id.as_u64(), &interaction.token, &json!({"type": 5})).await {
+        error!(error = %crate::logging::chain(&e), "could not respond to slash command");
+        return
+    }
+    let (name, declared, given) = match data.options.first() {
+        Some(subcommand) if !command.subcommands.is_empty() => {
+            match command.subcommands.iter().find(|(name, _, _)| *name == subcommand.name) {
+                Some((name, _, declared)) => (format!("{} {}", command.command, name), declared, &subcommand.options),
+                None => (format!("{} {}", command.command, subcommand.name), &command.options, &subcommand.options),
+            }
+        },
+        _ => (command.command.to_string(), &command.options, &data.options),
+    };
+    let (args, user_ids) = flatten_options(declared, given);
+    let mut users = Vec::new();
+    for id in user_ids {
+        match UserId(id).to_user(ctx).await {
+            Ok(user) => users.push(user),
+            Err(e) => warn!(user = id, error = %crate::logging::chain(&e), "could not get user"),
+        }
+    }
+    let invocation = Invocation {
+        guild: Some(*interaction.guild_id.as_u64()),
+        channel: *interaction.channel_id.as_u64(),
+        author: interaction.member.user.clone(),
+        users,
+        args,
+        attachments: Vec::new(),
+        replied_to: None,
+    };
+    info!(command = %name, args = ?invocation.args, "slash command");
+    let reply = if !crate::guild_config(ctx, invocation.guild).await.feature_enabled(command.command, invocation.channel) {
+        Err(format!("/{} is turned off here", command.name).into())
+    }
+    else if crate::member_role(ctx, invocation.guild, *invocation.author.id.as_u64()).await < command.role {
+        Err(format!("you need to be a {} for that", command.role).into())
+    }
+    else {
+        crate::run_command(ctx, &name, &invocation).await
+    };
+    crate::bot_metrics(ctx).await.command(&name, reply.as_ref().err().map_or("ok", |e| e.kind()));
+    if let Err(e) = &reply {
+        crate::report_error(ctx, &format!("/{}", name), e).await;
+    }
+    respond(ctx, application, &interaction, &invocation, reply).await;
+}
+
+async fn respond(ctx: &Context, application: u64, interaction: &Interaction, invocation: &Invocation, reply: CommandReply) {
+    let reply = match reply {
+        Ok(reply) if !reply.ephemeral => reply,
+        Ok(reply) => {
+            invocation::discard(&reply);
+            return respond_privately(ctx, application, interaction, reply.content).await
+        },
+        Err(e) => return respond_privately(ctx, application, interaction, format!("{} {}", e.reaction(), e.user_message())).await,
+    };
+    if reply.file.is_some() {
+        //interaction responses can't carry files yet, so it goes in the channel like a prefix command's reply would
+        if let Err(e) = ctx.http.delete_original_interaction_response(application, &interaction.token).await {
+            error!(error = %crate::logging::chain(&e), "could not remove slash command response");
+        }
+        invocation::send_reply(crate::chat(ctx).await.as_ref(), *interaction.channel_id.as_u64(), None, reply).await;
+        return
+    }
+    let mut response = Map::new();
+    if !reply.content.is_empty() {
+        response.insert("content".to_string(), json!(reply.content));
+    }
+    if let Some(embed) = &reply.embed {
+        let mut embed_json = json!({"title": embed.title, "description": embed.description});
+        if let Some(url) = &embed.thumbnail {
+            embed_json["thumbnail"] = json!({"url": url});
+        }
+        response.insert("embeds".to_string(), json!([embed_json]));
+    }
+    match ctx.http.edit_original_interaction_response(application, &interaction.token, &Value::Object(response)).await {
+        Ok(sent) => {
+            if let Some(date_time) = reply.reminder {
+                crate::schedule_reminder(ctx, date_time, sent.clone(), sent, *invocation.author.id.as_u64()).await;
+            }
+        },
+        Err(e) => error!(error = %crate::logging::chain(&e), "could not respond to slash command"),
+    }
+}
+
+//the "thinking" response can't be made private after the fact, so it's swapped for a private followup
+async fn respond_privately(ctx: &Context, application: u64, interaction: &Interaction, content: String) {
+    let mut followup = Map::new();
+    followup.insert("content".to_string(), json!(content));
+    followup.insert("flags".to_string(), json!(EPHEMERAL));
+    if let Err(e) = ctx.http.create_followup_message(application, &interaction.token, false, &followup).await {
+        error!(error = %crate::logging::chain(&e), "could not send private response");
+    }
+    if let Err(e) = ctx.http.delete_original_interaction_response(application, &interaction.token).await {
+        error!(error = %crate::logging::chain(&e), "could not remove slash command response");
+    }
+}
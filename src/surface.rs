@@ -0,0 +1,287 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use serenity::{
+    async_trait,
+    cache::Cache,
+    http::{request::RequestBuilder, routing::RouteInfo, Http},
+    model::{channel::{Message, ReactionType}, id::{ChannelId, MessageId, UserId}},
+};
+use serde_json::Value;
+use crate::command_error::CommandError;
+use crate::invocation::Reply;
+
+//anything bigger than this isn't downloaded, avatars and the gallery api are well under it
+pub const MAX_FETCH_BYTES: usize = 8 * 1024 * 1024;
+const MAX_REDIRECTS: usize = 3;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+
+//a downloaded page or image
+#[derive(Debug, Clone)]
+pub struct Fetched {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+//everything the bot says to discord or asks the web for, so the commands and message handling can run against a fake in the tests
+#[async_trait]
+pub trait ChatSurface: Send + Sync {
+    //the bot's own user id
+    async fn current_user(&self) -> Result<u64, CommandError>;
+    //posts reply in the channel, as a reply to reference if there is one
+    async fn send(&self, channel: u64, reference: Option<&Message>, reply: &Reply) -> Result<Message, CommandError>;
+    async fn react(&self, channel: u64, message: u64, reaction: ReactionType) -> Result<(), CommandError>;
+    //everyone who reacted with reaction, the bot included
+    async fn reaction_users(&self, channel: u64, message: u64, reaction: ReactionType) -> Result<Vec<u64>, CommandError>;
+    async fn dm(&self, user: u64, text: &str) -> Result<(), CommandError>;
+    //false if the channel can't be looked up
+    async fn is_nsfw(&self, channel: u64) -> bool;
+    //the avatar the user set for this server, if they have one
+    async fn member_avatar(&self, guild: u64, user: u64) -> Option<String>;
+    async fn fetch(&self, url: &str) -> Result<Fetched, CommandError>;
+}
+
+//the real thing
+pub struct Discord {
+    cache: Arc<Cache>,
+    http: Arc<Http>,
+    //redirects are followed by hand so every hop gets checked
+    web: reqwest::Client,
+}
+
+impl Discord {
+    pub fn new(cache: Arc<Cache>, http: Arc<Http>) -> Discord {
+        let web = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(FETCH_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Discord {cache, http, web}
+    }
+}
+
+//false for anywhere a link shouldn't be able to reach: the bot's own machine, the local network, cloud metadata...
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => {
+            //ipv4 wrapped up in ipv6 goes to the same place as the ipv4 address
+            if let Some(ip) = ip.to_ipv4() {
+                return is_public_v4(ip)
+            }
+            //nat64 points at an ipv4 address too
+            let segments = ip.segments();
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                return is_public_v4(Ipv4Addr::from(u128::from(ip) as u32))
+            }
+            !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast()
+                //unique local fc00::/7, link local fe80::/10, the old site local fec0::/10, documentation 2001:db8::/32
+                || segments[0] & 0xfe00 == 0xfc00 || segments[0] & 0xffc0 == 0xfe80 || segments[0] & 0xffc0 == 0xfec0
+                || (segments[0] == 0x2001 && segments[1] == 0xdb8))
+        }
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast()
+        || ip.is_multicast() || ip.is_documentation()
+        //"this network", carrier grade nat, ietf protocol assignments, benchmarking, reserved
+        || a == 0 || (a == 100 && b & 0xc0 == 64) || (a == 192 && b == 0 && c == 0) || (a == 198 && b & 0xfe == 18) || a >= 240)
+}
+
+//only http(s) links to public addresses get fetched. the name is looked up here, and what was actually connected to gets checked again after
+pub async fn check_url(url: &str) -> Result<reqwest::Url, CommandError> {
+    let url = match reqwest::Url::parse(url) {
+        Ok(url) => url,
+        Err(_) => return Err(CommandError::User(format!("{} isn't a link", url))),
+    };
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(CommandError::User(format!("can only fetch http and https links, not {}", url.scheme())))
+    }
+    let host = match url.host_str() {
+        Some(host) => host.trim_start_matches('[').trim_end_matches(']').to_string(),
+        None => return Err(CommandError::User(format!("{} doesn't say where to fetch from", url))),
+    };
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addresses: Vec<SocketAddr> = match tokio::net::lookup_host((host.as_str(), port)).await {
+        Ok(addresses) => addresses.collect(),
+        Err(e) => return Err(transient(&format!("could not look up {}", host), &e)),
+    };
+    if addresses.is_empty() {
+        return Err(CommandError::User(format!("could not find {}", host)))
+    }
+    if addresses.iter().any(|address| !is_public(address.ip())) {
+        return Err(CommandError::User(format!("won't fetch from {}, it's a local or private address", host)))
+    }
+    Ok(url)
+}
+
+//reads the body a piece at a time and gives up once it's too big, rather than finding out after it's all in memory
+pub async fn read_capped(mut response: reqwest::Response, cap: usize) -> Result<Vec<u8>, CommandError> {
+    let too_big = || CommandError::User(format!("that's too big, the limit is {}MB", cap / (1024 * 1024)));
+    if response.content_length().is_some_and(|length| length > cap as u64) {
+        return Err(too_big())
+    }
+    let mut body = Vec::new();
+    loop {
+        match response.chunk().await {
+            Ok(Some(chunk)) => {
+                if body.len() + chunk.len() > cap {
+                    return Err(too_big())
+                }
+                body.extend_from_slice(&chunk);
+            },
+            Ok(None) => return Ok(body),
+            Err(e) => return Err(transient("could not get the response body", &e)),
+        }
+    }
+}
+
+//discord not answering is worth trying again
+fn transient(what: &str, e: &dyn std::error::Error) -> CommandError {
+    CommandError::Transient(format!("{}: {}", what, crate::logging::chain(e)))
+}
+
+#[async_trait]
+impl ChatSurface for Discord {
+    async fn current_user(&self) -> Result<u64, CommandError> {
+        match self.http.get_current_user().await {
+            Ok(user) => Ok(*user.id.as_u64()),
+            Err(e) => Err(transient("couldn't get current user", &e)),
+        }
+    }
+
+    async fn send(&self, channel: u64, reference: Option<&Message>, reply: &Reply) -> Result<Message, CommandError> {
+        let sent = ChannelId(channel).send_message(&self.http, |m| {
+            if let Some(reference) = reference {
+                m.reference_message(reference);
+            }
+            if !reply.content.is_empty() {
+                m.content(&reply.content);
+            }
+            if let Some(embed) = &reply.embed {
+                m.embed(|e| {
+                    e.title(&embed.title);
+                    e.description(&embed.description);
+                    if let Some(url) = &embed.thumbnail {
+                        e.thumbnail(url);
+                    }
+                    if let Some(name) = &embed.author {
+                        e.author(|a| {
+                            a.name(name);
+                            if let Some(url) = &embed.author_icon {
+                                a.icon_url(url);
+                            }
+                            a
+                        });
+                    }
+                    if let Some(timestamp) = &embed.timestamp {
+                        e.timestamp(timestamp.as_str());
+                    }
+                    //attachments go by their name, not where they were on disk
+                    if let Some(name) = reply.file.as_deref().and_then(|file| Path::new(file).file_name()) {
+                        e.image(format!("attachment://{}", name.to_string_lossy()));
+                    }
+                    e
+                });
+            }
+            if let Some(file) = &reply.file {
+                m.add_file(file.as_str());
+            }
+            m
+        }).await;
+        sent.map_err(|e| transient("could not send message", &e))
+    }
+
+    async fn react(&self, channel: u64, message: u64, reaction: ReactionType) -> Result<(), CommandError> {
+        match self.http.create_reaction(channel, message, &reaction).await {
+            Ok(()) => Ok(()),
+            Err(e) => Err(transient("could not react to message", &e)),
+        }
+    }
+
+    async fn reaction_users(&self, channel: u64, message: u64, reaction: ReactionType) -> Result<Vec<u64>, CommandError> {
+        match ChannelId(channel).reaction_users(&self.http, MessageId(message), reaction, None, None).await {
+            Ok(users) => Ok(users.iter().map(|user| *user.id.as_u64()).collect()),
+            Err(e) => Err(transient("could not get who reacted", &e)),
+        }
+    }
+
+    async fn dm(&self, user: u64, text: &str) -> Result<(), CommandError> {
+        let sent = match UserId(user).create_dm_channel(&self.http).await {
+            Ok(channel) => channel.say(&self.http, text).await.map(|_| ()),
+            Err(e) => Err(e),
+        };
+        sent.map_err(|e| transient("could not DM", &e))
+    }
+
+    async fn is_nsfw(&self, channel: u64) -> bool {
+        match ChannelId(channel).to_channel((&self.cache, self.http.as_ref())).await {
+            Ok(channel) => channel.is_nsfw(),
+            Err(_) => false,
+        }
+    }
+
+    //serenity's Member doesn't know about per-server avatars yet, so read it out of the raw member object
+    async fn member_avatar(&self, guild_id: u64, user_id: u64) -> Option<String> {
+        let request = RequestBuilder::new(RouteInfo::GetMember {guild_id, user_id}).build();
+        let member = match self.http.request(request).await {
+            Ok(response) => match response.json::<Value>().await {
+                Ok(member) => member,
+                Err(e) => {
+                    tracing::warn!(error = %crate::logging::chain(&e), "could not parse member");
+                    return None
+                }
+            },
+            Err(e) => {
+                tracing::warn!(error = %crate::logging::chain(&e), "could not fetch member");
+                return None
+            }
+        };
+        match member.get("avatar") {
+            Some(Value::String(hash)) => Some(format!("https://cdn.discordapp.com/guilds/{}/users/{}/avatars/{}.png?size=1024", guild_id, user_id, hash)),
+            _ => None,
+        }
+    }
+
+    async fn fetch(&self, url: &str) -> Result<Fetched, CommandError> {
+        let mut url = check_url(url).await?;
+        let mut redirects = 0;
+        let response = loop {
+            let response = match self.web.get(url.clone()).send().await {
+                Ok(response) => response,
+                Err(e) => return Err(transient("could not fetch", &e)),
+            };
+            //the name could have been pointed somewhere else since it was checked
+            if let Some(address) = response.remote_addr() {
+                if !is_public(address.ip()) {
+                    return Err(CommandError::User("won't fetch from there, it's a local or private address".to_string()))
+                }
+            }
+            let location = response.headers().get(reqwest::header::LOCATION).and_then(|location| location.to_str().ok());
+            match location {
+                Some(location) if response.status().is_redirection() => {
+                    redirects += 1;
+                    if redirects > MAX_REDIRECTS {
+                        return Err(CommandError::User("that link redirects too many times".to_string()))
+                    }
+                    let next = match url.join(location) {
+                        Ok(next) => next,
+                        Err(_) => return Err(CommandError::User("that link redirects somewhere that isn't a link".to_string())),
+                    };
+                    url = check_url(next.as_str()).await?;
+                },
+                _ => break response,
+            }
+        };
+        let status = response.status().as_u16();
+        let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .map(|content_type| content_type.to_string());
+        let body = read_capped(response, MAX_FETCH_BYTES).await?;
+        Ok(Fetched {status, content_type, body})
+    }
+}
@@ -1,40 +1,1647 @@
-use super::mock_string;
-use super::overlay_bonk;
-use super::ImageData;
-
-use std::env;
-use std::fs::File;
-use std::io::prelude::*;
-use image::imageops;
-#[test]
-fn test_mock() {
-    let test_string = "mock this bitch";
-    let mocked_string = mock_string(test_string);
-    println!("{}", mocked_string);
-    assert_eq!(test_string.len(), mocked_string.len());
-}
-
-#[test]
-fn test_bonk() {
-    /*
-    let token = match env::var("DISCORD_TOKEN") {
-        Ok(tok) => tok,
-        Err(_) => {
-            let mut content = String::new();
-            File::open("assets/key").expect("could not find env DISCORD_TOKEN or file containing bot key")
-                .read_to_string(&mut content).expect("could not read contents of assets/key");
-            content
-        },
-    };
-
-    let mut client = Client::new(&token, Handler).expect("Error creating client");
-    client.start().unwrap();
-    */
-    let test_avatar = image::io::Reader::open("assets/test/test.png").unwrap()
-        .decode().unwrap();
-    let meta_data: Vec<ImageData> = serde_json::from_str(&std::fs::read_to_string("assets/bonk_locations.json").unwrap()).unwrap();
-    for meta in meta_data {
-        let bonked_avatar = overlay_bonk(test_avatar.clone(), &meta).unwrap();
-        bonked_avatar.save_with_format(format!("assets/test/{}", meta.name), image::ImageFormat::Png).unwrap();
-    }
-}
\ No newline at end of file
+use super::{canonical_command, full_command_name, invoked_command, mock_string};
+use super::bonk_stats::{leaderboard, top_templates, user_stats, BonkRecord, Period};
+use super::checkem::{detect, Pattern};
+use super::gets::{GetRecord, GetsLog};
+use super::config::GuildConfig;
+use super::{activity, assets, command_error, logging, metrics, settings, slash};
+use super::command_error::CommandError;
+use super::{help, MOCKER_GROUP, OWNER_GROUP};
+use super::permissions::{self, Role};
+use serenity::model::permissions::Permissions;
+use super::bonker::{select_bonk, ImageData, Mask};
+use image::{Rgba, RgbaImage};
+use image::GenericImageView;
+
+#[test]
+fn test_mock() {
+    let test_string = "mock this bitch";
+    let mocked_string = mock_string(test_string);
+    println!("{}", mocked_string);
+    assert_eq!(test_string.len(), mocked_string.len());
+}
+
+#[test]
+fn test_bonk() {
+    //use a real avatar if one has been dropped in, otherwise a plain square works fine
+    let test_avatar = match image::io::Reader::open("assets/test/test.png") {
+        Ok(reader) => reader.decode().unwrap(),
+        Err(_) => image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(128, 128, image::Rgba([255, 0, 255, 255]))),
+    };
+    std::fs::create_dir_all("assets/test").unwrap();
+    let loaded = assets::load_assets("assets").unwrap();
+    for meta in &loaded.bonks {
+        let bonked_avatar = loaded.render_bonk(&test_avatar, meta).unwrap();
+        bonked_avatar.save_with_format(format!("assets/test/{}", meta.name), image::ImageFormat::Png).unwrap();
+    }
+}
+
+#[test]
+fn test_select_bonk() {
+    let meta_data: Vec<ImageData> = serde_json::from_str(r#"[
+        {"name": "a.png", "bonkee_x": 0, "bonkee_y": 0, "bonkee_width": 1, "bonkee_height": 1, "bonk_label_x": 0, "bonk_label_y": 0, "bonk_label_width": 1, "bonk_label_height": 1, "bonkee_top": false, "tags": ["cat"]},
+        {"name": "b.png", "bonkee_x": 0, "bonkee_y": 0, "bonkee_width": 1, "bonkee_height": 1, "bonk_label_x": 0, "bonk_label_y": 0, "bonk_label_width": 1, "bonk_label_height": 1, "bonkee_top": false, "tags": ["cat"], "weight": 0},
+        {"name": "c.png", "bonkee_x": 0, "bonkee_y": 0, "bonkee_width": 1, "bonkee_height": 1, "bonk_label_x": 0, "bonk_label_y": 0, "bonk_label_width": 1, "bonk_label_height": 1, "bonkee_top": false}
+    ]"#).unwrap();
+    //by name, with or without the extension, even if it was just used
+    assert_eq!(select_bonk(&meta_data, Some("c"), &["c.png".to_string()]).unwrap().name, "c.png");
+    assert_eq!(select_bonk(&meta_data, Some("B.png"), &[]).unwrap().name, "b.png");
+    assert!(select_bonk(&meta_data, Some("d"), &[]).is_err());
+    //b has no weight, so only a can come out of the cat tag
+    for _ in 0..20 {
+        assert_eq!(select_bonk(&meta_data, Some("#cat"), &[]).unwrap().name, "a.png");
+    }
+    assert!(select_bonk(&meta_data, Some("#dog"), &[]).is_err());
+    //recently used templates are skipped, unless there is nothing else left
+    for _ in 0..20 {
+        assert_eq!(select_bonk(&meta_data, None, &["a.png".to_string()]).unwrap().name, "c.png");
+    }
+    assert_eq!(select_bonk(&meta_data, Some("#cat"), &["a.png".to_string()]).unwrap().name, "a.png");
+}
+
+//renders are shrunk to this before comparing so the checked in images stay small
+const GOLDEN_SIZE: u32 = 256;
+//how far a channel can drift before the pixel counts as different, and how many pixels can differ
+const GOLDEN_CHANNEL_TOLERANCE: u8 = 12;
+const GOLDEN_PIXEL_TOLERANCE: f64 = 0.01;
+
+//compares every template, plus the masking/warping options, against assets/golden/
+//run with UPDATE_GOLDEN=1 to rewrite the expected images after an intentional change
+#[test]
+fn test_bonk_golden() {
+    //drawn the same way the bot draws them, through the loaded assets
+    let loaded = assets::load_assets("assets").unwrap();
+    let meta_data = &loaded.bonks;
+    let mut cases: Vec<(String, ImageData)> = meta_data.iter().map(|meta| (meta.short_name().to_string(), meta.clone())).collect();
+    let base = meta_data[0].clone();
+    cases.push(("circle".to_string(), ImageData {mask: Mask::Circle, ..base.clone()}));
+    cases.push(("rounded".to_string(), ImageData {mask: Mask::Rounded(24), ..base.clone()}));
+    cases.push(("rotated".to_string(), ImageData {rotation: 30.0, ..base.clone()}));
+    cases.push(("perspective".to_string(), ImageData {perspective: Some([[40.0, 60.0], [160.0, 40.0], [170.0, 150.0], [50.0, 170.0]]), ..base}));
+
+    let update = std::env::var("UPDATE_GOLDEN").is_ok();
+    let avatar = sample_avatar();
+    let mut failures = Vec::new();
+    for (name, meta) in cases {
+        let rendered = loaded.render_bonk(&avatar, &meta).unwrap().thumbnail(GOLDEN_SIZE, GOLDEN_SIZE);
+        let golden_path = format!("assets/golden/{}.png", name);
+        if update {
+            std::fs::create_dir_all("assets/golden").unwrap();
+            rendered.save_with_format(&golden_path, image::ImageFormat::Png).unwrap();
+            continue
+        }
+        let golden = match image::open(&golden_path) {
+            Ok(golden) => golden,
+            Err(e) => {
+                failures.push(format!("{}: could not open {}: {}", name, golden_path, e));
+                continue
+            }
+        };
+        if let Err(e) = compare_images(&rendered, &golden) {
+            //keep what we got around so it can be diffed by eye
+            std::fs::create_dir_all("assets/test").unwrap();
+            rendered.save_with_format(format!("assets/test/{}.actual.png", name), image::ImageFormat::Png).unwrap();
+            failures.push(format!("{}: {}", name, e));
+        }
+    }
+    assert!(failures.is_empty(), "golden images differ:\n{}", failures.join("\n"));
+}
+
+//stand-in avatar, a checkerboard with one marked corner so stretching and flipping are easy to spot
+fn sample_avatar() -> image::DynamicImage {
+    let avatar = RgbaImage::from_fn(128, 128, |x, y| {
+        if x < 32 && y < 32 {
+            Rgba([255, 200, 0, 255])
+        }
+        else if (x / 16 + y / 16) % 2 == 0 {
+            Rgba([255, 0, 255, 255])
+        }
+        else {
+            Rgba([40, 40, 40, 255])
+        }
+    });
+    image::DynamicImage::ImageRgba8(avatar)
+}
+
+fn compare_images(actual: &image::DynamicImage, expected: &image::DynamicImage) -> Result<(), String> {
+    if actual.dimensions() != expected.dimensions() {
+        return Err(format!("size {:?} does not match expected {:?}", actual.dimensions(), expected.dimensions()))
+    }
+    let actual = actual.to_rgba8();
+    let expected = expected.to_rgba8();
+    let different = actual.pixels().zip(expected.pixels())
+        .filter(|(a, e)| a.0.iter().zip(e.0.iter()).any(|(a, e)| a.max(e) - a.min(e) > GOLDEN_CHANNEL_TOLERANCE))
+        .count();
+    let fraction = different as f64 / (actual.width() * actual.height()) as f64;
+    if fraction > GOLDEN_PIXEL_TOLERANCE {
+        return Err(format!("{} pixels ({:.2}%) differ", different, fraction * 100.0))
+    }
+    Ok(())
+}
+
+#[test]
+fn test_bonk_stats() {
+    let now = chrono::NaiveDate::from_ymd(2021, 6, 30).and_hms(12, 0, 0);
+    let record = |bonker, bonkee, template: &str, guild, days_ago| BonkRecord {
+        bonker,
+        bonkee,
+        template: template.to_string(),
+        guild,
+        time: now - chrono::Duration::days(days_ago),
+    };
+    let records = vec![
+        record(1, 2, "bonk1.png", Some(10), 1),
+        record(1, 3, "bonk1.png", Some(10), 2),
+        record(2, 1, "bonk2.png", Some(10), 3),
+        record(2, 1, "bonk2.png", Some(10), 20),
+        record(2, 1, "bonk3.png", Some(10), 60),
+        record(3, 1, "bonk1.png", Some(11), 1),
+    ];
+    assert_eq!(leaderboard(&records, Some(10), Period::Week, now), vec![(1, 2), (2, 1)]);
+    //ties are broken by id so the order is stable
+    assert_eq!(leaderboard(&records, Some(10), Period::Month, now), vec![(1, 2), (2, 2)]);
+    assert_eq!(leaderboard(&records, Some(10), Period::All, now), vec![(2, 3), (1, 2)]);
+    assert_eq!(leaderboard(&records, None, Period::All, now), vec![]);
+    assert_eq!(top_templates(&records, Some(10)), vec![("bonk1.png".to_string(), 2), ("bonk2.png".to_string(), 2), ("bonk3.png".to_string(), 1)]);
+    let stats = user_stats(&records, Some(10), 1);
+    assert_eq!(stats.given, 2);
+    assert_eq!(stats.received, 3);
+    assert_eq!(stats.favorite_template, Some(("bonk1.png".to_string(), 2)));
+    assert_eq!(stats.nemesis, Some((2, 3)));
+}
+
+#[test]
+fn test_checkem() {
+    let cases: &[(u64, &[Pattern])] = &[
+        (796856698098810931, &[]),
+        (796856698098810911, &[Pattern::Dubs]),
+        (796856698098810111, &[Pattern::Trips]),
+        (796856698098866666, &[Pattern::Quints]),
+        (796856698077777777, &[Pattern::Octs]),
+        (796855555555555555, &[Pattern::Decs]),
+        (796856698098123321, &[Pattern::Palindrome]),
+        (796856698098812345, &[Pattern::Straight]),
+        (796856698098898765, &[Pattern::Straight]),
+        (796856698098876543, &[Pattern::Straight]),
+        (796856698098810000, &[Pattern::Quads, Pattern::Round]),
+        (796856698098800000, &[Pattern::Quints, Pattern::Round]),
+        //palindromes made of one digit are just repeats
+        (796856698098111111, &[Pattern::Sexes]),
+        (796856698098812355, &[Pattern::Dubs]),
+    ];
+    for (id, expected) in cases {
+        assert_eq!(&detect(*id)[..], *expected, "{}", id);
+    }
+}
+
+#[test]
+fn test_checkem_emoji() {
+    use serenity::model::channel::ReactionType;
+    for emoji in ["🎉", "❤️", "👍🏽", "👨‍👩‍👧", "🇺🇸", "4\u{fe0f}\u{20e3}", "#\u{20e3}", "🏴\u{e0067}\u{e0062}\u{e0073}\u{e0063}\u{e0074}\u{e007f}", "🔥🔥", "⭐"].iter() {
+        assert_eq!(super::checkem::parse_emoji(emoji), Some(ReactionType::Unicode(emoji.to_string())), "{}", emoji);
+    }
+    assert!(matches!(super::checkem::parse_emoji("<:quads:796861430297001985>"), Some(ReactionType::Custom {animated: false, ..})));
+    assert!(matches!(super::checkem::parse_emoji("<a:spin:1>"), Some(ReactionType::Custom {animated: true, ..})));
+    for text in ["hello", "", "4", "🎉 party", "\u{fe0f}", "\u{200d}", ":quads:", "<:quads:>", "<:quads:12x>", "<b:quads:1>", "<:qu ads:1>", "<:quads:1"].iter() {
+        assert_eq!(super::checkem::parse_emoji(text), None, "{}", text);
+    }
+    //a saved emoji that can't be used falls back instead of failing every time
+    let overrides = vec![(Pattern::Quads, "hello".to_string())].into_iter().collect();
+    assert_eq!(super::checkem::reaction_for(&overrides, Pattern::Quads), Some(Pattern::Quads.fallback_reaction()));
+}
+
+#[test]
+fn test_gets() {
+    let now = chrono::NaiveDate::from_ymd(2021, 6, 30).and_hms(12, 0, 0);
+    let record = |user, pattern, guild, days_ago| GetRecord {
+        guild,
+        channel: 5,
+        message: 1,
+        user,
+        pattern,
+        time: now - chrono::Duration::days(days_ago),
+    };
+    let log = GetsLog {
+        records: vec![
+            record(1, Pattern::Dubs, Some(10), 3),
+            record(1, Pattern::Dubs, Some(10), 2),
+            record(1, Pattern::Trips, Some(10), 1),
+            record(2, Pattern::Quads, Some(10), 5),
+            record(3, Pattern::Sexes, Some(11), 1),
+        ],
+    };
+    let scores = super::gets::leaderboard(&log, Some(10));
+    assert_eq!(scores.iter().map(|score| (score.user, score.points, score.count, score.best)).collect::<Vec<_>>(),
+        vec![(2, 100, 1, Pattern::Quads), (1, 12, 3, Pattern::Trips)]);
+    let history = super::gets::history(&log, Some(10), 1);
+    assert_eq!(history.iter().map(|record| record.pattern).collect::<Vec<_>>(), vec![Pattern::Trips, Pattern::Dubs, Pattern::Dubs]);
+    assert!(super::gets::is_hall_of_fame(Pattern::Quads));
+    assert!(!super::gets::is_hall_of_fame(Pattern::Trips));
+}
+
+#[test]
+fn test_guild_config() {
+    let mut config = GuildConfig::default();
+    assert_eq!(config.get("mock_count"), Ok("3".to_string()));
+    assert!(config.set("mock_count", "7").is_ok());
+    assert_eq!(config.mock_count, 7);
+    //out of range and garbage values leave the setting alone
+    assert!(config.set("mock_count", "0").is_err());
+    assert!(config.set("mock_count", "lots").is_err());
+    assert_eq!(config.mock_count, 7);
+    assert!(config.set("gets_tracking", "off").is_ok());
+    assert!(!config.gets_tracking);
+    assert!(config.set("hall_of_fame", "<#1234>").is_ok());
+    assert_eq!(config.get("hall_of_fame"), Ok("<#1234>".to_string()));
+    assert!(config.set("hall_of_fame", "general").is_err());
+    assert!(config.set("not_a_setting", "1").is_err());
+    assert!(config.set("prefix", "$$").is_ok());
+    assert!(config.set("prefix", "a b").is_err());
+    assert!(config.set("prefix", "toolong").is_err());
+    assert!(config.set("prefix", "<@").is_err());
+    assert_eq!(config.prefix, "$$");
+    assert!(config.reset("mock_count").is_ok());
+    assert!(config.reset("hall_of_fame").is_ok());
+    assert_eq!(config.mock_count, 3);
+    assert_eq!(config.hall_of_fame, None);
+    //old files without every field still load
+    let loaded: GuildConfig = serde_json::from_str(r#"{"mock_count": 5}"#).unwrap();
+    assert_eq!(loaded.mock_count, 5);
+    assert!(loaded.gets_tracking);
+}
+
+#[test]
+fn test_feature_toggles() {
+    let mut config = GuildConfig::default();
+    assert!(config.feature_enabled("bonk", 1));
+    //things that aren't features are always on
+    assert!(config.feature_enabled("config", 1));
+    assert!(config.set_feature("config", None, false).is_err());
+    assert!(config.set_feature("bonk", None, false).is_ok());
+    assert!(!config.feature_enabled("bonk", 1));
+    //a channel can turn it back on just for itself
+    assert!(config.set_feature("bonk", Some(2), true).is_ok());
+    assert!(config.feature_enabled("bonk", 2));
+    assert!(!config.feature_enabled("bonk", 1));
+    assert!(config.set_feature("gallery", Some(1), false).is_ok());
+    assert!(!config.feature_enabled("gallery", 1));
+    assert!(config.feature_enabled("gallery", 2));
+    //setting it for the guild clears the channel overrides
+    assert!(config.set_feature("bonk", None, true).is_ok());
+    assert!(config.feature_enabled("bonk", 1));
+    assert!(!config.channel_features.contains_key(&2));
+    assert!(config.set_feature("bonk", None, false).is_ok());
+    assert!(!config.feature_enabled("bonk", 2));
+    //turning off the reactions leaves the command that sets them up alone
+    assert!(config.set_feature("checkem", None, false).is_err());
+    assert!(config.set_feature("checkem_reactions", None, false).is_ok());
+    assert!(!config.feature_enabled("checkem_reactions", 1));
+    assert!(config.feature_enabled("checkem", 1));
+    assert!(config.set_feature("checkem_reactions", Some(1), true).is_ok());
+    assert!(config.feature_enabled("checkem_reactions", 1));
+    assert!(!config.feature_enabled("checkem_reactions", 2));
+    //configs saved with the old name are moved over, and an old leftover can't turn a command off
+    let dir = std::env::temp_dir().join(format!("shawty_features_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("guild_config.json"), r#"{"5": {"disabled": ["checkem", "gallery"], "channel_features": {"9": {"checkem": true}}}}"#).unwrap();
+    let configs = super::config::load_configs(dir.to_str().unwrap()).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let config = &configs[&5];
+    assert!(!config.feature_enabled("checkem_reactions", 1));
+    assert!(config.feature_enabled("checkem_reactions", 9));
+    assert!(!config.feature_enabled("gallery", 1));
+    assert!(config.feature_enabled("checkem", 1));
+    let mut config = GuildConfig::default();
+    config.disabled.insert("checkem".to_string());
+    assert!(config.feature_enabled("checkem", 1));
+}
+
+#[test]
+fn test_invoked_command() {
+    assert_eq!(invoked_command("!bonk list", "!"), "bonk");
+    assert_eq!(invoked_command("  $$Gets @someone", "$$"), "gets");
+    assert_eq!(invoked_command("<@!1234> features list", "!"), "features");
+    assert_eq!(invoked_command("<@1234>remind me in 5 minutes", "!"), "remind");
+    assert_eq!(invoked_command("!", "!"), "");
+}
+
+#[test]
+fn test_slash_commands() {
+    let templates: Vec<String> = (0..25).map(|i| format!("bonk{}", i)).collect();
+    let valid_name = |name: &str| !name.is_empty() && name.len() <= 32 && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    for command in slash::commands(templates) {
+        //discord rejects the whole command if any of this is off
+        let json = slash::command_json(&command);
+        assert!(valid_name(json["name"].as_str().unwrap()), "{}", command.name);
+        assert!(json["description"].as_str().unwrap().len() <= 100, "{}", command.name);
+        let options = json["options"].as_array().unwrap();
+        assert!(options.len() <= 25);
+        for option in options.iter().chain(options.iter().flat_map(|option| option["options"].as_array().into_iter().flatten())) {
+            assert!(valid_name(option["name"].as_str().unwrap()), "{} {}", command.name, option["name"]);
+            assert!(!option["description"].as_str().unwrap().is_empty());
+            assert!(option["description"].as_str().unwrap().len() <= 100);
+            assert!(option["choices"].as_array().map_or(0, |choices| choices.len()) <= 25);
+        }
+    }
+    //options come back in whatever order they were filled in, args keep the declared order
+    let commands = slash::commands(Vec::new());
+    let config = commands.iter().find(|command| command.name == "config").unwrap();
+    let (_, _, declared) = config.subcommands.iter().find(|(name, _, _)| *name == "set").unwrap();
+    let given: Vec<_> = serde_json::from_str(r#"[{"name": "value", "value": "5"}, {"name": "setting", "value": "mock_count"}]"#).unwrap();
+    assert_eq!(slash::flatten_options(declared, &given), (vec!["mock_count".to_string(), "5".to_string()], vec![]));
+    let bonk = commands.iter().find(|command| command.name == "bonk").unwrap();
+    let given: Vec<_> = serde_json::from_str(r##"[{"name": "template", "value": "#soft"}, {"name": "user", "value": "1234"}]"##).unwrap();
+    assert_eq!(slash::flatten_options(&bonk.options, &given), (vec!["<@1234>".to_string(), "#soft".to_string()], vec![1234]));
+    let leaderboard = commands.iter().find(|command| command.name == "bonkstats").unwrap()
+        .subcommands.iter().find(|(name, _, _)| *name == "leaderboard").unwrap();
+    let given: Vec<_> = serde_json::from_str(r#"[{"name": "chart", "value": true}, {"name": "period", "value": "week"}]"#).unwrap();
+    assert_eq!(slash::flatten_options(&leaderboard.2, &given).0, vec!["week".to_string(), "chart".to_string()]);
+    //the owner commands are there too, and only for owners
+    for name in ["reload-assets", "shutdown", "guilds", "say", "activity"].iter() {
+        let command = commands.iter().find(|command| command.name == *name).unwrap();
+        assert_eq!(command.role, Role::Owner, "{}", name);
+    }
+    let activity = commands.iter().find(|command| command.name == "activity").unwrap();
+    let (_, _, declared) = activity.subcommands.iter().find(|(name, _, _)| *name == "add").unwrap();
+    let given: Vec<_> = serde_json::from_str(r#"[{"name": "text", "value": "{bonk_count} bonks"}, {"name": "link", "value": "https://twitch.tv/x"}, {"name": "kind", "value": "streaming"}]"#).unwrap();
+    let (args, _) = slash::flatten_options(declared, &given);
+    assert_eq!(args, ["streaming", "https://twitch.tv/x", "{bonk_count} bonks"]);
+    assert!(super::activity::Entry::parse(&args).is_ok());
+    //too many templates to offer as choices leaves it open to typing anything
+    let commands = slash::commands((0..30).map(|i| format!("bonk{}", i)).collect());
+    let bonk = slash::command_json(commands.iter().find(|command| command.name == "bonk").unwrap());
+    let template = bonk["options"].as_array().unwrap().iter().find(|option| option["name"] == "template").unwrap();
+    assert!(template.get("choices").is_none());
+}
+
+#[test]
+fn test_help() {
+    let commands = MOCKER_GROUP.options.commands;
+    let path = |path: &str| path.split(' ').map(|part| part.to_string()).collect::<Vec<_>>();
+    //aliases and subcommands resolve to their full name
+    assert_eq!(help::find(commands, &path("coin")).map(|(name, _)| name), Some("flip".to_string()));
+    assert_eq!(help::find(commands, &path("Bonk leaderboard")).map(|(name, _)| name), Some("bonk leaderboard".to_string()));
+    assert!(help::find(commands, &path("bonk nothing")).is_none());
+    assert!(help::find(commands, &path("nothing")).is_none());
+    //every command has something to say for itself
+    for command in commands.iter().chain(commands.iter().flat_map(|command| command.options.sub_commands.iter())) {
+        assert!(command.options.desc.is_some(), "{} has no description", command.options.names[0]);
+    }
+    let (name, bonk) = help::find(commands, &path("bonk")).unwrap();
+    let details = help::details("?", &name, bonk).embed.unwrap();
+    assert_eq!(details.title, "?bonk");
+    assert!(details.description.contains("`?bonk @someone #soft`"));
+    assert!(details.description.contains("`?bonk leaderboard [week|month|all] [chart]`"));
+    //turned off commands are left out, and the pages shrink to match
+    let everything = help::overview(commands, "!", 1, |_| true).unwrap().embed.unwrap();
+    assert!(everything.description.contains("`!bonk [target...] [template|#tag]`"));
+    let pages = commands.len().div_ceil(help::PAGE_SIZE);
+    assert_eq!(everything.title, format!("Commands (1/{})", pages));
+    assert!(help::overview(commands, "!", pages + 1, |_| true).is_err());
+    let no_bonk = help::overview(commands, "!", 1, |name| name != "bonk").unwrap().embed.unwrap();
+    assert!(!no_bonk.description.contains("!bonk"));
+    //checks show up as what's needed
+    let (name, config) = help::find(commands, &path("config set")).unwrap();
+    assert!(help::details("!", &name, config).embed.unwrap().description.contains("**Needs:** admin"));
+    assert_eq!(help::needs(help::find(commands, &path("features")).unwrap().1), Some("moderator".to_string()));
+    assert_eq!(help::needs(help::find(commands, &path("flip")).unwrap().1), None);
+    assert!(OWNER_GROUP.options.commands.iter().all(|command| help::needs(command) == Some("owner".to_string())));
+}
+
+#[test]
+fn test_permissions() {
+    let nothing = Permissions::empty();
+    assert_eq!(permissions::role_for(false, nothing, &[], None), Role::User);
+    assert_eq!(permissions::role_for(false, Permissions::MANAGE_MESSAGES, &[], None), Role::Moderator);
+    assert_eq!(permissions::role_for(false, Permissions::MANAGE_GUILD, &[], None), Role::Admin);
+    assert_eq!(permissions::role_for(false, Permissions::ADMINISTRATOR, &[], None), Role::Admin);
+    assert_eq!(permissions::role_for(true, nothing, &[], None), Role::Owner);
+    //the moderator role only counts if they have it
+    assert_eq!(permissions::role_for(false, nothing, &[1, 2], Some(2)), Role::Moderator);
+    assert_eq!(permissions::role_for(false, nothing, &[1], Some(2)), Role::User);
+    assert!(Role::Owner > Role::Admin && Role::Admin > Role::Moderator && Role::Moderator > Role::User);
+    assert_eq!(permissions::parse_owners("1, 2,3,,nope"), [1, 2, 3].iter().cloned().collect());
+    assert!(permissions::parse_owners("").is_empty());
+    let mut config = GuildConfig::default();
+    assert_eq!(config.get("moderator_role"), Ok("off".to_string()));
+    assert!(config.set("moderator_role", "<@&1234>").is_ok());
+    assert_eq!(config.moderator_role, Some(1234));
+    assert_eq!(config.get("moderator_role"), Ok("<@&1234>".to_string()));
+    assert!(config.set("moderator_role", "<#1234>").is_err());
+    assert!(config.reset("moderator_role").is_ok());
+    assert_eq!(config.moderator_role, None);
+}
+
+#[test]
+fn test_assets() {
+    //everything checked in has to pass its own checks
+    let loaded = assets::load_assets("assets").unwrap();
+    assert!(!loaded.activities.is_empty());
+    assert!(loaded.files().contains(&"bonk_locations.json".to_string()));
+    let meta = &loaded.bonks[0];
+    assert!(loaded.render_bonk(&sample_avatar(), meta).is_ok());
+    //a broken folder gives back every problem instead of a half loaded registry
+    let dir = std::env::temp_dir().join(format!("shawty_assets_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let dir_name = dir.to_str().unwrap();
+    std::fs::write(dir.join("activities.json"), r#"["fine", " "]"#).unwrap();
+    std::fs::write(dir.join("bonk_locations.json"), serde_json::to_string(&vec![
+        ImageData {name: "missing.png".to_string(), ..meta.clone()},
+        ImageData {name: "Missing.jpg".to_string(), weight: 0, ..meta.clone()},
+    ]).unwrap()).unwrap();
+    let errors = assets::load_assets(dir_name).err().unwrap();
+    assert!(errors.iter().any(|e| e.contains("blank activity")));
+    assert!(errors.iter().any(|e| e.contains("could not open missing.png")));
+    assert!(errors.iter().any(|e| e.contains("could not open bonklabel.png")));
+    assert!(errors.iter().any(|e| e.contains("more than one bonk template named Missing")));
+    std::fs::write(dir.join("activities.json"), "not json").unwrap();
+    assert!(assets::load_assets(dir_name).err().unwrap().iter().any(|e| e.contains("could not parse activities.json")));
+    std::fs::remove_dir_all(&dir).unwrap();
+    //only the files assets come from are watched
+    let files = vec!["activities.json".to_string()];
+    assert!(assets::last_modified("assets", &files).is_some());
+    assert!(assets::last_modified("assets", &["nothing.json".to_string()]).is_none());
+    //templates have to fit the image they're on
+    let mut images = std::collections::HashMap::new();
+    images.insert(meta.name.clone(), image::DynamicImage::new_rgba8(1, 1));
+    let far = ImageData {bonkee_x: 5, ..meta.clone()};
+    assert!(assets::check_bonks(&[far], &images).iter().any(|e| e.contains("starts outside the 1x1 image")));
+}
+
+#[test]
+fn test_settings() {
+    let args = |line: &str| line.split(' ').map(|arg| arg.to_string()).collect::<Vec<_>>();
+    let parsed = settings::parse_args(&args("--config bot.toml --data /var/lib/shawty --owner 1 --owner 2 --log-level debug")).unwrap();
+    assert_eq!(parsed.config, Some("bot.toml".to_string()));
+    assert_eq!(parsed.overrides.data_dir, Some("/var/lib/shawty".to_string()));
+    assert_eq!(parsed.overrides.owners, vec![1, 2]);
+    assert!(settings::parse_args(&args("--help")).unwrap().help);
+    assert!(settings::parse_args(&args("--owner me")).unwrap_err().contains("needs a user id"));
+    assert!(settings::parse_args(&args("--data")).unwrap_err().contains("needs a value"));
+    assert!(settings::parse_args(&args("--colour blue")).unwrap_err().contains("unknown option"));
+    //the file fills in what the flags leave out
+    let file = settings::parse_file("bot.toml", "assets_dir = \"/srv/assets\"\nowners = [3]\nlog_level = \"warn\"\n").unwrap();
+    let resolved = settings::resolve(file.clone(), parsed.overrides).unwrap();
+    assert_eq!(resolved.assets_dir, "/srv/assets");
+    assert_eq!(resolved.data_dir, "/var/lib/shawty");
+    assert_eq!(resolved.token_file, "/var/lib/shawty/key");
+    assert_eq!(resolved.owners, vec![1, 2]);
+    assert_eq!(resolved.log_level, "debug");
+    let from_file = settings::resolve(file, Default::default()).unwrap();
+    assert_eq!(from_file.owners, vec![3]);
+    assert_eq!(from_file.log_level, "warn");
+    //the defaults are what the bot always used
+    let defaults = settings::resolve(Default::default(), Default::default()).unwrap();
+    assert_eq!((defaults.assets_dir.as_str(), defaults.data_dir.as_str(), defaults.token_file.as_str()), ("./assets", "./assets", "./assets/key"));
+    //mistakes are caught before anything starts
+    assert!(settings::parse_file("bot.toml", "asset_dir = \"x\"").unwrap_err().contains("bot.toml isn't valid"));
+    assert!(settings::parse_file("bot.toml", "owners = \"me\"").is_err());
+    assert!(settings::resolve(settings::SettingsFile {log_level: Some("loud".to_string()), ..Default::default()}, Default::default()).unwrap_err().contains("log level"));
+    assert_eq!(settings::resolve(Default::default(), settings::parse_args(&args("--log-format JSON")).unwrap().overrides).unwrap().log_format, "json");
+    assert!(settings::resolve(settings::SettingsFile {log_format: Some("xml".to_string()), ..Default::default()}, Default::default()).unwrap_err().contains("log format"));
+    assert_eq!(defaults.http_addr, None);
+    assert_eq!(settings::resolve(settings::SettingsFile {admin_token: Some(" ".to_string()), ..Default::default()}, Default::default()).unwrap().admin_token, None);
+    assert_eq!(settings::resolve(Default::default(), settings::parse_args(&args("--http 127.0.0.1:9100")).unwrap().overrides).unwrap().http_addr, Some("127.0.0.1:9100".parse().unwrap()));
+    assert!(settings::resolve(settings::SettingsFile {http_addr: Some("localhost".to_string()), ..Default::default()}, Default::default()).unwrap_err().contains("http address"));
+    assert_eq!(settings::load(&args("--help")), Ok(None));
+    assert!(settings::load(&args("--config nothing.toml")).unwrap_err().contains("could not read nothing.toml"));
+    assert!(settings::load(&args("--assets nothing")).unwrap_err().contains("assets folder nothing doesn't exist"));
+}
+
+#[test]
+fn test_activity() {
+    //plain strings still work, next to the full form
+    let entries: Vec<activity::Entry> = serde_json::from_str(r#"[
+        "mineycrafta",
+        {"text": "{bonk_count} bonks", "kind": "listening", "weight": 0},
+        {"text": "sleep simulator", "hours": [22, 6], "weight": 5}
+    ]"#).unwrap();
+    assert_eq!(entries[0], activity::Entry::new("mineycrafta"));
+    assert_eq!(entries[1].kind, activity::Kind::Listening);
+    assert!(serde_json::from_str::<Vec<activity::Entry>>(r#"[{"text": "tv", "kind": "watching"}]"#).is_err());
+    //hours wrap past midnight
+    assert!(entries[2].active_at(23) && entries[2].active_at(0) && !entries[2].active_at(6) && !entries[2].active_at(12));
+    assert!(!activity::Entry {hours: Some([9, 17]), ..activity::Entry::new("work")}.active_at(17));
+    //weight 0 is never picked, and the night one only at night
+    for _ in 0..20 {
+        assert_eq!(activity::pick(&entries, 12).unwrap().text, "mineycrafta");
+    }
+    assert!(activity::pick(&entries[1..], 12).is_none());
+    assert_eq!(activity::pick(&entries[1..], 3).unwrap().text, "sleep simulator");
+    //variables
+    assert_eq!(entries[1].variables(), vec!["bonk_count"]);
+    let mut values = std::collections::HashMap::new();
+    values.insert("bonk_count", "42".to_string());
+    assert_eq!(activity::fill("{bonk_count} bonks, {nope}", &values), "42 bonks, {nope}");
+    assert_eq!(activity::describe_wait(chrono::Duration::minutes(3 * 24 * 60 + 125)), "3d 2h");
+    assert_eq!(activity::describe_wait(chrono::Duration::minutes(125)), "2h 5m");
+    assert_eq!(activity::describe_wait(chrono::Duration::seconds(-5)), "0m");
+    //checks
+    let broken = vec![
+        activity::Entry::new("{guild_count} servers and {nope}"),
+        activity::Entry {kind: activity::Kind::Streaming, ..activity::Entry::new("live")},
+        activity::Entry {hours: Some([3, 3]), ..activity::Entry::new("never")},
+    ];
+    let errors = activity::check(&broken);
+    assert_eq!(errors.len(), 3, "{:?}", errors);
+    assert!(errors[0].contains("{nope}"));
+    assert!(errors[1].contains("needs a url"));
+    assert!(errors[2].contains("two different hours"));
+    assert!(activity::check(&entries).is_empty());
+    assert!(activity::check(&[]).iter().any(|e| e.contains("no activities")));
+    //what !activity add and set take
+    let args = |line: &str| line.split(' ').map(|arg| arg.to_string()).collect::<Vec<_>>();
+    assert_eq!(activity::Entry::parse(&args("mineycrafta 2")).unwrap(), activity::Entry::new("mineycrafta 2"));
+    let listening = activity::Entry::parse(&args("Listening {bonk_count} bonks")).unwrap();
+    assert_eq!((listening.kind, listening.text.as_str()), (activity::Kind::Listening, "{bonk_count} bonks"));
+    let streaming = activity::Entry::parse(&args("streaming https://twitch.tv/someone bonking live")).unwrap();
+    assert_eq!(streaming.url.as_deref(), Some("https://twitch.tv/someone"));
+    assert!(activity::Entry::parse(&args("streaming bonking live")).is_err());
+    assert!(activity::Entry::parse(&args("competing")).is_err());
+    assert_eq!(entries[2].describe(), "playing `sleep simulator` (weight 5, 22:00-6:00)");
+    assert_eq!(listening.describe(), "listening to `{bonk_count} bonks`");
+    //plain entries are written back as plain strings
+    let written = serde_json::to_string(&vec![activity::Entry::new("mineycrafta"), listening.clone()]).unwrap();
+    assert_eq!(written, r#"["mineycrafta",{"text":"{bonk_count} bonks","kind":"listening","weight":1}]"#);
+    assert_eq!(serde_json::from_str::<Vec<activity::Entry>>(&written).unwrap(), vec![activity::Entry::new("mineycrafta"), listening]);
+}
+
+#[test]
+fn test_logging() {
+    #[derive(Debug)]
+    struct Wrapped(&'static str, Option<std::io::Error>);
+    impl std::fmt::Display for Wrapped {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+    impl std::error::Error for Wrapped {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            self.1.as_ref().map(|e| e as &(dyn std::error::Error + 'static))
+        }
+    }
+    let refused = || Some(std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "connection refused"));
+    assert_eq!(logging::chain(&Wrapped("error sending request", refused())), "error sending request: connection refused");
+    //causes already in the message aren't repeated
+    assert_eq!(logging::chain(&Wrapped("error sending request: connection refused", refused())), "error sending request: connection refused");
+    assert_eq!(logging::chain(&Wrapped("timed out", None)), "timed out");
+}
+
+#[test]
+fn test_command_error() {
+    //plain messages are the user's fault and shown as they are
+    let refused: CommandError = format!("there's no {} command", "bork").into();
+    assert_eq!(refused, CommandError::User("there's no bork command".to_string()));
+    assert_eq!(refused.user_message(), "there's no bork command");
+    assert!(CommandError::Transient("could not fetch image".to_string()).user_message().contains("try again"));
+    //internal details aren't shown, only logged and sent to the owners
+    let broken = CommandError::Internal("could not save the bonk: disk full".to_string());
+    assert!(!broken.user_message().contains("disk full"));
+    assert!(broken.is_internal() && !refused.is_internal());
+    assert_ne!(broken.reaction(), refused.reaction());
+    //what the after hook gets back out of a failed prefix command
+    let boxed: Box<dyn std::error::Error + Send + Sync> = Box::new(refused.clone());
+    assert_eq!(command_error::from_boxed(boxed.as_ref()), refused);
+    let other: Box<dyn std::error::Error + Send + Sync> = Box::new(std::io::Error::other("oops"));
+    assert_eq!(command_error::from_boxed(other.as_ref()), CommandError::Internal("oops".to_string()));
+}
+
+#[test]
+fn test_report_limiter() {
+    let start = std::time::Instant::now();
+    let minute = std::time::Duration::from_secs(60);
+    let mut limiter = command_error::ReportLimiter::new(minute * 10);
+    assert_eq!(limiter.allow(start), Some(0));
+    assert_eq!(limiter.allow(start + minute), None);
+    assert_eq!(limiter.allow(start + minute * 9), None);
+    //the next one through says how many were held back
+    assert_eq!(limiter.allow(start + minute * 10), Some(2));
+    assert_eq!(limiter.allow(start + minute * 11), None);
+    assert_eq!(limiter.allow(start + minute * 30), Some(1));
+}
+
+#[test]
+fn test_metrics() {
+    let metrics = metrics::Metrics::default();
+    metrics.command("bonk", "ok");
+    metrics.command("bonk", "ok");
+    metrics.command("bonk list", "internal");
+    metrics.reminder_fired();
+    metrics.bonk_render.observe(std::time::Duration::from_millis(80));
+    metrics.bonk_render.observe(std::time::Duration::from_secs(3));
+    metrics.bonk_render.observe(std::time::Duration::from_secs(60));
+    let rendered = metrics.render(4);
+    assert!(rendered.contains("shawty_commands_total{command=\"bonk\",outcome=\"ok\"} 2\n"));
+    assert!(rendered.contains("shawty_commands_total{command=\"bonk list\",outcome=\"internal\"} 1\n"));
+    assert!(rendered.contains("shawty_reminders_fired_total 1\n"));
+    assert!(rendered.contains("shawty_reminders_failed_total 0\n"));
+    assert!(rendered.contains("shawty_reminders_pending 4\n"));
+    //buckets count everything at or under them, +Inf is everything
+    assert!(rendered.contains("shawty_bonk_render_seconds_bucket{le=\"0.05\"} 0\n"));
+    assert!(rendered.contains("shawty_bonk_render_seconds_bucket{le=\"0.1\"} 1\n"));
+    assert!(rendered.contains("shawty_bonk_render_seconds_bucket{le=\"5\"} 2\n"));
+    assert!(rendered.contains("shawty_bonk_render_seconds_bucket{le=\"+Inf\"} 3\n"));
+    assert!(rendered.contains("shawty_bonk_render_seconds_count 3\n"));
+    assert!(rendered.contains("# TYPE shawty_avatar_fetch_seconds histogram\n"));
+    //what the after hook labels commands with
+    assert_eq!(full_command_name(&canonical_command("coin"), "flip"), "flip");
+    assert_eq!(full_command_name(&canonical_command("bonk"), "list"), "bonk list");
+    assert_eq!(full_command_name(&canonical_command("reload"), "reload-assets"), "reload-assets");
+}
+
+#[tokio::test]
+async fn test_web() {
+    let mut data = serenity::prelude::TypeMap::new();
+    data.insert::<super::ReminderList>(Vec::new());
+    let metrics = std::sync::Arc::new(metrics::Metrics::default());
+    metrics.command("flip", "ok");
+    data.insert::<super::BotMetrics>(metrics);
+    data.insert::<super::MockTracker>(std::iter::once((42, 3)).collect());
+    let settings = settings::resolve(settings::SettingsFile {admin_token: Some("secret".to_string()), ..Default::default()}, Default::default()).unwrap();
+    data.insert::<super::BotSettings>(settings);
+    let addr: std::net::SocketAddr = "127.0.0.1:39471".parse().unwrap();
+    let http = std::sync::Arc::new(serenity::http::Http::new_with_token("not a token"));
+    tokio::spawn(super::web::serve(addr, std::sync::Arc::new(serenity::prelude::RwLock::new(data)), http));
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    let client = reqwest::Client::new();
+    let url = |path: &str| format!("http://{}{}", addr, path);
+    let response = client.get(&url("/metrics")).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body = response.text().await.unwrap();
+    assert!(body.contains("shawty_commands_total{command=\"flip\",outcome=\"ok\"} 1"));
+    assert!(body.contains("shawty_reminders_pending 0"));
+    //never connected and the reminder loop never ran, but the data folder is there
+    let response = client.get(&url("/healthz")).send().await.unwrap();
+    assert_eq!(response.status(), 503);
+    assert_eq!(response.json::<serde_json::Value>().await.unwrap(), serde_json::json!({"gateway": false, "scheduler": false, "storage": true}));
+    //the admin api wants the token
+    assert_eq!(client.get(&url("/admin/mocks")).send().await.unwrap().status(), 401);
+    assert_eq!(client.get(&url("/admin/mocks")).bearer_auth("guess").send().await.unwrap().status(), 401);
+    let mocks = client.get(&url("/admin/mocks")).bearer_auth("secret").send().await.unwrap().json::<serde_json::Value>().await.unwrap();
+    assert_eq!(mocks, serde_json::json!({"42": 3}));
+    let reminders = client.get(&url("/admin/reminders")).bearer_auth("secret").send().await.unwrap().json::<serde_json::Value>().await.unwrap();
+    assert_eq!(reminders, serde_json::json!([]));
+    assert_eq!(client.delete(&url("/admin/reminders/123")).bearer_auth("secret").send().await.unwrap().status(), 404);
+    let config = client.get(&url("/admin/config")).bearer_auth("secret").send().await.unwrap().text().await.unwrap();
+    assert!(config.contains("\"log_level\":\"info\"") && !config.contains("secret"));
+    assert_eq!(client.get(&url("/nope")).send().await.unwrap().status(), 404);
+}
+
+#[test]
+fn test_web_routes() {
+    use hyper::Method;
+    use super::web::{authorized, route, Route};
+    assert_eq!(route(&Method::GET, "/metrics"), Route::Metrics);
+    assert_eq!(route(&Method::GET, "/healthz/"), Route::Health);
+    assert_eq!(route(&Method::DELETE, "/admin/reminders/853"), Route::CancelReminder(853));
+    assert_eq!(route(&Method::DELETE, "/admin/reminders/all"), Route::NotFound);
+    assert_eq!(route(&Method::GET, "/admin/reload"), Route::NotFound);
+    assert_eq!(route(&Method::POST, "/admin/reload"), Route::Reload);
+    assert!(route(&Method::GET, "/admin/config").is_admin());
+    assert!(!route(&Method::GET, "/healthz").is_admin());
+    assert!(authorized(Some("Bearer hunter2"), "hunter2"));
+    assert!(!authorized(Some("Bearer hunter"), "hunter2"));
+    assert!(!authorized(Some("hunter2"), "hunter2"));
+    assert!(!authorized(None, "hunter2"));
+}
+
+#[tokio::test]
+async fn test_lifecycle() {
+    use std::sync::Arc;
+    use std::time::Duration;
+    let lifecycle = Arc::new(super::lifecycle::Lifecycle::default());
+    let first = lifecycle.begin().unwrap();
+    let second = lifecycle.begin().unwrap();
+    assert_eq!(lifecycle.in_flight(), 2);
+    drop(first);
+    lifecycle.stop();
+    assert!(lifecycle.is_shutting_down());
+    //nothing new once it's stopping, and that doesn't count as in flight
+    assert!(lifecycle.begin().is_none());
+    assert_eq!(lifecycle.in_flight(), 1);
+    //gives up on the one that's still going
+    assert_eq!(lifecycle.wait_idle(Duration::from_millis(100)).await, 1);
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        drop(second);
+    });
+    assert_eq!(lifecycle.wait_idle(Duration::from_secs(5)).await, 0);
+    //the !shutdown command wakes up whoever's waiting
+    let waiting = lifecycle.clone();
+    lifecycle.request();
+    assert_eq!(tokio::time::timeout(Duration::from_secs(1), waiting.wait_for_request()).await, Ok("!shutdown"));
+}
+
+#[tokio::test]
+async fn test_flush_state() {
+    let dir = std::env::temp_dir().join(format!("shawty_flush_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let dir = dir.to_str().unwrap().to_string();
+    let mut data = serenity::prelude::TypeMap::new();
+    data.insert::<super::ReminderList>(Vec::new());
+    data.insert::<super::MockTracker>(std::iter::once((42, 3)).collect());
+    let settings = settings::resolve(settings::SettingsFile {data_dir: Some(dir.clone()), ..Default::default()}, Default::default()).unwrap();
+    data.insert::<super::BotSettings>(settings);
+    let (saved, errors) = super::flush_state(&serenity::prelude::RwLock::new(data)).await;
+    assert!(errors.is_empty(), "{:?}", errors);
+    assert_eq!(saved, vec!["0 reminders", "1 mock targets"]);
+    //a restart picks the mocks back up
+    assert_eq!(super::load_mock_tracker(&dir).get(&42), Some(&3));
+    assert!(super::load_mock_tracker("/nonexistent").is_empty());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+//a discord that writes down everything the bot does instead, so commands and messages can be driven with no network
+#[derive(Default)]
+struct FakeChat {
+    me: u64,
+    //channel, what it replied to, what was said
+    sent: std::sync::Mutex<Vec<(u64, Option<u64>, super::invocation::Reply)>>,
+    //message, reaction
+    reactions: std::sync::Mutex<Vec<(u64, String)>>,
+    dms: std::sync::Mutex<Vec<(u64, String)>>,
+    //who reacted to a message, the same whatever the reaction
+    reacted: std::sync::Mutex<std::collections::HashMap<u64, Vec<u64>>>,
+    nsfw: std::collections::HashSet<u64>,
+    //anything not in here fails like the network is down
+    pages: std::collections::HashMap<String, super::surface::Fetched>,
+    next_id: std::sync::atomic::AtomicU64,
+}
+
+impl FakeChat {
+    fn sent(&self) -> Vec<(u64, Option<u64>, super::invocation::Reply)> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    fn said(&self) -> Vec<String> {
+        self.sent().into_iter().map(|(_, _, reply)| reply.content).collect()
+    }
+
+    fn reactions(&self) -> Vec<(u64, String)> {
+        self.reactions.lock().unwrap().clone()
+    }
+}
+
+#[serenity::async_trait]
+impl super::surface::ChatSurface for FakeChat {
+    async fn current_user(&self) -> Result<u64, CommandError> {
+        Ok(self.me)
+    }
+
+    async fn send(&self, channel: u64, reference: Option<&serenity::model::channel::Message>, reply: &super::invocation::Reply) -> Result<serenity::model::channel::Message, CommandError> {
+        let id = 5_000 + self.next_id.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        self.sent.lock().unwrap().push((channel, reference.map(|msg| *msg.id.as_u64()), reply.clone()));
+        Ok(fake_message(id, channel, None, self.me, &reply.content))
+    }
+
+    async fn react(&self, _channel: u64, message: u64, reaction: serenity::model::channel::ReactionType) -> Result<(), CommandError> {
+        self.reactions.lock().unwrap().push((message, reaction.to_string()));
+        Ok(())
+    }
+
+    async fn reaction_users(&self, _channel: u64, message: u64, _reaction: serenity::model::channel::ReactionType) -> Result<Vec<u64>, CommandError> {
+        Ok(self.reacted.lock().unwrap().get(&message).cloned().unwrap_or_default())
+    }
+
+    async fn dm(&self, user: u64, text: &str) -> Result<(), CommandError> {
+        self.dms.lock().unwrap().push((user, text.to_string()));
+        Ok(())
+    }
+
+    async fn is_nsfw(&self, channel: u64) -> bool {
+        self.nsfw.contains(&channel)
+    }
+
+    async fn member_avatar(&self, _guild: u64, _user: u64) -> Option<String> {
+        None
+    }
+
+    async fn fetch(&self, url: &str) -> Result<super::surface::Fetched, CommandError> {
+        self.pages.get(url).cloned().ok_or_else(|| CommandError::Transient(format!("could not fetch {}", url)))
+    }
+}
+
+//a message as discord would send it, mentions are picked out of the content
+fn fake_message(id: u64, channel: u64, guild: Option<u64>, author: u64, content: &str) -> serenity::model::channel::Message {
+    let user = |id: u64| serde_json::json!({"id": id.to_string(), "username": format!("user{}", id), "discriminator": "0001", "avatar": null});
+    let mentions: Vec<serde_json::Value> = content.split_whitespace()
+        .filter_map(|word| word.strip_prefix("<@")?.trim_start_matches('!').strip_suffix('>')?.parse().ok())
+        .map(user)
+        .collect();
+    serde_json::from_value(serde_json::json!({
+        "id": id.to_string(),
+        "channel_id": channel.to_string(),
+        "guild_id": guild.map(|guild| guild.to_string()),
+        "author": user(author),
+        "content": content,
+        "timestamp": "2021-06-11T12:00:00+00:00",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": mentions,
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": [],
+        "pinned": false,
+        "type": 0,
+    })).unwrap()
+}
+
+//the bot, as main sets it up, talking to chat and keeping its files in a fresh folder
+fn fake_bot(name: &str, chat: std::sync::Arc<FakeChat>, owners: &[u64]) -> (serenity::client::Context, super::TracedFramework) {
+    use std::sync::Arc;
+    let dir = std::env::temp_dir().join(format!("shawty_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let settings = settings::resolve(settings::SettingsFile {data_dir: Some(dir.to_str().unwrap().to_string()), ..Default::default()}, Default::default()).unwrap();
+    let owners: std::collections::HashSet<u64> = owners.iter().copied().collect();
+    let framework = super::framework(Some(serenity::model::id::UserId(chat.me)), &owners);
+    let mut data = serenity::prelude::TypeMap::new();
+    super::load_state(&mut data, settings, owners, Arc::new(super::clock::SystemClock::local()));
+    data.insert::<super::BotLifecycle>(Arc::new(super::lifecycle::Lifecycle::default()));
+    data.insert::<super::Chat>(chat);
+    let ctx = serenity::client::Context {
+        data: Arc::new(serenity::prelude::RwLock::new(data)),
+        shard: serenity::client::bridge::gateway::ShardMessenger::new(futures::channel::mpsc::unbounded().0),
+        shard_id: 0,
+        http: Arc::new(serenity::http::Http::new_with_token("not a token")),
+        cache: Arc::new(serenity::cache::Cache::default()),
+    };
+    (ctx, framework)
+}
+
+//what serenity does with every message: the commands get a look, then the event handler
+async fn deliver(ctx: &serenity::client::Context, framework: &super::TracedFramework, msg: serenity::model::channel::Message) {
+    use serenity::client::EventHandler;
+    use serenity::framework::Framework;
+    framework.dispatch(ctx.clone(), msg.clone()).await;
+    super::Handler.message(ctx.clone(), msg).await;
+}
+
+#[tokio::test]
+async fn test_fake_flip_and_mock() {
+    let chat = std::sync::Arc::new(FakeChat {me: 1, ..Default::default()});
+    let (ctx, framework) = fake_bot("mock", chat.clone(), &[2]);
+    deliver(&ctx, &framework, fake_message(101, 10, None, 7, "!flip")).await;
+    assert!(chat.said() == ["Heads"] || chat.said() == ["Tails"], "{:?}", chat.said());
+    //the bot and its owners can't be mocked, and the reply is only for whoever asked
+    deliver(&ctx, &framework, fake_message(102, 10, None, 7, "!mock <@42> <@2> <@1>")).await;
+    assert_eq!(chat.sent().len(), 1);
+    for (i, content) in ["one", "two", "three", "four"].iter().enumerate() {
+        deliver(&ctx, &framework, fake_message(103 + i as u64, 10, None, 42, content)).await;
+    }
+    //three messages, in random case
+    let mocked: Vec<String> = chat.said()[1..].iter().map(|said| said.to_lowercase()).collect();
+    assert_eq!(mocked, ["one", "two", "three"]);
+    //nobody left to mock
+    deliver(&ctx, &framework, fake_message(107, 10, None, 7, "!mock <@1>")).await;
+    assert_eq!(chat.reactions(), vec![(107, "❌".to_string())]);
+    assert_eq!(chat.said().last().unwrap(), "nobody to mock");
+}
+
+#[tokio::test]
+async fn test_fake_remind() {
+    let chat = std::sync::Arc::new(FakeChat {me: 1, ..Default::default()});
+    let (ctx, framework) = fake_bot("remind", chat.clone(), &[]);
+    deliver(&ctx, &framework, fake_message(201, 10, None, 7, "!remind in 2 hours check the oven")).await;
+    //confirmed as a reply to the message, with a 🕑 for others to join in
+    let (channel, replied_to, confirmation) = chat.sent().remove(0);
+    assert_eq!((channel, replied_to), (10, Some(201)));
+    assert!(confirmation.content.starts_with("I will remind you"));
+    assert_eq!(chat.reactions(), vec![(5_000, "🕑".to_string())]);
+    chat.reacted.lock().unwrap().insert(5_000, vec![1, 99]);
+    let now = chrono::Local::now().naive_local();
+    //not due yet
+    super::fire_due_reminders(&ctx, 1, now + chrono::Duration::hours(1)).await;
+    assert_eq!(chat.sent().len(), 1);
+    super::fire_due_reminders(&ctx, 1, now + chrono::Duration::hours(3)).await;
+    let sent = chat.sent();
+    assert_eq!((sent[1].1, sent[1].2.content.as_str()), (Some(201), "Reminding you of this message"));
+    //everyone who reacted but the bot
+    assert_eq!(sent[2].2.content, "<@99>");
+    assert!(ctx.data.read().await.get::<super::ReminderList>().unwrap().is_empty());
+    //and it doesn't go off twice
+    super::fire_due_reminders(&ctx, 1, now + chrono::Duration::hours(4)).await;
+    assert_eq!(chat.sent().len(), 3);
+    deliver(&ctx, &framework, fake_message(202, 10, None, 7, "!remind whenever")).await;
+    assert_eq!(chat.said().last().unwrap(), "I couldn't find a time in that");
+}
+
+#[tokio::test]
+async fn test_fake_dispatch_errors() {
+    let chat = std::sync::Arc::new(FakeChat {me: 1, ..Default::default()});
+    let (ctx, framework) = fake_bot("dispatch", chat.clone(), &[2]);
+    //the wrong number of arguments gets the same ❌ as any other refusal, with how it should have been typed
+    deliver(&ctx, &framework, fake_message(301, 10, None, 2, "!say")).await;
+    assert_eq!(chat.reactions(), vec![(301, "❌".to_string())]);
+    assert_eq!(chat.said().last().unwrap(), "that needs at least 1 argument, you gave 0, it goes `!say [#channel] <message>`");
+    //subcommands find their own usage
+    deliver(&ctx, &framework, fake_message(302, 10, None, 2, "!activity remove 1 2")).await;
+    assert_eq!(chat.reactions().last().unwrap(), &(302, "❌".to_string()));
+    assert_eq!(chat.said().last().unwrap(), "that takes at most 1 argument, you gave 2, it goes `!activity remove <number>`");
+    //and so do failed checks
+    deliver(&ctx, &framework, fake_message(303, 10, None, 7, "!say hi")).await;
+    assert_eq!(chat.reactions().last().unwrap(), &(303, "❌".to_string()));
+    assert_eq!(chat.said().last().unwrap(), "you need to be a bot owner for that");
+}
+
+#[tokio::test]
+async fn test_fake_checkem_set() {
+    let chat = std::sync::Arc::new(FakeChat {me: 1, ..Default::default()});
+    let (ctx, framework) = fake_bot("checkem_set", chat.clone(), &[2]);
+    //plain text isn't saved, it would only fail to react on every get
+    deliver(&ctx, &framework, fake_message(321, 10, Some(5), 2, "!checkem set quads hello")).await;
+    assert_eq!(chat.reactions(), vec![(321, "❌".to_string())]);
+    assert_eq!(chat.said().last().unwrap(), "'hello' isn't an emoji, use an emoji or a custom one");
+    assert!(ctx.data.read().await.get::<super::GuildConfigs>().unwrap().get(&5).is_none_or(|config| config.checkem_emoji.is_empty()));
+    deliver(&ctx, &framework, fake_message(324, 10, Some(5), 2, "!checkem set quads 🎉")).await;
+    assert_eq!(chat.said().last().unwrap(), "quads will now get 🎉");
+    assert_eq!(ctx.data.read().await.get::<super::GuildConfigs>().unwrap()[&5].checkem_emoji[&Pattern::Quads], "🎉");
+}
+
+#[tokio::test]
+async fn test_background_starts_once() {
+    let chat = std::sync::Arc::new(FakeChat {me: 1, ..Default::default()});
+    let (ctx, _) = fake_bot("background", chat, &[]);
+    assert!(super::start_background(ctx.clone()).await);
+    //a reconnect fires ready again, that mustn't start a second set of loops
+    assert!(!super::start_background(ctx.clone()).await);
+    assert!(!super::start_background(ctx).await);
+}
+
+#[tokio::test]
+async fn test_reload_data() {
+    let chat = std::sync::Arc::new(FakeChat {me: 1, ..Default::default()});
+    let (ctx, _) = fake_bot("reload_data", chat, &[]);
+    let dir = super::data_dir(&*ctx.data.read().await);
+    //nothing saved yet isn't a failure
+    assert!(super::reload_data(&ctx.data).await.is_empty());
+    //a config saved before checkem_reactions was renamed, and still no gets.json or bonk_stats.json
+    std::fs::write(format!("{}/guild_config.json", dir), r#"{"5": {"disabled": ["checkem"]}}"#).unwrap();
+    assert!(super::reload_data(&ctx.data).await.is_empty());
+    {
+        let data = ctx.data.read().await;
+        let config = &data.get::<super::GuildConfigs>().unwrap()[&5];
+        assert!(!config.feature_enabled("checkem_reactions", 1));
+        assert!(config.feature_enabled("checkem", 1));
+        assert!(data.get::<super::GetsTracker>().unwrap().records.is_empty());
+    }
+    //a broken file is reported and what's in memory stays
+    std::fs::write(format!("{}/gets.json", dir), "{").unwrap();
+    let errors = super::reload_data(&ctx.data).await;
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].contains("gets.json"));
+    assert!(ctx.data.read().await.get::<super::GuildConfigs>().unwrap().contains_key(&5));
+}
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut png = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::new_rgba8(width, height).write_to(&mut png, image::ImageOutputFormat::Png).unwrap();
+    png.into_inner()
+}
+
+#[tokio::test]
+async fn test_fake_bonk() {
+    let author = fake_message(0, 10, None, 7, "").author;
+    let mut chat = FakeChat {me: 1, ..Default::default()};
+    let image = |body: Vec<u8>| super::surface::Fetched {status: 200, content_type: Some("image/png".to_string()), body};
+    chat.pages.insert(author.default_avatar_url(), image(png(64, 64)));
+    chat.pages.insert("https://example.com/page".to_string(), super::surface::Fetched {status: 200, content_type: Some("text/html".to_string()), body: png(64, 64)});
+    chat.pages.insert("https://example.com/wide.png".to_string(), image(png(super::avatar::MAX_AVATAR_SIDE + 1, 1)));
+    chat.pages.insert("https://example.com/junk.png".to_string(), image(b"not a png".to_vec()));
+    let chat = std::sync::Arc::new(chat);
+    let (ctx, framework) = fake_bot("bonk", chat.clone(), &[]);
+    //nobody mentioned, so the author gets it
+    deliver(&ctx, &framework, fake_message(301, 10, None, 7, "!bonk")).await;
+    //drawn into the data folder under a name of its own, and gone once it's sent
+    let bonked = chat.sent()[0].2.file.clone().unwrap();
+    let data_dir = super::bot_settings(&ctx).await.data_dir;
+    assert!(bonked.starts_with(&format!("{}/bonk-", data_dir)), "{}", bonked);
+    assert!(!std::path::Path::new(&bonked).exists());
+    let records = ctx.data.read().await.get::<super::BonkStats>().unwrap().clone();
+    assert_eq!(records.len(), 1);
+    assert_eq!((records[0].bonker, records[0].bonkee), (7, 7));
+    //an image that can't be downloaded is worth trying again
+    deliver(&ctx, &framework, fake_message(302, 10, None, 7, "!bonk https://example.com/cat.png")).await;
+    assert_eq!(chat.reactions(), vec![(302, "⏳".to_string())]);
+    assert!(chat.said().last().unwrap().ends_with("try again in a bit"));
+    //something that isn't an image, or is too big to be decoded, isn't
+    deliver(&ctx, &framework, fake_message(303, 10, None, 7, "!bonk https://example.com/page")).await;
+    assert!(chat.said().last().unwrap().ends_with("isn't an image"));
+    deliver(&ctx, &framework, fake_message(304, 10, None, 7, "!bonk https://example.com/wide.png")).await;
+    assert!(chat.said().last().unwrap().starts_with("that image is too big"));
+    deliver(&ctx, &framework, fake_message(305, 10, None, 7, "!bonk https://example.com/junk.png")).await;
+    assert!(chat.said().last().unwrap().starts_with("could not parse avatar image"));
+    assert_eq!(chat.reactions()[1..], [(303, "❌".to_string()), (304, "❌".to_string()), (305, "❌".to_string())]);
+    let history = |ctx: serenity::client::Context, channel| async move {
+        ctx.data.read().await.get::<super::BonkHistory>().unwrap().get(&channel).map_or(0, |recent| recent.len())
+    };
+    assert_eq!(history(ctx.clone(), 10).await, 1);
+    //a bonk that couldn't be saved wasn't posted, so it doesn't count toward the channel's recent templates
+    std::fs::remove_dir_all(&data_dir).unwrap();
+    deliver(&ctx, &framework, fake_message(306, 20, None, 7, "!bonk")).await;
+    assert_eq!(chat.reactions().last().unwrap(), &(306, "💥".to_string()));
+    assert_eq!(history(ctx.clone(), 20).await, 0);
+    assert_eq!(ctx.data.read().await.get::<super::BonkStats>().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_fetch_limits() {
+    use super::surface::{check_url, is_public, read_capped};
+    //links to the bot's own machine, the local network or the cloud metadata service are turned down before anything is sent
+    for url in [
+        "http://127.0.0.1/", "http://127.1.2.3:8080/admin/reload", "http://localhost:8080/metrics", "http://0.0.0.0/",
+        "http://10.1.2.3/cat.png", "http://172.16.5.4/", "http://192.168.1.1/", "http://100.64.0.1/", "http://169.254.169.254/latest/meta-data/",
+        "http://[::1]/", "http://[::]/", "http://[fe80::1]/", "http://[fd12:3456::1]/", "http://[::ffff:127.0.0.1]/", "http://[::ffff:10.0.0.1]/",
+        "http://[64:ff9b::a9fe:a9fe]/", "http://2130706433/", "http://0x7f.1/",
+    ].iter() {
+        match check_url(url).await {
+            Err(CommandError::User(message)) => assert!(message.contains("local or private"), "{}: {}", url, message),
+            other => panic!("{} gave {:?}", url, other),
+        }
+    }
+    for url in ["file:///etc/passwd", "ftp://example.com/cat.png", "gopher://example.com/", "not a link", "http://"].iter() {
+        assert!(matches!(check_url(url).await, Err(CommandError::User(_))), "{}", url);
+    }
+    //ip literals don't need a lookup, so these work offline
+    assert_eq!(check_url("https://93.184.216.34/cat.png").await.unwrap().as_str(), "https://93.184.216.34/cat.png");
+    assert!(check_url("http://[2606:2800:220:1:248:1893:25c8:1946]/").await.is_ok());
+    for ip in ["1.1.1.1", "8.8.8.8", "2606:4700::1111", "64:ff9b::808:808"].iter() {
+        assert!(is_public(ip.parse().unwrap()), "{}", ip);
+    }
+    for ip in ["192.0.2.1", "198.18.0.1", "224.0.0.1", "255.255.255.255", "240.0.0.1", "2001:db8::1", "ff02::1", "fec0::1"].iter() {
+        assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+    }
+    //the body stops being read once it's over the limit
+    let response = |body: Vec<u8>| reqwest::Response::from(hyper::Response::new(body));
+    assert_eq!(read_capped(response(vec![7; 1024]), 1024).await.unwrap().len(), 1024);
+    assert!(matches!(read_capped(response(vec![7; 1025]), 1024).await, Err(CommandError::User(_))));
+}
+
+#[tokio::test]
+async fn test_fake_messages() {
+    let mut chat = FakeChat {me: 1, ..Default::default()};
+    chat.nsfw.insert(66);
+    chat.pages.insert("https://nhentai.net/api/gallery/123456".to_string(), super::surface::Fetched {
+        status: 200,
+        content_type: Some("application/json".to_string()),
+        body: br#"{"title": {"english": "a title"}, "tags": [{"id": 1}]}"#.to_vec(),
+    });
+    let chat = std::sync::Arc::new(chat);
+    let (ctx, framework) = fake_bot("messages", chat.clone(), &[]);
+    //check 'em
+    deliver(&ctx, &framework, fake_message(1_777, 10, None, 7, "look")).await;
+    assert_eq!(chat.reactions().len(), 1);
+    assert_eq!(chat.reactions()[0].0, 1_777);
+    //the gallery only looks in nsfw channels
+    deliver(&ctx, &framework, fake_message(9_123_456, 10, None, 7, "hi")).await;
+    assert!(chat.sent().is_empty());
+    deliver(&ctx, &framework, fake_message(9_123_456, 66, None, 7, "hi")).await;
+    assert_eq!(chat.said(), ["a title\n<https://nhentai.net/g/123456>"]);
+    //a page that isn't there says nothing
+    deliver(&ctx, &framework, fake_message(9_123_450, 66, None, 7, "hi")).await;
+    assert_eq!(chat.sent().len(), 1);
+    //a server without the reactions still gets to use !checkem to set them up
+    let mut config = GuildConfig::default();
+    config.set_feature("checkem_reactions", None, false).unwrap();
+    config.set_feature("bonk", None, false).unwrap();
+    ctx.data.write().await.insert::<super::GuildConfigs>(vec![(5, config)].into_iter().collect());
+    let reactions = chat.reactions().len();
+    deliver(&ctx, &framework, fake_message(2_777, 10, Some(5), 7, "look")).await;
+    assert_eq!(chat.reactions().len(), reactions);
+    assert!(super::before(&ctx, &fake_message(2_778, 10, Some(5), 7, "!checkem list"), "list").await);
+    assert!(!super::before(&ctx, &fake_message(2_779, 10, Some(5), 7, "!bonk"), "bonk").await);
+}
+
+//a clock that stays put until it's moved, read in tz
+struct FixedClock<Tz> {
+    now: std::sync::Mutex<chrono::DateTime<chrono::Utc>>,
+    tz: Tz,
+}
+
+impl<Tz> FixedClock<Tz> {
+    fn new(now: chrono::DateTime<chrono::Utc>, tz: Tz) -> FixedClock<Tz> {
+        FixedClock {now: std::sync::Mutex::new(now), tz}
+    }
+
+    fn advance(&self, by: chrono::Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now = *now + by;
+    }
+}
+
+impl<Tz: chrono::TimeZone + Send + Sync> super::clock::Clock for FixedClock<Tz> {
+    fn now(&self) -> chrono::DateTime<chrono::Utc> {
+        *self.now.lock().unwrap()
+    }
+
+    fn local_naive(&self) -> chrono::NaiveDateTime {
+        self.now().with_timezone(&self.tz).naive_local()
+    }
+
+    fn find_time(&self, text: &str) -> Option<chrono::NaiveDateTime> {
+        super::scheduler::find_time(text, &self.now().with_timezone(&self.tz))
+    }
+}
+
+//us eastern time: -5, or -4 from 2am on the second sunday in march until 2am on the first sunday in november
+#[derive(Debug, Clone, Copy)]
+struct Eastern;
+
+impl Eastern {
+    fn est() -> chrono::FixedOffset {
+        chrono::FixedOffset::west(5 * 3600)
+    }
+
+    fn edt() -> chrono::FixedOffset {
+        chrono::FixedOffset::west(4 * 3600)
+    }
+
+    //the nth sunday of the month, at hour utc
+    fn sunday(year: i32, month: u32, nth: i64, hour: u32) -> chrono::NaiveDateTime {
+        use chrono::Datelike;
+        let first = chrono::NaiveDate::from_ymd(year, month, 1);
+        let to_sunday = (7 - first.weekday().num_days_from_sunday() as i64) % 7;
+        (first + chrono::Duration::days(to_sunday + 7 * (nth - 1))).and_hms(hour, 0, 0)
+    }
+}
+
+impl chrono::TimeZone for Eastern {
+    type Offset = chrono::FixedOffset;
+
+    fn from_offset(_: &chrono::FixedOffset) -> Eastern {
+        Eastern
+    }
+
+    fn offset_from_local_date(&self, local: &chrono::NaiveDate) -> chrono::LocalResult<chrono::FixedOffset> {
+        self.offset_from_local_datetime(&local.and_hms(0, 0, 0))
+    }
+
+    fn offset_from_local_datetime(&self, local: &chrono::NaiveDateTime) -> chrono::LocalResult<chrono::FixedOffset> {
+        //whichever offsets give back the same local time
+        let fits = |offset: chrono::FixedOffset| self.offset_from_utc_datetime(&(*local - chrono::Duration::seconds(offset.local_minus_utc() as i64))) == offset;
+        match (fits(Eastern::edt()), fits(Eastern::est())) {
+            (true, true) => chrono::LocalResult::Ambiguous(Eastern::edt(), Eastern::est()),
+            (true, false) => chrono::LocalResult::Single(Eastern::edt()),
+            (false, true) => chrono::LocalResult::Single(Eastern::est()),
+            (false, false) => chrono::LocalResult::None,
+        }
+    }
+
+    fn offset_from_utc_date(&self, utc: &chrono::NaiveDate) -> chrono::FixedOffset {
+        self.offset_from_utc_datetime(&utc.and_hms(0, 0, 0))
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &chrono::NaiveDateTime) -> chrono::FixedOffset {
+        use chrono::Datelike;
+        let year = utc.year();
+        if *utc >= Eastern::sunday(year, 3, 2, 7) && *utc < Eastern::sunday(year, 11, 1, 6) {
+            Eastern::edt()
+        }
+        else {
+            Eastern::est()
+        }
+    }
+}
+
+fn at(text: &str) -> chrono::NaiveDateTime {
+    chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap()
+}
+
+fn utc(text: &str) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::from_utc(at(text), chrono::Utc)
+}
+
+#[test]
+fn test_scheduler_offsets() {
+    use chrono::Duration;
+    use super::scheduler::{find_time, FURTHEST_DAYS};
+    //a tuesday
+    let now = utc("2021-06-15 10:00");
+    let units = [
+        ("millisecond", Duration::milliseconds(1)), ("milliseconds", Duration::milliseconds(1)),
+        ("second", Duration::seconds(1)), ("seconds", Duration::seconds(1)), ("sec", Duration::seconds(1)), ("secs", Duration::seconds(1)),
+        ("minute", Duration::minutes(1)), ("minutes", Duration::minutes(1)), ("min", Duration::minutes(1)), ("mins", Duration::minutes(1)),
+        ("minaltatitatude", Duration::minutes(1)),
+        ("hour", Duration::hours(1)), ("hours", Duration::hours(1)),
+        ("day", Duration::days(1)), ("days", Duration::days(1)),
+        ("week", Duration::weeks(1)), ("weeks", Duration::weeks(1)),
+        ("month", Duration::weeks(4)), ("months", Duration::weeks(4)),
+        ("year", Duration::days(365)), ("years", Duration::days(365)),
+        ("decade", Duration::days(3650)), ("decades", Duration::days(3650)),
+        ("century", Duration::days(36500)), ("centuries", Duration::days(36500)),
+    ];
+    let forms = ["in {n} {unit}", "{n} {unit} from now", "{n}{unit}", "remind me in {n} {unit} to stretch", "IN {n} {unit}"];
+    let mut checked = 0;
+    for (unit, length) in units.iter() {
+        for n in [1, 2, 3, 7, 45].iter() {
+            for form in forms.iter() {
+                let phrase = form.replace("{n}", &n.to_string()).replace("{unit}", unit);
+                //45 centuries is past the furthest anything can be scheduled
+                let expected = Some(now.naive_utc() + *length * *n).filter(|_| *length * *n <= Duration::days(FURTHEST_DAYS));
+                assert_eq!(find_time(&phrase, &now), expected, "{}", phrase);
+                checked += 1;
+            }
+        }
+        let phrase = format!("next {}", unit);
+        assert_eq!(find_time(&phrase, &now), Some(now.naive_utc() + *length), "{}", phrase);
+        checked += 1;
+    }
+    assert!(checked > 600);
+    //the first one that's in the future wins
+    assert_eq!(find_time("in 0 days or 3 hours", &now), Some(at("2021-06-15 13:00")));
+    for nothing in ["in a few fortnights", "next time", "in a while", "", "soon", "check the oven"].iter() {
+        assert_eq!(find_time(nothing, &now), None, "{}", nothing);
+    }
+}
+
+#[test]
+fn test_scheduler_exact() {
+    use super::scheduler::find_time;
+    let now = utc("2021-06-15 10:00");
+    let table = [
+        //dates get the current time if they don't say
+        ("2021/07/04", Some("2021-07-04 10:00")),
+        ("2021-07-04", Some("2021-07-04 10:00")),
+        ("2021.07.04", Some("2021-07-04 10:00")),
+        ("2021_07_04", Some("2021-07-04 10:00")),
+        ("20210704", Some("2021-07-04 10:00")),
+        ("2021/07/04 5:30pm", Some("2021-07-04 17:30")),
+        ("2021/07/04 5:30 pm", Some("2021-07-04 17:30")),
+        ("2021/07/04 5:30 p.m.", Some("2021-07-04 17:30")),
+        ("2021/07/04 5:30am", Some("2021-07-04 05:30")),
+        ("2021/07/04 05:30 A.M.", Some("2021-07-04 05:30")),
+        ("2021/07/04 17:30", Some("2021-07-04 17:30")),
+        ("2021/07/04 12:30pm", Some("2021-07-04 12:30")),
+        ("2021/07/04 11:59 p", Some("2021-07-04 23:59")),
+        ("07/04/2021", Some("2021-07-04 10:00")),
+        ("07-04-2021 8:15", Some("2021-07-04 08:15")),
+        ("dentist 07/04/2021 at 9:00am", Some("2021-07-04 09:00")),
+        //two digit years, whichever part is over 12 is the year
+        ("210704", Some("2021-07-04 10:00")),
+        ("21/07/04", Some("2021-07-04 10:00")),
+        ("12/25/21", Some("2021-12-25 10:00")),
+        ("12-25-21 6:00pm", Some("2021-12-25 18:00")),
+        ("990101", Some("2099-01-01 10:00")),
+        //no year, this year if it's still coming up
+        ("07/04", Some("2021-07-04 10:00")),
+        ("12/25", Some("2021-12-25 10:00")),
+        ("12/25 7:00am", Some("2021-12-25 07:00")),
+        ("06/15 11:00", Some("2021-06-15 11:00")),
+        ("06/15 9:00pm", Some("2021-06-15 21:00")),
+        //or next year
+        ("01/15", Some("2022-01-15 10:00")),
+        ("06/14", Some("2022-06-14 10:00")),
+        //and for the 29th of february, the next year that has one
+        ("02/29", Some("2024-02-29 10:00")),
+        //not dates
+        ("13/45", None),
+        ("99/99", None),
+    ];
+    for (phrase, expected) in table.iter() {
+        assert_eq!(find_time(phrase, &now), expected.map(at), "{}", phrase);
+    }
+    //the year changing under it
+    let now = utc("2021-12-31 23:30");
+    let table = [
+        ("01/01", Some("2022-01-01 23:30")),
+        ("12/31 11:45pm", Some("2021-12-31 23:45")),
+        ("in 1 hour", Some("2022-01-01 00:30")),
+        ("in 45 minutes", Some("2022-01-01 00:15")),
+        ("next day", Some("2022-01-01 23:30")),
+        //12am is midnight, 12pm is noon
+        ("2022/01/01 12:05am", Some("2022-01-01 00:05")),
+        ("2022/01/01 12:05pm", Some("2022-01-01 12:05")),
+        ("2022/01/01 00:05", Some("2022-01-01 00:05")),
+        ("12/30", Some("2022-12-30 23:30")),
+        ("220101", Some("2022-01-01 23:30")),
+    ];
+    for (phrase, expected) in table.iter() {
+        assert_eq!(find_time(phrase, &now), expected.map(at), "{}", phrase);
+    }
+    //every way of writing a date, with every way of writing a time
+    let now = utc("2021-06-15 10:00");
+    let seps = ["/", "-", ".", "_", "\\", " ", ""];
+    let dates: Vec<(String, &str)> = seps.iter().flat_map(|sep| vec![
+        (["2021", "07", "04"].join(sep), "2021-07-04"),
+        (["07", "04", "2021"].join(sep), "2021-07-04"),
+        (["21", "07", "04"].join(sep), "2021-07-04"),
+        (["12", "25", "21"].join(sep), "2021-12-25"),
+        (["07", "04"].join(sep), "2021-07-04"),
+    ]).collect();
+    let times = [
+        ("", "10:00"), ("5:30pm", "17:30"), ("5:30 pm", "17:30"), ("5:30 p.m.", "17:30"), ("5:30am", "05:30"), ("05:30 A.M.", "05:30"),
+        ("17:30", "17:30"), ("12:05am", "00:05"), ("12:05pm", "12:05"), ("11:59 p", "23:59"), ("9:00", "09:00"),
+    ];
+    let mut checked = 0;
+    for (date, day) in dates.iter() {
+        for (time, clock) in times.iter() {
+            //"210704 05:30" really is 2107/04/05, so the time always comes after a word
+            for phrase in [format!("{} at {}", date, time), format!("dentist on {} at {}", date, time)].iter() {
+                assert_eq!(find_time(phrase, &now), Some(at(&format!("{} {}", day, clock))), "{}", phrase);
+                checked += 1;
+            }
+        }
+    }
+    assert!(checked > 700);
+}
+
+#[test]
+fn test_scheduler_fuzzy() {
+    use super::scheduler::find_time;
+    //a tuesday morning
+    let now = utc("2021-06-15 10:00");
+    let table = [
+        ("tomorrow", Some("2021-06-16 00:00")),
+        ("tomorrow at 5pm", Some("2021-06-16 17:00")),
+        ("tomorrow at noon", Some("2021-06-16 12:00")),
+        ("tomorrow morning", Some("2021-06-16 09:00")),
+        ("at 11pm", Some("2021-06-15 23:00")),
+        ("at 9:45pm", Some("2021-06-15 21:45")),
+        ("6pm", Some("2021-06-15 18:00")),
+        ("at noon", Some("2021-06-15 12:00")),
+        ("in the evening", Some("2021-06-15 18:00")),
+        ("tonight at 8", Some("2021-06-15 20:00")),
+        //morning times that have gone by are taken to mean the evening
+        ("at 7:30", Some("2021-06-15 19:30")),
+        ("at 6", Some("2021-06-15 18:00")),
+        //including the start of today
+        ("tuesday", Some("2021-06-15 12:00")),
+        ("friday", Some("2021-06-18 00:00")),
+        ("next friday", Some("2021-06-25 00:00")),
+        ("on friday at noon", Some("2021-06-18 12:00")),
+        ("july 4th", Some("2021-07-04 00:00")),
+        ("july 4th at 8pm", Some("2021-07-04 20:00")),
+        ("december 25", Some("2021-12-25 00:00")),
+        ("yesterday", None),
+        ("check the oven", None),
+    ];
+    for (phrase, expected) in table.iter() {
+        assert_eq!(find_time(phrase, &now), expected.map(at), "{}", phrase);
+    }
+    //every kind of day, with every kind of time of day
+    let days = [
+        ("today", "2021-06-15"), ("tomorrow", "2021-06-16"),
+        ("thursday", "2021-06-17"), ("thu", "2021-06-17"), ("friday", "2021-06-18"), ("fri", "2021-06-18"), ("saturday", "2021-06-19"), ("sat", "2021-06-19"),
+        ("this friday", "2021-06-18"), ("next thursday", "2021-06-24"), ("next friday", "2021-06-25"), ("next saturday", "2021-06-26"),
+        ("july 4th", "2021-07-04"), ("jul 20th", "2021-07-20"), ("august 1st", "2021-08-01"), ("aug 31st", "2021-08-31"), ("september 2nd", "2021-09-02"),
+        ("october 3rd", "2021-10-03"), ("november 11th", "2021-11-11"), ("december 25th", "2021-12-25"), ("dec 31st", "2021-12-31"),
+    ];
+    let times = [
+        ("", "00:00"), ("at 5pm", "17:00"), ("at 9:45pm", "21:45"), ("at 8", "20:00"), ("at 11am", "11:00"), ("at noon", "12:00"),
+        ("in the morning", "09:00"), ("in the afternoon", "14:00"), ("in the evening", "18:00"), ("tonight", "21:00"), ("at midnight", "00:00"),
+    ];
+    let mut checked = 0;
+    for (day, date) in days.iter() {
+        for (time, clock) in times.iter() {
+            let mut expected = at(&format!("{} {}", date, clock));
+            //only today's can have gone by, and then it's the evening that's meant
+            if expected < now.naive_utc() {
+                expected += chrono::Duration::hours(12);
+            }
+            for phrase in [format!("{} {}", day, time), format!("remind me {} {} to call", time, day)].iter() {
+                assert_eq!(find_time(phrase, &now), Some(expected).filter(|expected| *expected > now.naive_utc()), "{}", phrase);
+                checked += 1;
+            }
+        }
+    }
+    assert!(checked > 400);
+    //months that have gone by this year aren't looked for next year
+    for month in ["january 5th", "feb 1st", "march 3rd", "april 20th", "may 31st", "june 14th"].iter() {
+        assert_eq!(find_time(month, &now), None, "{}", month);
+    }
+    //date_time_parser unwraps whatever it finds, so impossible dates and times are taken out before it sees them
+    let now = utc("2021-05-31 10:00");
+    let table = [
+        ("june 31", None),
+        ("june 31 at 5pm", Some("2021-05-31 17:00")),
+        ("13/31 tomorrow", Some("2021-06-01 00:00")),
+        //there's no 31st of april, but there's still a time in it
+        ("last month at 9pm", Some("2021-05-31 21:00")),
+        ("at 25:00", None),
+        ("at 7:99 tomorrow", Some("2021-06-01 00:00")),
+        ("at 13pm", None),
+        //digits from other scripts match its \d but don't parse
+        ("𑓐{", None),
+        ("at ٥pm", None),
+        ("45", None),
+    ];
+    for (phrase, expected) in table.iter() {
+        assert_eq!(find_time(phrase, &now), expected.map(at), "{}", phrase);
+    }
+}
+
+#[test]
+fn test_scheduler_dst() {
+    use chrono::TimeZone;
+    use super::scheduler::find_time;
+    //1:30am on the night the clocks go forward, 2:00 doesn't happen
+    let now = Eastern.from_local_datetime(&at("2021-03-14 01:30")).unwrap();
+    let table = [
+        //an hour of real time
+        ("in 1 hour", Some("2021-03-14 03:30")),
+        ("in 90 minutes", Some("2021-03-14 04:00")),
+        //the same time on the clock
+        ("in 1 day", Some("2021-03-15 01:30")),
+        ("next week", Some("2021-03-21 01:30")),
+        //a time that doesn't exist is read as if the clocks hadn't changed
+        ("2021/03/14 2:30am", Some("2021-03-14 03:30")),
+        ("2021/03/14 3:30am", Some("2021-03-14 03:30")),
+        ("2021/03/14 1:45am", Some("2021-03-14 01:45")),
+    ];
+    for (phrase, expected) in table.iter() {
+        assert_eq!(find_time(phrase, &now), expected.map(at), "{}", phrase);
+    }
+    //a day that's going to be 23 hours long
+    let now = Eastern.from_local_datetime(&at("2021-03-13 12:00")).unwrap();
+    assert_eq!(find_time("in 1 day", &now), Some(at("2021-03-14 12:00")));
+    assert_eq!(find_time("in 24 hours", &now), Some(at("2021-03-14 13:00")));
+    //1:50am the first time round, on the night the clocks go back and 1:00 to 2:00 happens twice
+    let now = Eastern.from_local_datetime(&at("2021-11-07 01:50")).earliest().unwrap();
+    let table = [
+        //1:10 the second time round
+        ("in 20 minutes", Some("2021-11-07 01:10")),
+        ("in 1 hour", Some("2021-11-07 01:50")),
+        ("in 2 hours", Some("2021-11-07 02:50")),
+        ("in 1 day", Some("2021-11-08 01:50")),
+        ("2021/11/07 1:55am", Some("2021-11-07 01:55")),
+        ("2021/11/07 3:00am", Some("2021-11-07 03:00")),
+    ];
+    for (phrase, expected) in table.iter() {
+        assert_eq!(find_time(phrase, &now), expected.map(at), "{}", phrase);
+    }
+    //in the summer it's 4 hours behind
+    let now = Eastern.from_utc_datetime(&at("2021-06-15 14:00"));
+    assert_eq!(find_time("in 1 hour", &now), Some(at("2021-06-15 11:00")));
+    assert_eq!(find_time("06/15 9:00pm", &now), Some(at("2021-06-15 21:00")));
+}
+
+//the things find_time promises whatever it's given: no panics, and anything it finds is in the future but not too far in it
+fn check_found<Tz: chrono::TimeZone>(phrase: &str, now: &chrono::DateTime<Tz>) -> Result<Option<chrono::NaiveDateTime>, proptest::test_runner::TestCaseError> {
+    use super::scheduler::{find_time, parse_fuzzy, FURTHEST_DAYS};
+    //find_time catches date_time_parser panicking, so this is what shows whether its copied patterns still keep it from happening
+    parse_fuzzy(phrase, now.naive_local());
+    let found = match find_time(phrase, now) {
+        Some(found) => found,
+        None => return Ok(None),
+    };
+    //when the clocks go back a wall clock time can be either, the later one has to be after now
+    let when = now.timezone().from_local_datetime(&found).latest();
+    proptest::prop_assert!(matches!(&when, Some(when) if when > now), "{:?} gave {} at {:?}", phrase, found, now);
+    //a day of slack for the clock changing between now and then
+    let furthest = now.naive_local() + chrono::Duration::days(FURTHEST_DAYS + 1);
+    proptest::prop_assert!(found <= furthest, "{:?} gave {} at {:?}", phrase, found, now);
+    Ok(Some(found))
+}
+
+//somewhere between 1970 and 2200, in utc, eastern and a fixed offset
+fn any_now() -> impl proptest::strategy::Strategy<Value = (chrono::DateTime<chrono::Utc>, chrono::DateTime<Eastern>, chrono::DateTime<chrono::FixedOffset>)> {
+    use chrono::TimeZone;
+    use proptest::strategy::Strategy;
+    (0i64..7_258_118_400, -14 * 60..=14 * 60).prop_map(|(seconds, minutes)| {
+        let now = chrono::Utc.timestamp(seconds, 0);
+        (now, now.with_timezone(&Eastern), now.with_timezone(&chrono::FixedOffset::east(minutes * 60)))
+    })
+}
+
+//arbitrary junk, mostly built out of the things the parsers look for so it gets past the regexes
+fn junk_phrase() -> impl proptest::strategy::Strategy<Value = String> {
+    use proptest::prelude::*;
+    let pieces = vec![
+        "0", "1", "2", "7", "9", "12", "13", "21", "24", "25", "29", "30", "31", "32", "45", "60", "99", "2021", "9999", "2147483647", "2147483648",
+        "99999999999999999999", "/", "-", ".", "_", "\\", ":", " ", "  ", "\n", "a", "p", "am", "pm", "p.m.", "A.M.", "in", "next", "last", "this",
+        "from now", "at", "noon", "morning", "tonight", "second", "minutes", "mins", "hour", "hours", "day", "days", "week", "month", "months", "years",
+        "decade", "centuries", "minaltatitatude", "today", "tomorrow", "yesterday", "mon", "monday", "wed", "friday", "sat", "jan", "february", "june",
+        "sep", "december", "é", "日本", "🔨", "\u{0}",
+    ];
+    let piece = prop_oneof![
+        9 => proptest::sample::select(pieces).prop_map(|piece| piece.to_string()),
+        1 => any::<char>().prop_map(|c| c.to_string()),
+    ];
+    proptest::collection::vec(piece, 1..12).prop_map(|pieces| pieces.concat())
+}
+
+//the shapes date_time_parser itself looks for, with numbers that are sometimes a real date or time and sometimes not
+fn parser_trigger() -> impl proptest::strategy::Strategy<Value = String> {
+    use proptest::prelude::*;
+    let months = vec![
+        "jan", "january", "Feb", "february", "mar", "march", "apr", "april", "may", "jun", "June", "jul", "july", "aug", "august",
+        "sep", "september", "oct", "october", "nov", "November", "dec", "december",
+    ];
+    let meridiem = proptest::sample::select(vec!["", "a", "p", "am", "pm", "AM", " pm"]);
+    let trigger = prop_oneof![
+        (0..10000u32, 0..100u32, 0..100u32).prop_map(|(year, month, day)| format!("{}/{:02}/{:02}", year, month, day)),
+        (0..100u32, 0..100u32, 0..10000u32).prop_map(|(month, day, year)| format!("{}/{}/{}", month, day, year)),
+        (0..100u32, 0..100u32).prop_map(|(month, day)| format!("{}/{}", month, day)),
+        (proptest::sample::select(months), 0..100u32).prop_map(|(month, day)| format!("{} {}", month, day)),
+        (0..1000u32).prop_map(|months| format!("in {} months", months)),
+        proptest::sample::select(vec!["next month", "last month", "this month", "Next Month"]).prop_map(|month| month.to_string()),
+        (0..100u32, 0..100u32, meridiem.clone()).prop_map(|(hour, minute, meridiem)| format!("{}:{:02}{}", hour, minute, meridiem)),
+        (0..100u32, meridiem).prop_map(|(hour, meridiem)| format!("{}{}", hour, meridiem)),
+    ];
+    (proptest::sample::select(vec!["", "at ", "on ", "remind me "]), proptest::collection::vec(trigger, 1..3))
+        .prop_map(|(start, triggers)| format!("{}{}", start, triggers.join(" at ")))
+}
+
+proptest::proptest! {
+    //the fuzzy parser takes a while in a debug build, so these are kept small. the fuzz target is for the long runs
+    #![proptest_config(proptest::prelude::ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn test_scheduler_junk(phrase in junk_phrase(), (now_utc, now_eastern, now_fixed) in any_now()) {
+        check_found(&phrase, &now_utc)?;
+        check_found(&phrase, &now_eastern)?;
+        check_found(&phrase, &now_fixed)?;
+    }
+
+    //what a new version of date_time_parser would most likely change, so a bump it isn't ready for fails here
+    #[test]
+    fn test_scheduler_parser_triggers(phrase in parser_trigger(), (now_utc, now_eastern, now_fixed) in any_now()) {
+        check_found(&phrase, &now_utc)?;
+        check_found(&phrase, &now_eastern)?;
+        check_found(&phrase, &now_fixed)?;
+    }
+
+    #[test]
+    fn test_scheduler_any_text(phrase in "\\PC{0,40}", (now_utc, now_eastern, _) in any_now()) {
+        check_found(&phrase, &now_utc)?;
+        check_found(&phrase, &now_eastern)?;
+    }
+
+    //any date at all, written the unambiguous way
+    #[test]
+    fn test_scheduler_any_date(
+        (year, month, day, hour, minute) in (0..10000, 0..100, 0..100, 0..100, 0..100),
+        meridiem in proptest::sample::select(vec!["", "am", "pm", " p.m."]),
+        (now_utc, now_eastern, _) in any_now(),
+    ) {
+        let phrase = format!("{:04}/{:02}/{:02} {}:{:02}{}", year, month, day, hour, minute, meridiem);
+        check_found(&phrase, &now_utc)?;
+        check_found(&phrase, &now_eastern)?;
+    }
+}
+
+proptest::proptest! {
+    #![proptest_config(proptest::prelude::ProptestConfig::with_cases(256))]
+
+    //a count of any unit is exactly that far ahead, or nothing if it's too far
+    #[test]
+    fn test_scheduler_any_offset(
+        n in proptest::prop_oneof![1..1000i32, 1000..i32::MAX, proptest::strategy::Just(i32::MAX)],
+        (unit, seconds) in proptest::sample::select(vec![("seconds", 1i64), ("hours", 3600), ("weeks", 7 * 86_400), ("years", 365 * 86_400), ("centuries", 36_500 * 86_400)]),
+        form in proptest::sample::select(vec!["in {n} {unit}", "{n} {unit} from now", "{n}{unit}"]),
+        (now, _, _) in any_now(),
+    ) {
+        use chrono::Duration;
+        use super::scheduler::FURTHEST_DAYS;
+        let phrase = form.replace("{n}", &n.to_string()).replace("{unit}", unit);
+        let found = check_found(&phrase, &now)?;
+        //numbers with 4 or more digits can be read as a date, which gets looked for first. i32::MAX can't be one
+        if n < 1000 || n == i32::MAX {
+            let expected = seconds.checked_mul(n as i64)
+                .filter(|seconds| *seconds <= Duration::days(FURTHEST_DAYS).num_seconds())
+                .map(|seconds| now.naive_utc() + Duration::seconds(seconds));
+            //too far off for an offset, though the fuzzy parser can still make something of it
+            if expected.is_some() {
+                proptest::prop_assert_eq!(found, expected, "{}", phrase);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_scheduler_limits() {
+    use chrono::Duration;
+    //each of these panics inside date_time_parser 0.1.0 if it isn't taken out first
+    for now in [utc("2021-01-31 10:00"), utc("2021-05-31 10:00"), utc("2021-12-31 23:00")].iter() {
+        for phrase in ["2021/03/14", "21/03/14", "13/01", "june 31", "feb 30", "in 13 months", "in 1 month", "next month", "last month", "25:00", "7:99", "13pm", "at ٥pm"].iter() {
+            check_found(phrase, now).unwrap();
+        }
+    }
+    assert_eq!(check_found("in 2147483647 centuries", &utc("2021-06-15 10:00")).unwrap(), None);
+    assert_eq!(check_found("2147483647 milliseconds", &utc("2021-06-15 10:00")).unwrap(), Some(at("2021-07-10 06:31")).map(|t| t + Duration::milliseconds(23_647)));
+}
+
+#[tokio::test]
+async fn test_fake_remind_clock() {
+    use chrono::TimeZone;
+    use super::clock::Clock;
+    let chat = std::sync::Arc::new(FakeChat {me: 1, ..Default::default()});
+    let (ctx, framework) = fake_bot("remind_clock", chat.clone(), &[]);
+    //noon the day before the clocks go forward, whatever timezone the machine running the tests is in
+    let clock = std::sync::Arc::new(FixedClock::new(Eastern.from_local_datetime(&at("2021-03-13 12:00")).unwrap().with_timezone(&chrono::Utc), Eastern));
+    ctx.data.write().await.insert::<super::BotClock>(clock.clone());
+    deliver(&ctx, &framework, fake_message(401, 10, None, 7, "!remind in 1 day")).await;
+    let reminders: Vec<chrono::NaiveDateTime> = ctx.data.read().await.get::<super::ReminderList>().unwrap().iter().map(|reminder| reminder.date_time).collect();
+    assert_eq!(reminders, vec![at("2021-03-14 12:00")]);
+    //noon on the clock is only 23 hours away
+    for hour in 1..=24 {
+        clock.advance(chrono::Duration::hours(1));
+        super::fire_due_reminders(&ctx, 1, clock.local_naive()).await;
+        assert_eq!(chat.sent().len(), if hour < 23 {1} else {2}, "after {} hours", hour);
+    }
+    //gets are logged on the same clock
+    deliver(&ctx, &framework, fake_message(1_777, 10, Some(5), 7, "look")).await;
+    let gets = ctx.data.read().await.get::<super::GetsTracker>().unwrap().records.clone();
+    assert_eq!(gets.iter().map(|get| get.time).collect::<Vec<_>>(), vec![at("2021-03-14 13:00")]);
+}
@@ -0,0 +1,194 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use hyper::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use serde_json::{json, Value};
+use serenity::gateway::ConnectionStage;
+use serenity::http::Http;
+use serenity::prelude::{RwLock, TypeMap};
+use tracing::{error, info, warn};
+
+//the reminder loop wakes up every minute, missing a few means it's stuck
+const SCHEDULER_TIMEOUT: Duration = Duration::from_secs(3 * 60);
+
+#[derive(Debug, PartialEq)]
+pub enum Route {
+    Metrics,
+    Health,
+    Reminders,
+    CancelReminder(u64),
+    Mocks,
+    Reload,
+    Config,
+    NotFound,
+}
+
+impl Route {
+    //everything but /metrics and /healthz needs the admin token
+    pub fn is_admin(&self) -> bool {
+        !matches!(self, Route::Metrics | Route::Health | Route::NotFound)
+    }
+}
+
+pub fn route(method: &Method, path: &str) -> Route {
+    let parts: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, parts.as_slice()) {
+        (&Method::GET, ["metrics"]) => Route::Metrics,
+        (&Method::GET, ["healthz"]) => Route::Health,
+        (&Method::GET, ["admin", "reminders"]) => Route::Reminders,
+        (&Method::DELETE, ["admin", "reminders", id]) => match id.parse() {
+            Ok(id) => Route::CancelReminder(id),
+            Err(_) => Route::NotFound,
+        },
+        (&Method::GET, ["admin", "mocks"]) => Route::Mocks,
+        (&Method::POST, ["admin", "reload"]) => Route::Reload,
+        (&Method::GET, ["admin", "config"]) => Route::Config,
+        _ => Route::NotFound,
+    }
+}
+
+//"Bearer <token>", compared without stopping at the first difference so the time taken doesn't give the token away
+pub fn authorized(header: Option<&str>, token: &str) -> bool {
+    let given = match header.and_then(|header| header.strip_prefix("Bearer ")) {
+        Some(given) => given.as_bytes(),
+        None => return false,
+    };
+    given.len() == token.len() && given.iter().zip(token.as_bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+//the optional http server (--http), it works off the same TypeMap and services the commands use
+pub async fn serve(addr: SocketAddr, data: Arc<RwLock<TypeMap>>, http: Arc<Http>) {
+    let make_service = make_service_fn(move |_| {
+        let (data, http) = (data.clone(), http.clone());
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| respond(request, data.clone(), http.clone())))
+        }
+    });
+    let server = match Server::try_bind(&addr) {
+        Ok(server) => server,
+        Err(e) => {
+            error!(%addr, error = %crate::logging::chain(&e), "could not start the http server");
+            return
+        }
+    };
+    info!(%addr, "http server listening");
+    if let Err(e) = server.serve(make_service).await {
+        error!(error = %crate::logging::chain(&e), "http server stopped");
+    }
+}
+
+async fn respond(request: Request<Body>, data: Arc<RwLock<TypeMap>>, http: Arc<Http>) -> Result<Response<Body>, Infallible> {
+    let route = route(request.method(), request.uri().path());
+    if route.is_admin() {
+        let token = data.read().await.get::<crate::BotSettings>().and_then(|settings| settings.admin_token.clone());
+        let header = request.headers().get(AUTHORIZATION).and_then(|header| header.to_str().ok());
+        match token {
+            None => return Ok(json_response(StatusCode::NOT_FOUND, json!({"error": "the admin api is off, set SHAWTY_ADMIN_TOKEN or admin_token"}))),
+            Some(token) if !authorized(header, &token) => {
+                warn!(path = request.uri().path(), "admin api request without the right token");
+                return Ok(json_response(StatusCode::UNAUTHORIZED, json!({"error": "wrong or missing token"})))
+            },
+            Some(_) => info!(path = request.uri().path(), method = %request.method(), "admin api request"),
+        }
+    }
+    let response = match route {
+        Route::Metrics => {
+            let data = data.read().await;
+            let pending = data.get::<crate::ReminderList>().map(|list| list.len()).unwrap_or_default();
+            match data.get::<crate::BotMetrics>() {
+                Some(metrics) => text(StatusCode::OK, metrics.render(pending)),
+                None => text(StatusCode::SERVICE_UNAVAILABLE, "not started yet".to_string()),
+            }
+        },
+        Route::Health => {
+            let health = health(&data).await;
+            let status = if health.values().all(|ok| ok == true) {StatusCode::OK} else {StatusCode::SERVICE_UNAVAILABLE};
+            json_response(status, Value::Object(health))
+        },
+        Route::Reminders => {
+            let data = data.read().await;
+            let reminders: Vec<Value> = data.get::<crate::ReminderList>().into_iter().flatten().map(|reminder| json!({
+                "id": reminder.verification_message.id.as_u64(),
+                "time": reminder.date_time,
+                "guild": reminder.message.guild_id.map(|id| *id.as_u64()),
+                "channel": reminder.message.channel_id.as_u64(),
+                "requester": reminder.requester,
+                "message": reminder.message.content,
+            })).collect();
+            json_response(StatusCode::OK, json!(reminders))
+        },
+        Route::CancelReminder(id) => match crate::cancel_reminder(&data, id).await {
+            Ok(true) => json_response(StatusCode::OK, json!({"cancelled": id})),
+            Ok(false) => json_response(StatusCode::NOT_FOUND, json!({"error": format!("there's no reminder {}", id)})),
+            Err(e) => json_response(StatusCode::INTERNAL_SERVER_ERROR, json!({"error": e})),
+        },
+        Route::Mocks => {
+            let data = data.read().await;
+            let mocks: serde_json::Map<String, Value> = data.get::<crate::MockTracker>().into_iter().flatten()
+                .map(|(user, left)| (user.to_string(), json!(left)))
+                .collect();
+            json_response(StatusCode::OK, Value::Object(mocks))
+        },
+        Route::Reload => {
+            let errors = crate::reload_all(&http, &data).await;
+            let status = if errors.is_empty() {StatusCode::OK} else {StatusCode::UNPROCESSABLE_ENTITY};
+            json_response(status, json!({"errors": errors}))
+        },
+        Route::Config => {
+            let data = data.read().await;
+            json_response(StatusCode::OK, json!({
+                "settings": data.get::<crate::BotSettings>(),
+                "owners": data.get::<crate::BotOwners>(),
+                "guilds": data.get::<crate::GuildConfigs>(),
+            }))
+        },
+        Route::NotFound => json_response(StatusCode::NOT_FOUND, json!({"error": "not found"})),
+    };
+    Ok(response)
+}
+
+//each part is true when it's working
+async fn health(data: &RwLock<TypeMap>) -> serde_json::Map<String, Value> {
+    let (shard_manager, heartbeat, data_dir) = {
+        let data = data.read().await;
+        (
+            data.get::<crate::ShardManagerContainer>().cloned(),
+            data.get::<crate::SchedulerHeartbeat>().copied(),
+            data.get::<crate::BotSettings>().map(|settings| settings.data_dir.clone()),
+        )
+    };
+    let gateway = match shard_manager {
+        Some(shard_manager) => {
+            let shard_manager = shard_manager.lock().await;
+            let runners = shard_manager.runners.lock().await;
+            !runners.is_empty() && runners.values().all(|runner| runner.stage == ConnectionStage::Connected)
+        },
+        None => false,
+    };
+    let scheduler = heartbeat.is_some_and(|heartbeat| heartbeat.elapsed() < SCHEDULER_TIMEOUT);
+    let storage = data_dir
+        .and_then(|dir| std::fs::metadata(dir).ok())
+        .is_some_and(|metadata| metadata.is_dir() && !metadata.permissions().readonly());
+    let mut health = serde_json::Map::new();
+    health.insert("gateway".to_string(), json!(gateway));
+    health.insert("scheduler".to_string(), json!(scheduler));
+    health.insert("storage".to_string(), json!(storage));
+    health
+}
+
+fn text(status: StatusCode, body: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("text/plain; version=0.0.4"));
+    response
+}
+
+fn json_response(status: StatusCode, body: Value) -> Response<Body> {
+    let mut response = Response::new(Body::from(body.to_string()));
+    *response.status_mut() = status;
+    response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}